@@ -1,2 +1,4 @@
+pub mod layout;
 pub mod load;
 pub mod store;
+pub mod var;
@@ -0,0 +1,124 @@
+//! Structure of the values that a harness loads from its input cells and stores
+//! into its output cells.
+//!
+//! The IO of the circuit is a flat list of cells. The layout maps positions in
+//! that list back to the types of the harness, for example to show which byte
+//! of a hash two solutions of the circuit disagree on.
+
+use std::any::type_name;
+
+use mdnt_support::cells::CellReprSize;
+use serde::{Deserialize, Serialize};
+
+/// Structure of a type that occupies cells.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellLayout {
+    /// A type that is not decomposed any further.
+    Cells { ty: String, size: usize },
+    /// An array of `len` elements.
+    Array { elem: Box<CellLayout>, len: usize },
+    /// A tuple whose elements are laid out in order.
+    Tuple(Vec<CellLayout>),
+}
+
+impl CellLayout {
+    /// Layout of a type that is not decomposed any further.
+    pub fn cells<T: CellReprSize>() -> Self {
+        Self::Cells {
+            ty: short_type_name(type_name::<T>()),
+            size: T::SIZE,
+        }
+    }
+
+    pub fn array(elem: Self, len: usize) -> Self {
+        Self::Array {
+            elem: Box::new(elem),
+            len,
+        }
+    }
+
+    pub fn tuple(elems: Vec<Self>) -> Self {
+        Self::Tuple(elems)
+    }
+
+    /// Number of cells the type occupies.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Cells { size, .. } => *size,
+            Self::Array { elem, len } => elem.size() * len,
+            Self::Tuple(elems) => elems.iter().map(Self::size).sum(),
+        }
+    }
+}
+
+/// Removes the module paths from a type name.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut ident = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+        } else if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            ident.clear();
+        } else {
+            short.push_str(&ident);
+            ident.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(&ident);
+    short
+}
+
+/// Layout of the inputs and outputs of a harness.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IoLayout {
+    inputs: CellLayout,
+    outputs: CellLayout,
+}
+
+impl IoLayout {
+    pub fn new(inputs: CellLayout, outputs: CellLayout) -> Self {
+        Self { inputs, outputs }
+    }
+
+    pub fn inputs(&self) -> &CellLayout {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &CellLayout {
+        &self.outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pair;
+
+    impl CellReprSize for Pair {
+        const SIZE: usize = 2;
+    }
+
+    #[test]
+    fn layout_size_and_names() {
+        let layout = CellLayout::tuple(vec![
+            CellLayout::array(CellLayout::cells::<Pair>(), 3),
+            CellLayout::cells::<u8>(),
+        ]);
+        assert_eq!(layout.size(), 6);
+        assert_eq!(
+            short_type_name("midnight_circuits::types::AssignedByte<blstrs::scalar::Scalar>"),
+            "AssignedByte<Scalar>"
+        );
+        assert_eq!(short_type_name("(a::B, [c::D<e::F>; 2])"), "(B, [D<F>; 2])");
+        assert!(matches!(
+            CellLayout::cells::<Pair>(),
+            CellLayout::Cells { ty, size: 2 } if ty == "Pair"
+        ));
+    }
+}
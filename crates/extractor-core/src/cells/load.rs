@@ -28,7 +28,10 @@ use num_bigint::BigUint;
 pub use crate::fields::{
     Blstrs, Jubjub, JubjubFr, JubjubSubgroup, MidnightFp, Secp256k1, Secp256k1Fp, Secp256k1Fq, G1,
 };
-use crate::fields::{Loaded, Zero};
+use crate::{
+    fields::{Loaded, Zero},
+    harness::metadata::{ConstantKind, ReadsConstants},
+};
 pub use mdnt_support::cells::load::LoadFromCells;
 
 pub struct LoadedJubjub(Jubjub);
@@ -54,12 +57,12 @@ impl From<PointNotInCurve> for Error {
     }
 }
 
-impl<Chip, L> LoadFromCells<Blstrs, Chip, ExtractionSupport, L> for LoadedJubjub {
+impl<F: Field, Chip, L> LoadFromCells<F, Chip, ExtractionSupport, L> for LoadedJubjub {
     fn load(
-        ctx: &mut ICtx<Blstrs, ExtractionSupport>,
+        ctx: &mut ICtx<F, ExtractionSupport>,
         chip: &Chip,
-        layouter: &mut impl LayoutAdaptor<Blstrs, ExtractionSupport, Adaptee = L>,
-        injected_ir: &mut InjectedIR<RegionIndex, Expression<Blstrs>>,
+        layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+        injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
     ) -> Result<Self, Error> {
         let x = Loaded::<Blstrs>::load(ctx, chip, layouter, injected_ir)?.0;
         let y = Loaded::<Blstrs>::load(ctx, chip, layouter, injected_ir)?.0;
@@ -68,6 +71,10 @@ impl<Chip, L> LoadFromCells<Blstrs, Chip, ExtractionSupport, L> for LoadedJubjub
     }
 }
 
+impl ReadsConstants for LoadedJubjub {
+    const CONSTANTS: &'static [ConstantKind] = &[ConstantKind::Field, ConstantKind::Field];
+}
+
 pub struct LoadedJubjubSubgroup(JubjubSubgroup);
 
 impl From<LoadedJubjubSubgroup> for JubjubSubgroup {
@@ -80,13 +87,13 @@ impl CellReprSize for LoadedJubjubSubgroup {
     const SIZE: usize = <Zero<JubjubSubgroup> as CellReprSize>::SIZE;
 }
 
-impl<Chip, L> LoadFromCells<Blstrs, Chip, ExtractionSupport, L> for LoadedJubjubSubgroup {
+impl<F: Field, Chip, L> LoadFromCells<F, Chip, ExtractionSupport, L> for LoadedJubjubSubgroup {
     fn load(
-        ctx: &mut ICtx<Blstrs, ExtractionSupport>,
+        ctx: &mut ICtx<F, ExtractionSupport>,
         chip: &Chip,
-        layouter: &mut impl LayoutAdaptor<Blstrs, ExtractionSupport, Adaptee = L>,
+        layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
 
-        injected_ir: &mut InjectedIR<RegionIndex, Expression<Blstrs>>,
+        injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
     ) -> Result<Self, Error> {
         LoadedJubjub::load(ctx, chip, layouter, injected_ir)
             .map(|c| c.0.into_subgroup())
@@ -94,6 +101,10 @@ impl<Chip, L> LoadFromCells<Blstrs, Chip, ExtractionSupport, L> for LoadedJubjub
     }
 }
 
+impl ReadsConstants for LoadedJubjubSubgroup {
+    const CONSTANTS: &'static [ConstantKind] = LoadedJubjub::CONSTANTS;
+}
+
 pub struct LoadedG1(G1);
 
 impl From<LoadedG1> for G1 {
@@ -127,12 +138,12 @@ impl From<Point3NotInCurve> for Error {
     }
 }
 
-impl<Chip, L> LoadFromCells<Blstrs, Chip, ExtractionSupport, L> for LoadedG1 {
+impl<F: Field, Chip, L> LoadFromCells<F, Chip, ExtractionSupport, L> for LoadedG1 {
     fn load(
-        ctx: &mut ICtx<Blstrs, ExtractionSupport>,
+        ctx: &mut ICtx<F, ExtractionSupport>,
         chip: &Chip,
-        layouter: &mut impl LayoutAdaptor<Blstrs, ExtractionSupport, Adaptee = L>,
-        injected_ir: &mut InjectedIR<RegionIndex, Expression<Blstrs>>,
+        layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+        injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
     ) -> Result<Self, Error> {
         let x = Loaded::<MidnightFp>::load(ctx, chip, layouter, injected_ir)?.0;
         let y = Loaded::<MidnightFp>::load(ctx, chip, layouter, injected_ir)?.0;
@@ -145,6 +156,14 @@ impl<Chip, L> LoadFromCells<Blstrs, Chip, ExtractionSupport, L> for LoadedG1 {
     }
 }
 
+impl ReadsConstants for LoadedG1 {
+    const CONSTANTS: &'static [ConstantKind] = &[
+        ConstantKind::Field,
+        ConstantKind::Field,
+        ConstantKind::Field,
+    ];
+}
+
 pub struct LoadedSecp256k1(Secp256k1);
 
 impl From<LoadedSecp256k1> for Secp256k1 {
@@ -178,12 +197,12 @@ impl From<Point3NotInCurveSecp256k1> for Error {
     }
 }
 
-impl<Chip, L> LoadFromCells<Blstrs, Chip, ExtractionSupport, L> for LoadedSecp256k1 {
+impl<F: Field, Chip, L> LoadFromCells<F, Chip, ExtractionSupport, L> for LoadedSecp256k1 {
     fn load(
-        ctx: &mut ICtx<Blstrs, ExtractionSupport>,
+        ctx: &mut ICtx<F, ExtractionSupport>,
         chip: &Chip,
-        layouter: &mut impl LayoutAdaptor<Blstrs, ExtractionSupport, Adaptee = L>,
-        injected_ir: &mut InjectedIR<RegionIndex, Expression<Blstrs>>,
+        layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+        injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
     ) -> Result<Self, Error> {
         let x = Loaded::<Secp256k1Fp>::load(ctx, chip, layouter, injected_ir)?.0;
         let y = Loaded::<Secp256k1Fp>::load(ctx, chip, layouter, injected_ir)?.0;
@@ -196,6 +215,14 @@ impl<Chip, L> LoadFromCells<Blstrs, Chip, ExtractionSupport, L> for LoadedSecp25
     }
 }
 
+impl ReadsConstants for LoadedSecp256k1 {
+    const CONSTANTS: &'static [ConstantKind] = &[
+        ConstantKind::Field,
+        ConstantKind::Field,
+        ConstantKind::Field,
+    ];
+}
+
 mod sealed {
     pub trait ZeroTraitSealed {}
 }
@@ -230,6 +257,12 @@ where
     }
 }
 
+/// Zeros are skipped, so the schema holds when the given constants are not
+/// zero.
+impl<T: ZeroTrait + ReadsConstants> ReadsConstants for NonZero<T> {
+    const CONSTANTS: &'static [ConstantKind] = T::CONSTANTS;
+}
+
 /// Requires that the given constant is >1.
 pub struct Gt1<S>(pub S);
 
@@ -251,12 +284,12 @@ impl<S: CellReprSize> CellReprSize for Gt1<S> {
 
 macro_rules! gt1_impl {
     ($f:ty) => {
-        impl<C, L> LoadFromCells<Blstrs, C, ExtractionSupport, L> for Gt1<Loaded<$f>> {
+        impl<F: Field, C, L> LoadFromCells<F, C, ExtractionSupport, L> for Gt1<Loaded<$f>> {
             fn load(
-                ctx: &mut ICtx<Blstrs, ExtractionSupport>,
+                ctx: &mut ICtx<F, ExtractionSupport>,
                 chip: &C,
-                layouter: &mut impl LayoutAdaptor<Blstrs, ExtractionSupport, Adaptee = L>,
-                injected_ir: &mut InjectedIR<RegionIndex, Expression<Blstrs>>,
+                layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+                injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
             ) -> Result<Self, Error> {
                 loop {
                     let s = Loaded::<$f>::load(ctx, chip, layouter, injected_ir)?;
@@ -269,6 +302,10 @@ macro_rules! gt1_impl {
     };
 }
 
+impl<S: ReadsConstants> ReadsConstants for Gt1<S> {
+    const CONSTANTS: &'static [ConstantKind] = S::CONSTANTS;
+}
+
 gt1_impl!(JubjubFr);
 gt1_impl!(Blstrs);
 gt1_impl!(Secp256k1Fq);
@@ -0,0 +1,383 @@
+//! Inputs and outputs whose length is chosen when the harness is extracted.
+//!
+//! The number of cells of the inputs and outputs of a harness is fixed when
+//! the circuit is configured, so these types reserve cells for the largest
+//! value they can hold. The actual length is read from the constants of the
+//! harness and the cells that are not used are tied to zero. This way a single
+//! harness covers every length up to the maximum.
+
+use ff::PrimeField;
+use haloumi_ir::stmt::IRStmt;
+use mdnt_support::{
+    cell_to_expr,
+    cells::{
+        ctx::{ICtx, LayoutAdaptor, OCtx},
+        load::LoadFromCells,
+        store::StoreIntoCells,
+        CellReprSize,
+    },
+    circuit::injected::InjectedIR,
+    expect_elements,
+};
+use midnight_circuits::types::AssignedNative;
+use midnight_proofs::{
+    circuit::RegionIndex,
+    plonk::{Error, Expression},
+    ExtractionSupport,
+};
+
+use crate::harness::metadata::{ConstantKind, ReadsConstants};
+
+/// A vector of at most `MAX` elements prefixed by its length.
+///
+/// When loaded, the length is read from the next constant and the prefix is
+/// constrained to be equal to it. The cells of the elements past the length are
+/// constrained to be zero.
+pub struct BoundedVec<T, const MAX: usize>(pub Vec<T>);
+
+impl<T, const MAX: usize> From<BoundedVec<T, MAX>> for Vec<T> {
+    fn from(value: BoundedVec<T, MAX>) -> Self {
+        value.0
+    }
+}
+
+impl<T, const MAX: usize> From<Vec<T>> for BoundedVec<T, MAX> {
+    fn from(value: Vec<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: CellReprSize, const MAX: usize> CellReprSize for BoundedVec<T, MAX> {
+    const SIZE: usize = 1 + MAX * T::SIZE;
+}
+
+impl<F, C, L, T, const MAX: usize> LoadFromCells<F, C, ExtractionSupport, L> for BoundedVec<T, MAX>
+where
+    F: PrimeField,
+    T: LoadFromCells<F, C, ExtractionSupport, L>,
+{
+    fn load(
+        ctx: &mut ICtx<F, ExtractionSupport>,
+        chip: &C,
+        layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+        injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
+    ) -> Result<Self, Error> {
+        let len: usize = ctx.primitive_constant()?;
+        expect_elements!((MAX >= len), "Vector of at most {MAX} elements: ");
+        load_constant(F::from(len as u64), ctx, chip, layouter, injected_ir)?;
+        let elements = T::load_many(len, ctx, chip, layouter, injected_ir)?;
+        load_zeros((MAX - len) * T::SIZE, ctx, chip, layouter, injected_ir)?;
+        Ok(Self(elements))
+    }
+}
+
+impl<T, const MAX: usize> ReadsConstants for BoundedVec<T, MAX> {
+    const CONSTANTS: &'static [ConstantKind] = &[ConstantKind::Length];
+}
+
+impl<F, C, L, T, const MAX: usize> StoreIntoCells<F, C, ExtractionSupport, L> for BoundedVec<T, MAX>
+where
+    F: PrimeField,
+    T: StoreIntoCells<F, C, ExtractionSupport, L>,
+{
+    fn store(
+        self,
+        ctx: &mut OCtx<F, ExtractionSupport>,
+        chip: &C,
+        layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+        injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
+    ) -> Result<(), Error> {
+        let len = self.0.len();
+        expect_elements!((MAX >= len), "Vector of at most {MAX} elements: ");
+        ctx.set_next_to_constant(F::from(len as u64), layouter)?;
+        for element in self.0 {
+            element.store(ctx, chip, layouter, injected_ir)?;
+        }
+        store_zeros((MAX - len) * T::SIZE, ctx, layouter)
+    }
+}
+
+/// An optional value prefixed by a flag that is 1 if the value is present and
+/// 0 otherwise.
+///
+/// When loaded, whether the value is present is read from the next constant
+/// and the flag is constrained accordingly. The cells of a missing value are
+/// constrained to be zero.
+pub struct Flagged<T>(pub Option<T>);
+
+impl<T> From<Flagged<T>> for Option<T> {
+    fn from(value: Flagged<T>) -> Self {
+        value.0
+    }
+}
+
+impl<T> From<Option<T>> for Flagged<T> {
+    fn from(value: Option<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: CellReprSize> CellReprSize for Flagged<T> {
+    const SIZE: usize = 1 + T::SIZE;
+}
+
+impl<F, C, L, T> LoadFromCells<F, C, ExtractionSupport, L> for Flagged<T>
+where
+    F: PrimeField,
+    T: LoadFromCells<F, C, ExtractionSupport, L>,
+{
+    fn load(
+        ctx: &mut ICtx<F, ExtractionSupport>,
+        chip: &C,
+        layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+        injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
+    ) -> Result<Self, Error> {
+        let is_some: bool = ctx.primitive_constant()?;
+        load_constant(F::from(is_some as u64), ctx, chip, layouter, injected_ir)?;
+        if is_some {
+            T::load(ctx, chip, layouter, injected_ir).map(Some).map(Self)
+        } else {
+            load_zeros(T::SIZE, ctx, chip, layouter, injected_ir)?;
+            Ok(Self(None))
+        }
+    }
+}
+
+impl<T> ReadsConstants for Flagged<T> {
+    const CONSTANTS: &'static [ConstantKind] = &[ConstantKind::Flag];
+}
+
+impl<F, C, L, T> StoreIntoCells<F, C, ExtractionSupport, L> for Flagged<T>
+where
+    F: PrimeField,
+    T: StoreIntoCells<F, C, ExtractionSupport, L>,
+{
+    fn store(
+        self,
+        ctx: &mut OCtx<F, ExtractionSupport>,
+        chip: &C,
+        layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+        injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
+    ) -> Result<(), Error> {
+        match self.0 {
+            Some(value) => {
+                ctx.set_next_to_constant(F::ONE, layouter)?;
+                value.store(ctx, chip, layouter, injected_ir)
+            }
+            None => store_zeros(Self::SIZE, ctx, layouter),
+        }
+    }
+}
+
+/// Loads the next cell and constrains it to be equal to the given constant.
+fn load_constant<F: PrimeField, C, L>(
+    constant: F,
+    ctx: &mut ICtx<F, ExtractionSupport>,
+    chip: &C,
+    layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+    injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
+) -> Result<(), Error> {
+    let cell = AssignedNative::load(ctx, chip, layouter, injected_ir)?;
+    let lhs = cell_to_expr!(&cell, F)?;
+    let rhs = Expression::Constant(constant);
+    injected_ir.entry(cell.cell().region_index).or_default().push(IRStmt::eq(
+        (cell.cell().row_offset, lhs),
+        (cell.cell().row_offset, rhs),
+    ));
+    Ok(())
+}
+
+/// Loads the next `n` cells and constrains them to be zero.
+fn load_zeros<F: PrimeField, C, L>(
+    n: usize,
+    ctx: &mut ICtx<F, ExtractionSupport>,
+    chip: &C,
+    layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+    injected_ir: &mut InjectedIR<RegionIndex, Expression<F>>,
+) -> Result<(), Error> {
+    (0..n).try_for_each(|_| load_constant(F::ZERO, ctx, chip, layouter, injected_ir))
+}
+
+/// Sets the next `n` outputs to zero.
+fn store_zeros<F: PrimeField, L>(
+    n: usize,
+    ctx: &mut OCtx<F, ExtractionSupport>,
+    layouter: &mut impl LayoutAdaptor<F, ExtractionSupport, Adaptee = L>,
+) -> Result<(), Error> {
+    (0..n).try_for_each(|_| ctx.set_next_to_zero(layouter))
+}
+
+#[cfg(test)]
+mod tests {
+    use mdnt_support::cells::ctx::OutputDescr;
+    use midnight_proofs::{
+        circuit::{AssignedCell, Cell, Region},
+        plonk::{Advice, Column, ConstraintSystem, Instance},
+        utils::rational::Rational,
+    };
+
+    use super::*;
+    use crate::{cells::store::FreshVar, fields::Blstrs};
+
+    /// Layout adaptor that records the constants the outputs are set to. It
+    /// has no input cells to assign.
+    #[derive(Default)]
+    struct Recorder {
+        constants: Vec<Blstrs>,
+        adaptee: (),
+    }
+
+    impl LayoutAdaptor<Blstrs, ExtractionSupport> for Recorder {
+        type Adaptee = ();
+
+        fn adaptee_ref(&self) -> &() {
+            &self.adaptee
+        }
+
+        fn adaptee_ref_mut(&mut self) -> &mut () {
+            &mut self.adaptee
+        }
+
+        fn constrain_instance(
+            &mut self,
+            _cell: Cell,
+            _instance_col: Column<Instance>,
+            _instance_row: usize,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn constrain_advice_constant(
+            &mut self,
+            advice_col: Column<Advice>,
+            advice_row: usize,
+            constant: Blstrs,
+        ) -> Result<Cell, Error> {
+            self.constants.push(constant);
+            Ok(Cell {
+                region_index: self.constants.len().into(),
+                row_offset: advice_row,
+                column: advice_col.into(),
+            })
+        }
+
+        fn assign_advice_from_instance<V>(
+            &mut self,
+            _advice_col: Column<Advice>,
+            _advice_row: usize,
+            _instance_col: Column<Instance>,
+            _instance_row: usize,
+        ) -> Result<AssignedCell<V, Blstrs>, Error>
+        where
+            V: Clone,
+            Rational<Blstrs>: for<'v> From<&'v V>,
+        {
+            unimplemented!("no input cells")
+        }
+
+        fn copy_advice<V>(
+            &mut self,
+            _ac: &AssignedCell<V, Blstrs>,
+            _region: &mut Region<'_, Blstrs>,
+            _advice_col: Column<Advice>,
+            _advice_row: usize,
+        ) -> Result<AssignedCell<V, Blstrs>, Error>
+        where
+            V: Clone,
+            Rational<Blstrs>: for<'v> From<&'v V>,
+        {
+            unimplemented!("no input cells")
+        }
+
+        fn region<A, AR, N, NR>(&mut self, _name: N, _assignment: A) -> Result<AR, Error>
+        where
+            A: FnMut(Region<'_, Blstrs>) -> Result<AR, Error>,
+            N: Fn() -> NR,
+            NR: Into<String>,
+        {
+            unimplemented!("no regions")
+        }
+    }
+
+    /// Stores the value and returns the constants its outputs were set to.
+    fn store<T>(value: T) -> Result<Vec<Blstrs>, Error>
+    where
+        T: StoreIntoCells<Blstrs, (), ExtractionSupport, ()> + CellReprSize,
+    {
+        let mut cs = ConstraintSystem::<Blstrs>::default();
+        let (instance, helper) = (cs.instance_column(), cs.advice_column());
+        let outputs = (0..T::SIZE).map(|row| OutputDescr::new((instance, row).into(), helper));
+        let mut ctx = OCtx::new(outputs);
+        let mut recorder = Recorder::default();
+        value.store(&mut ctx, &(), &mut recorder, &mut InjectedIR::default())?;
+        assert!(ctx.next().is_err(), "Not every output was stored");
+        Ok(recorder.constants)
+    }
+
+    /// Loads a value with the given constants and no input cells.
+    fn load<T>(constants: &[&str]) -> Result<T, Error>
+    where
+        T: LoadFromCells<Blstrs, (), ExtractionSupport, ()>,
+    {
+        let constants: Vec<_> = constants.iter().map(ToString::to_string).collect();
+        let mut ctx = ICtx::new(std::iter::empty(), &constants);
+        T::load(
+            &mut ctx,
+            &(),
+            &mut Recorder::default(),
+            &mut InjectedIR::default(),
+        )
+    }
+
+    fn felts(values: &[u64]) -> Vec<Blstrs> {
+        values.iter().copied().map(Blstrs::from).collect()
+    }
+
+    #[test]
+    fn size_includes_prefix() {
+        assert_eq!(<BoundedVec<FreshVar, 4> as CellReprSize>::SIZE, 5);
+        assert_eq!(<BoundedVec<[FreshVar; 2], 3> as CellReprSize>::SIZE, 7);
+        assert_eq!(
+            <BoundedVec<AssignedNative<Blstrs>, 0> as CellReprSize>::SIZE,
+            1
+        );
+        assert_eq!(<Flagged<[FreshVar; 3]> as CellReprSize>::SIZE, 4);
+    }
+
+    #[test]
+    fn stored_vectors_are_prefixed_by_their_length_and_padded_with_zeros() {
+        let stored = store(BoundedVec::<FreshVar, 4>(vec![FreshVar, FreshVar])).unwrap();
+        assert_eq!(stored, felts(&[2, 0, 0, 0, 0]));
+        let stored = store(BoundedVec::<[FreshVar; 2], 2>(vec![])).unwrap();
+        assert_eq!(stored, felts(&[0, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn lengths_over_the_maximum_are_rejected() {
+        let long = BoundedVec::<FreshVar, 2>(vec![FreshVar, FreshVar, FreshVar]);
+        let err = store(long).unwrap_err();
+        assert!(
+            err.to_string().contains("Vector of at most 2 elements"),
+            "{err}"
+        );
+        let Err(err) = load::<BoundedVec<AssignedNative<Blstrs>, 2>>(&["3"]) else {
+            panic!("Loaded a vector longer than its maximum");
+        };
+        assert!(
+            err.to_string().contains("Vector of at most 2 elements"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn missing_values_store_a_zero_flag_and_a_zeroed_payload() {
+        assert_eq!(
+            store(Flagged::<[FreshVar; 3]>(None)).unwrap(),
+            felts(&[0, 0, 0, 0])
+        );
+        assert_eq!(
+            store(Flagged(Some([FreshVar, FreshVar, FreshVar]))).unwrap(),
+            felts(&[1, 0, 0, 0])
+        );
+    }
+}
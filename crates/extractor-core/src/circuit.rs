@@ -1,6 +1,10 @@
 use crate::{
-    circuit::layouter::{AdaptsLayouter, ExtractionLayouter},
+    circuit::{
+        layouter::{AdaptsLayouter, ExtractionLayouter},
+        planner::FloorPlanning,
+    },
     harness::Ctx,
+    shuffles::ShuffleArgument,
 };
 use anyhow::{Context, Result};
 use configuration::Config;
@@ -32,6 +36,8 @@ use std::{cell::RefCell, marker::PhantomData};
 //pub mod assignment;
 pub mod configuration;
 pub mod layouter;
+pub mod mock;
+pub mod planner;
 pub mod traits;
 
 pub use traits::*;
@@ -48,7 +54,9 @@ pub struct CircuitImpl<'a, F, C, M = Function> {
     abstract_circuit: C,
     constants: &'a [String],
     allow_injected_ir_for_outputs: bool,
+    floor_planner: FloorPlanning,
     injected_ir: RefCell<InjectedIR<RegionIndex, Expression<F>>>,
+    shuffles: RefCell<Vec<ShuffleArgument<F>>>,
     _mode: PhantomData<M>,
 }
 
@@ -58,11 +66,19 @@ impl<'a, F, C, M> CircuitImpl<'a, F, C, M> {
             abstract_circuit,
             constants: ctx.constants(),
             allow_injected_ir_for_outputs: ctx.allow_injected_ir_for_outputs(),
+            floor_planner: ctx.floor_planner(),
             injected_ir: Default::default(),
+            shuffles: Default::default(),
             _mode: Default::default(),
         }
     }
 
+    /// Returns the shuffle arguments of the constraint system the circuit was
+    /// synthesized with.
+    pub fn take_shuffles(&self) -> Vec<ShuffleArgument<F>> {
+        self.shuffles.take()
+    }
+
     /// Consumes the circuit wrapper and returns the extra IR added during synthesis.
     pub fn take_injected_ir<'ir>(
         self,
@@ -109,6 +125,48 @@ where
         C::Chip::new_chip(&config.chip.inner, self.abstract_circuit.chip_args())
     }
 
+    /// Runs `synthesize` with the extraction layouter and the floor planner of
+    /// the context. Planners that need the shapes of the regions get a first
+    /// pass that only measures them.
+    ///
+    /// The shuffles of the constraint system are recorded, since haloumi
+    /// doesn't synthesize them.
+    fn synthesize_with_planner(
+        &self,
+        synthesizer: &mut Synthesizer<F>,
+        cs: &ConstraintSystem<F>,
+        synthesize: impl for<'a, 'b> Fn(ExtractionLayouter<'a, 'b, F>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        self.shuffles.replace(
+            cs.shuffles()
+                .iter()
+                .map(|shuffle| {
+                    ShuffleArgument::new(
+                        shuffle.name(),
+                        shuffle.input_expressions().to_vec(),
+                        shuffle.shuffle_expressions().to_vec(),
+                    )
+                })
+                .collect(),
+        );
+        let mut planner = self.floor_planner.planner();
+        if planner.measures() {
+            synthesize(ExtractionLayouter::measurement(
+                synthesizer,
+                planner.as_mut(),
+                cs.constants(),
+            ))?;
+            // The second pass injects the same IR again.
+            self.injected_ir.take();
+            planner.plan(cs.constants())?;
+        }
+        synthesize(ExtractionLayouter::new(
+            synthesizer,
+            planner.as_mut(),
+            cs.constants(),
+        ))
+    }
+
     fn load_chip<L>(
         &self,
         layouter: &mut L,
@@ -262,7 +320,11 @@ where
 }
 
 impl<F, C> CircuitImpl<'_, F, C, Function> {
-    fn synthesize_inner<L>(
+    /// Synthesizes the circuit with the given layouter.
+    ///
+    /// Used by the harnesses to run the circuit through the floor planner of
+    /// [`midnight_proofs`] in addition to the extraction layouter.
+    pub fn synthesize_inner<L>(
         &self,
         config: Config<C>,
         mut layouter: L,
@@ -302,7 +364,11 @@ impl<F, C> CircuitImpl<'_, F, C, Function> {
 }
 
 impl<F, C> CircuitImpl<'_, F, C, FunctionMut> {
-    fn synthesize_inner<L>(
+    /// Synthesizes the circuit with the given layouter.
+    ///
+    /// Used by the harnesses to run the circuit through the floor planner of
+    /// [`midnight_proofs`] in addition to the extraction layouter.
+    pub fn synthesize_inner<L>(
         &self,
         config: Config<C>,
         mut layouter: L,
@@ -347,7 +413,11 @@ impl<F, C> CircuitImpl<'_, F, C, FunctionMut> {
 }
 
 impl<F, C> CircuitImpl<'_, F, C, Procedure> {
-    fn synthesize_inner<L>(
+    /// Synthesizes the circuit with the given layouter.
+    ///
+    /// Used by the harnesses to run the circuit through the floor planner of
+    /// [`midnight_proofs`] in addition to the extraction layouter.
+    pub fn synthesize_inner<L>(
         &self,
         config: Config<C>,
         mut layouter: L,
@@ -436,8 +506,9 @@ where
         synthesizer: &mut Synthesizer<F>,
         cs: &Self::CS,
     ) -> Result<(), Self::Error> {
-        let layouter = ExtractionLayouter::new(synthesizer, cs.constants());
-        circuit.synthesize_inner(config, layouter)
+        circuit.synthesize_with_planner(synthesizer, cs, |layouter| {
+            circuit.synthesize_inner(config.clone(), layouter)
+        })
     }
 }
 
@@ -492,8 +563,9 @@ where
         synthesizer: &mut Synthesizer<F>,
         cs: &Self::CS,
     ) -> Result<(), Self::Error> {
-        let layouter = ExtractionLayouter::new(synthesizer, cs.constants());
-        circuit.synthesize_inner(config, layouter)
+        circuit.synthesize_with_planner(synthesizer, cs, |layouter| {
+            circuit.synthesize_inner(config.clone(), layouter)
+        })
     }
 }
 impl<F, C> CircuitSynthesis<F> for CircuitImpl<'_, F, C, Procedure>
@@ -547,8 +619,9 @@ where
         synthesizer: &mut Synthesizer<F>,
         cs: &Self::CS,
     ) -> Result<(), Self::Error> {
-        let layouter = ExtractionLayouter::new(synthesizer, cs.constants());
-        circuit.synthesize_inner(config, layouter)
+        circuit.synthesize_with_planner(synthesizer, cs, |layouter| {
+            circuit.synthesize_inner(config.clone(), layouter)
+        })
     }
 }
 
@@ -1,4 +1,4 @@
-use std::{cell::RefCell, cmp, collections::HashMap};
+use std::{cell::RefCell, collections::HashMap};
 
 use ff::Field;
 use haloumi::synthesis::synthesizer::Synthesizer;
@@ -7,7 +7,7 @@ use mdnt_support::cells::ctx::LayoutAdaptor;
 use midnight_proofs::{
     circuit::{
         groups::{self, GroupKeyInstance},
-        layouter::{RegionColumn, RegionLayouter, RegionShape},
+        layouter::{RegionLayouter, RegionShape},
         AssignedCell, Cell, Layouter, Region, RegionIndex, RegionStart, Table, TableLayouter,
         Value,
     },
@@ -18,15 +18,19 @@ use midnight_proofs::{
     ExtractionSupport,
 };
 
+use super::planner::Planner;
+
 #[derive(Debug)]
 pub struct ExtractionLayouter<'s, 'c, F: Field> {
     synthesizer: &'s mut Synthesizer<F>,
     constants: &'c [Column<Fixed>],
+    /// Places the regions and the constants.
+    planner: &'s mut dyn Planner,
+    /// Only reports the shapes of the regions to the planner.
+    measuring: bool,
     /// Stores the starting row for each region.
     regions: Vec<RegionStart>,
 
-    /// Stores the first empty row for each column.
-    columns: HashMap<RegionColumn, usize>,
     /// Stores the table fixed columns.
     table_columns: Vec<TableColumn>,
     /// Group depth
@@ -34,16 +38,34 @@ pub struct ExtractionLayouter<'s, 'c, F: Field> {
 }
 
 impl<'s, 'c, F: Field> ExtractionLayouter<'s, 'c, F> {
-    pub fn new(synthesizer: &'s mut Synthesizer<F>, constants: &'c [Column<Fixed>]) -> Self {
+    pub fn new(
+        synthesizer: &'s mut Synthesizer<F>,
+        planner: &'s mut dyn Planner,
+        constants: &'c [Column<Fixed>],
+    ) -> Self {
         Self {
             synthesizer,
             constants,
+            planner,
+            measuring: false,
             regions: Default::default(),
-            columns: Default::default(),
             table_columns: Default::default(),
             group_depth: Default::default(),
         }
     }
+
+    /// Creates a layouter for the measurement pass of the planner. Nothing is
+    /// recorded in the synthesizer.
+    pub fn measurement(
+        synthesizer: &'s mut Synthesizer<F>,
+        planner: &'s mut dyn Planner,
+        constants: &'c [Column<Fixed>],
+    ) -> Self {
+        Self {
+            measuring: true,
+            ..Self::new(synthesizer, planner, constants)
+        }
+    }
 }
 
 impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
@@ -65,24 +87,21 @@ impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
 
         // Get shape of the region.
         let mut shape = RegionShape::new(region_index.into());
-        {
+        let measured = {
             let region: &mut dyn RegionLayouter<F> = &mut shape;
-            assignment(region.into())?;
+            assignment(region.into())?
+        };
+        if self.measuring {
+            // The cells returned by the shape pass are enough for the rest of
+            // the measurement pass.
+            self.regions.push(0.into());
+            self.planner.measure(shape);
+            return Ok(measured);
         }
 
-        // Lay out this region. We implement the simplest approach here: position the
-        // region starting at the earliest row for which none of the columns are in use.
-        let mut region_start = 0;
-        for column in shape.columns() {
-            region_start = cmp::max(region_start, self.columns.get(column).cloned().unwrap_or(0));
-        }
+        let region_start = self.planner.place_region(&shape)?;
         self.regions.push(region_start.into());
 
-        // Update column usage information.
-        for column in shape.columns() {
-            self.columns.insert(*column, region_start + shape.row_count());
-        }
-
         // Assign region cells.
         self.synthesizer
             //.enter_region(name, Some(region_index.into()), Some(region_start.into()));
@@ -95,30 +114,16 @@ impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
         let constants_to_assign = region.constants;
         self.synthesizer.exit_region();
 
-        // Assign constants. For the simple floor planner, we assign constants in order
-        // in the first `constants` column.
-        if self.constants.is_empty() {
-            if !constants_to_assign.is_empty() {
-                return Err(Error::NotEnoughColumnsForConstants);
-            }
-        } else {
-            let constants_column = self.constants[0];
-            let next_constant_row =
-                self.columns.entry(Column::<Any>::from(constants_column).into()).or_default();
-            for (constant, advice) in constants_to_assign {
-                self.synthesizer.on_fixed_assigned(
-                    constants_column,
-                    *next_constant_row,
-                    constant.evaluate(),
-                );
-                self.synthesizer.copy(
-                    constants_column,
-                    *next_constant_row,
-                    advice.column,
-                    *self.regions[*advice.region_index] + advice.row_offset,
-                );
-                *next_constant_row += 1;
-            }
+        // Assign constants where the planner puts them.
+        for (constant, advice) in constants_to_assign {
+            let (constants_column, row) = self.planner.place_constant(self.constants)?;
+            self.synthesizer.on_fixed_assigned(constants_column, row, constant.evaluate());
+            self.synthesizer.copy(
+                constants_column,
+                row,
+                advice.column,
+                *self.regions[*advice.region_index] + advice.row_offset,
+            );
         }
 
         Ok(result)
@@ -130,6 +135,9 @@ impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
         N: Fn() -> NR,
         NR: Into<String>,
     {
+        if self.measuring {
+            return Ok(());
+        }
         self.synthesizer.enter_region(name().into(), None, None);
         let mut table = ExtractionTableLayouter::new(self.synthesizer, &self.table_columns);
         {
@@ -171,6 +179,9 @@ impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
         instance: Column<Instance>,
         row: usize,
     ) -> Result<(), Error> {
+        if self.measuring {
+            return Ok(());
+        }
         self.synthesizer.copy(
             cell.column,
             *self.regions[*cell.region_index] + cell.row_offset,
@@ -180,6 +191,9 @@ impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
         Ok(())
     }
 
+    /// Challenges have no value during extraction. The gates that query them
+    /// get a `Slot::Challenge` with the index and phase of the challenge, which
+    /// the backends treat as an input of the module.
     fn get_challenge(&self, _challenge: Challenge) -> Value<F> {
         Value::unknown()
     }
@@ -193,11 +207,15 @@ impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
         NR: Into<String>,
         N: FnOnce() -> NR,
     {
-        self.synthesizer.push_namespace(name_fn().into());
+        if !self.measuring {
+            self.synthesizer.push_namespace(name_fn().into());
+        }
     }
 
     fn pop_namespace(&mut self, gadget_name: Option<String>) {
-        self.synthesizer.pop_namespace(gadget_name);
+        if !self.measuring {
+            self.synthesizer.pop_namespace(gadget_name);
+        }
     }
 
     fn push_group<N, NR, K>(&mut self, name: N, key: K)
@@ -206,6 +224,9 @@ impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
         N: FnOnce() -> NR,
         K: groups::GroupKey,
     {
+        if self.measuring {
+            return;
+        }
         self.group_depth += 1;
         let name: String = name().into();
         log::debug!("{}> Pushing group '{name}'", "-".repeat(self.group_depth));
@@ -214,6 +235,9 @@ impl<F: Field> Layouter<F> for ExtractionLayouter<'_, '_, F> {
     }
 
     fn pop_group(&mut self, meta: groups::RegionsGroup) {
+        if self.measuring {
+            return;
+        }
         log::debug!("{}> Popping group", "-".repeat(self.group_depth));
         log::debug!(
             "{}>   Inputs:  {:?}",
@@ -640,7 +664,7 @@ fn compute_table_lengths<F: std::fmt::Debug>(
         .map(|col_len| col_len.1)
 }
 
-fn steal<T>(value: Value<T>) -> Option<T> {
+pub(crate) fn steal<T>(value: Value<T>) -> Option<T> {
     let data = RefCell::new(None);
     value.map(|t| data.replace(Some(t)));
     data.replace(None)
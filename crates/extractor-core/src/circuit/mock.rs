@@ -0,0 +1,662 @@
+//! Synthesis of the harness circuits with concrete values.
+//!
+//! The circuit is laid out by the floor planner of [`midnight_proofs`] that the
+//! extraction [planner](super::planner) mirrors instead of the
+//! [`ExtractionLayouter`](super::layouter::ExtractionLayouter), so checking the
+//! recorded assignment against the IR catches extraction bugs that place cells
+//! in rows the real layout doesn't use or that rewrite gates unsoundly.
+//!
+//! Each sample assigns some values to the input cells and runs the circuit. A
+//! sample is only checked against the IR if [`MockProver`] accepts it, since a
+//! witness that violates the circuit says nothing about the IR.
+//!
+//! Circuits with challenges are synthesized with the values [`MockProver`]
+//! draws for them, so the advice of later phases, the instances given to the
+//! prover and the IR all agree on them. The values are learned by running the
+//! first sample through the prover.
+
+use std::{cell::RefCell, cmp, collections::HashMap};
+
+use anyhow::{Context as _, Result};
+use ff::{Field, FromUniformBytes, PrimeField};
+use haloumi_core::{felt::Felt, info_traits::QueryInfo as _, slot::Slot};
+use haloumi_ir_gen::circuit::resolved::ResolvedIRCircuit;
+use haloumi_synthesis::lookups::Lookup;
+use mdnt_support::{cells::CellReprSize, fe_to_big};
+use midnight_proofs::{
+    circuit::{
+        floor_planner::V1,
+        groups::{GroupKey, RegionsGroup},
+        Layouter, SimpleFloorPlanner, Value,
+    },
+    dev::MockProver,
+    plonk::{
+        Advice, Any, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error, Expression,
+        Fixed, FloorPlanner, Instance, Selector,
+    },
+    utils::rational::Rational,
+};
+
+use super::{
+    configuration::Config, layouter::steal, planner::FloorPlanning, AbstractCircuitConfig,
+    AbstractCircuitIO,
+};
+use crate::{
+    interpreter::{reference::MultisetEq, Interpreter, InterpreterError, Witness},
+    lookups::{LookupHandlers, LookupTable},
+    shuffles::ShuffleModule,
+};
+
+/// Outcome of checking the IR of a circuit against its witnesses.
+#[derive(Debug, Default, Clone)]
+pub struct WitnessCheck {
+    accepted: usize,
+    rejected: usize,
+    skipped: Option<String>,
+}
+
+impl WitnessCheck {
+    /// Number of samples that satisfy both the circuit and the IR.
+    pub fn accepted(&self) -> usize {
+        self.accepted
+    }
+
+    /// Number of samples the circuit rejected.
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    /// Reason why the remaining samples were not checked, if any.
+    pub fn skipped(&self) -> Option<&str> {
+        self.skipped.as_deref()
+    }
+}
+
+/// Runs `samples` witnesses of the circuit through [`MockProver`] and checks
+/// the ones it accepts against the IR. The witnesses are laid out by the floor
+/// planner the IR was extracted with.
+///
+/// The calls to the modules of the lookup handlers are evaluated with their
+/// reference implementations, given the tables assigned by the circuit, and
+/// the calls to the shuffle modules with [`MultisetEq`].
+///
+/// Fails on the first witness that the circuit accepts and the IR doesn't.
+pub fn check_witnesses<F, C, AC>(
+    ir: &ResolvedIRCircuit,
+    circuit: &C,
+    lookups: Option<&dyn LookupHandlers<F>>,
+    shuffles: &[ShuffleModule],
+    samples: usize,
+    floor_planner: FloorPlanning,
+) -> Result<WitnessCheck>
+where
+    F: PrimeField + FromUniformBytes<64> + Ord,
+    C: Circuit<F, Config = Config<AC>>,
+    AC: AbstractCircuitIO,
+{
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+    let (input, output) = (config.input_instance(), config.output_instance());
+    let queried = queried_challenges(&cs);
+    // The tables are only known once the circuit has been synthesized.
+    let mut interpreter = None;
+    // The challenges drawn by the prover, once it has run.
+    let mut drawn = None;
+    let mut check = WitnessCheck::default();
+
+    for sample in 0..samples {
+        let inputs = sample_values(sample, AC::Input::SIZE);
+        let challenges =
+            drawn.clone().unwrap_or_else(|| sample_challenges(sample, cs.num_challenges()));
+        let prove = |challenges| {
+            prove_sample(
+                circuit,
+                &cs,
+                &config,
+                floor_planner,
+                sample,
+                &inputs,
+                challenges,
+            )
+        };
+        let Some(mut proved) = prove(challenges)? else {
+            check.rejected += 1;
+            continue;
+        };
+        if drawn.is_none() {
+            // The first time the prover runs the advice was computed with the
+            // sampled challenges, so the sample is proved again with the ones
+            // the prover drew.
+            if let Ok(challenges) = proved.drawn_challenges(&queried) {
+                if challenges != proved.recorder.challenges {
+                    let Some(again) = prove(challenges)? else {
+                        check.rejected += 1;
+                        continue;
+                    };
+                    proved = again;
+                }
+            }
+        }
+        match proved.drawn_challenges(&queried) {
+            Ok(challenges) if challenges == proved.recorder.challenges => drawn = Some(challenges),
+            Ok(_) => {
+                check.skipped = Some("MockProver draws different challenges in each run".into());
+                break;
+            }
+            Err(index) => {
+                check.skipped = Some(format!("MockProver gives no value to challenge {index}"));
+                break;
+            }
+        }
+        if !proved.valid {
+            check.rejected += 1;
+            continue;
+        }
+
+        let recorder = &proved.recorder;
+        let interpreter = interpreter.get_or_insert_with(|| {
+            let interpreter = match lookups {
+                Some(lookups) => {
+                    Interpreter::new(ir).with_lookup_modules(lookups, &recorder.tables(&cs))
+                }
+                None => Interpreter::new(ir),
+            };
+            shuffles.iter().fold(interpreter, |interpreter, module| {
+                interpreter.with_module(module.name(), MultisetEq::new(module.width()))
+            })
+        });
+        match interpreter.check(&recorder.witness(input, output)) {
+            Ok(()) => check.accepted += 1,
+            Err(err @ (InterpreterError::UnknownModule(_) | InterpreterError::Unsupported(_))) => {
+                check.skipped = Some(err.to_string());
+                break;
+            }
+            Err(err) => anyhow::bail!(
+                "The IR rejects a valid witness (sample {sample}, inputs {inputs:?}): {err}"
+            ),
+        }
+    }
+    Ok(check)
+}
+
+/// A sample synthesized with some challenges and run through [`MockProver`].
+struct ProvedSample<F> {
+    recorder: WitnessRecorder<F>,
+    /// Whether [`MockProver`] accepts the witness.
+    valid: bool,
+    /// Values [`MockProver`] gave to the challenges queried by the gates.
+    drawn: HashMap<usize, F>,
+}
+
+impl<F: PrimeField> ProvedSample<F> {
+    /// The challenges of the sample with the values [`MockProver`] drew for
+    /// the ones the gates query. Fails with the index of a queried challenge
+    /// the prover gave no value to.
+    fn drawn_challenges(&self, queried: &[Challenge]) -> Result<Vec<F>, usize> {
+        let mut challenges = self.recorder.challenges.clone();
+        for challenge in queried {
+            let index = challenge.index();
+            challenges[index] = *self.drawn.get(&index).ok_or(index)?;
+        }
+        Ok(challenges)
+    }
+}
+
+/// Synthesizes the sample with the given challenges and runs it through
+/// [`MockProver`]. Returns `None` if the circuit fails to synthesize it.
+fn prove_sample<F, C, AC>(
+    circuit: &C,
+    cs: &ConstraintSystem<F>,
+    config: &Config<AC>,
+    floor_planner: FloorPlanning,
+    sample: usize,
+    inputs: &[F],
+    challenges: Vec<F>,
+) -> Result<Option<ProvedSample<F>>>
+where
+    F: PrimeField + FromUniformBytes<64> + Ord,
+    C: Circuit<F, Config = Config<AC>>,
+    AC: AbstractCircuitIO,
+{
+    let (input, output) = (config.input_instance(), config.output_instance());
+    // Only procedures read their outputs; functions get them from the cells
+    // copied into the output instance.
+    let outputs = sample_values(sample, AC::Output::SIZE);
+    let mut recorder =
+        WitnessRecorder::new([(input, inputs.to_vec()), (output, outputs)], challenges);
+    let constants = cs.constants().clone();
+    let synthesized = match floor_planner {
+        FloorPlanning::Simple => {
+            SimpleFloorPlanner::synthesize(&mut recorder, circuit, config.clone(), constants)
+        }
+        FloorPlanning::V1 => V1::synthesize(&mut recorder, circuit, config.clone(), constants),
+    };
+    if let Err(err) = synthesized {
+        log::debug!("Sample {sample} failed to synthesize: {err}");
+        return Ok(None);
+    }
+    recorder.resolve_outputs(output);
+
+    let probe = ChallengeProbe::new(circuit);
+    let prover = MockProver::run(recorder.k(cs), &probe, recorder.instances(cs))
+        .with_context(|| format!("Failed to run the mock prover on sample {sample}"))?;
+    let valid = match prover.verify() {
+        Ok(()) => true,
+        Err(failures) => {
+            log::debug!(
+                "Sample {sample} is not a valid witness ({} failures, first: {})",
+                failures.len(),
+                failures.first().map(ToString::to_string).unwrap_or_default()
+            );
+            false
+        }
+    };
+    Ok(Some(ProvedSample {
+        recorder,
+        valid,
+        drawn: probe.drawn.into_inner(),
+    }))
+}
+
+/// Challenges queried by the gates of the constraint system.
+fn queried_challenges<F: Field>(cs: &ConstraintSystem<F>) -> Vec<Challenge> {
+    fn collect<F: Field>(expr: &Expression<F>, challenges: &mut Vec<Challenge>) {
+        match expr {
+            Expression::Challenge(challenge) => {
+                if !challenges.contains(challenge) {
+                    challenges.push(*challenge);
+                }
+            }
+            Expression::Negated(expr) | Expression::Scaled(expr, _) => collect(expr, challenges),
+            Expression::Sum(lhs, rhs) | Expression::Product(lhs, rhs) => {
+                collect(lhs, challenges);
+                collect(rhs, challenges);
+            }
+            Expression::Constant(_)
+            | Expression::Selector(_)
+            | Expression::Fixed(_)
+            | Expression::Advice(_)
+            | Expression::Instance(_) => {}
+        }
+    }
+
+    let mut challenges = vec![];
+    for gate in cs.gates() {
+        for poly in gate.polynomials() {
+            collect(poly, &mut challenges);
+        }
+    }
+    challenges
+}
+
+/// Circuit that records the values [`MockProver`] gives to the challenges the
+/// gates of the wrapped circuit query.
+///
+/// The prover has no way of taking the challenges, so they are read from the
+/// layouter while it synthesizes the circuit.
+struct ChallengeProbe<'c, F, C> {
+    circuit: &'c C,
+    drawn: RefCell<HashMap<usize, F>>,
+}
+
+impl<'c, F, C> ChallengeProbe<'c, F, C> {
+    fn new(circuit: &'c C) -> Self {
+        Self {
+            circuit,
+            drawn: RefCell::default(),
+        }
+    }
+}
+
+impl<F: Field, C: Circuit<F>> Circuit<F> for ChallengeProbe<'_, F, C> {
+    type Config = (C::Config, Vec<Challenge>);
+    type FloorPlanner = C::FloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.circuit)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let config = C::configure(meta);
+        (config, queried_challenges(meta))
+    }
+
+    fn synthesize(
+        &self,
+        (config, challenges): Self::Config,
+        layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // The prover synthesizes the circuit once per phase and the values are
+        // only known in the phases after the challenge's.
+        for challenge in challenges {
+            if let Some(value) = steal(layouter.get_challenge(challenge)) {
+                self.drawn.borrow_mut().insert(challenge.index(), value);
+            }
+        }
+        self.circuit.synthesize(config, layouter)
+    }
+}
+
+/// Values given to the instance cells in a sample.
+///
+/// The first sample is all zeros and the second all ones. The rest are
+/// pseudo-random values of 1, 8, 32 and 64 bits in turn so harnesses that
+/// expect bits or bytes also get inputs they accept.
+fn sample_values<F: PrimeField>(sample: usize, len: usize) -> Vec<F> {
+    (0..len)
+        .map(|n| match sample {
+            0 => F::ZERO,
+            1 => F::ONE,
+            _ => {
+                let bits = [1, 8, 32, 64][sample % 4];
+                F::from(splitmix64(((sample as u64) << 32) | n as u64) >> (64 - bits))
+            }
+        })
+        .collect()
+}
+
+/// Values of the challenges in a sample.
+///
+/// Challenges are uniformly random in a real proof, so they get full width
+/// values that don't depend on the ones of the inputs.
+fn sample_challenges<F: FromUniformBytes<64>>(sample: usize, len: usize) -> Vec<F> {
+    (0..len)
+        .map(|n| {
+            let mut bytes = [0; 64];
+            for (i, chunk) in bytes.chunks_exact_mut(8).enumerate() {
+                let seed = !(((sample as u64) << 32) | ((n as u64) << 8) | i as u64);
+                chunk.copy_from_slice(&splitmix64(seed).to_le_bytes());
+            }
+            F::from_uniform_bytes(&bytes)
+        })
+        .collect()
+}
+
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Assignment that keeps the values written by the floor planner.
+#[derive(Debug)]
+struct WitnessRecorder<F> {
+    instance: HashMap<Column<Instance>, Vec<F>>,
+    advice: HashMap<(usize, usize), F>,
+    fixed: HashMap<(usize, usize), F>,
+    copies: Vec<((Column<Any>, usize), (Column<Any>, usize))>,
+    challenges: Vec<F>,
+    rows: usize,
+}
+
+impl<F: PrimeField> WitnessRecorder<F> {
+    fn new(
+        instance: impl IntoIterator<Item = (Column<Instance>, Vec<F>)>,
+        challenges: Vec<F>,
+    ) -> Self {
+        Self {
+            instance: instance.into_iter().collect(),
+            advice: HashMap::new(),
+            fixed: HashMap::new(),
+            copies: vec![],
+            challenges,
+            rows: 0,
+        }
+    }
+
+    fn touch(&mut self, row: usize) {
+        self.rows = cmp::max(self.rows, row + 1);
+    }
+
+    /// Replaces the sampled outputs with the advice cells copied into them.
+    fn resolve_outputs(&mut self, output: Column<Instance>) {
+        let output_col = Column::<Any>::from(output);
+        let Some(outputs) = self.instance.get_mut(&output) else {
+            return;
+        };
+        for &(left, right) in &self.copies {
+            for ((from, from_row), (to, to_row)) in [(left, right), (right, left)] {
+                if to != output_col || !matches!(from.column_type(), Any::Advice(_)) {
+                    continue;
+                }
+                if let (Some(value), Some(cell)) = (
+                    self.advice.get(&(from.index(), from_row)),
+                    outputs.get_mut(to_row),
+                ) {
+                    *cell = *value;
+                }
+            }
+        }
+    }
+
+    /// Smallest `k` whose usable rows fit the assignment.
+    fn k(&self, cs: &ConstraintSystem<F>) -> u32 {
+        let rows = self.instance.values().map(Vec::len).fold(self.rows, cmp::max);
+        (rows + cs.minimum_rows()).next_power_of_two().trailing_zeros()
+    }
+
+    /// Values of every instance column in the order of the constraint system.
+    fn instances(&self, cs: &ConstraintSystem<F>) -> Vec<Vec<F>> {
+        let mut instances = vec![vec![]; cs.num_instance_columns()];
+        for (column, values) in &self.instance {
+            instances[column.index()] = values.clone();
+        }
+        instances
+    }
+
+    /// Rows of the tables of the lookups, in the order of their inputs.
+    ///
+    /// Lookups into expressions other than fixed columns are left out, and so
+    /// are the rows the circuit didn't assign.
+    fn tables(&self, cs: &ConstraintSystem<F>) -> Vec<LookupTable> {
+        Lookup::<Expression<F>>::load(cs)
+            .into_iter()
+            .filter_map(|lookup| {
+                let columns = lookup.table_queries().ok()?;
+                let rows = (0..self.rows)
+                    .filter_map(|row| {
+                        columns
+                            .iter()
+                            .map(|query| self.fixed.get(&(query.column_index(), row)))
+                            .map(|value| value.copied().map(fe_to_big))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .collect();
+                Some(LookupTable::new(lookup.name(), rows))
+            })
+            .collect()
+    }
+
+    fn witness(&self, input: Column<Instance>, output: Column<Instance>) -> Witness {
+        let felts = |column| {
+            self.instance
+                .get(&column)
+                .into_iter()
+                .flatten()
+                .map(|value| Felt::new(*value))
+                .collect::<Vec<_>>()
+        };
+        let advice = self
+            .advice
+            .iter()
+            .map(|(&(col, row), value)| (Slot::advice_abs(col, row), Felt::new(*value)));
+        let fixed = self
+            .fixed
+            .iter()
+            .map(|(&(col, row), value)| (Slot::fixed_abs(col, row), Felt::new(*value)));
+        Witness::new(felts(input), felts(output))
+            .with_cells(advice.chain(fixed))
+            .with_challenges(self.challenges.iter().map(|value| Felt::new(*value)))
+    }
+}
+
+impl<F: PrimeField> Assignment<F> for WitnessRecorder<F> {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        AR: Into<String>,
+        A: FnOnce() -> AR,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        // Instance columns other than the IO ones are left empty.
+        Ok(Value::known(
+            self.instance
+                .get(&column)
+                .and_then(|values| values.get(row))
+                .copied()
+                .unwrap_or(F::ZERO),
+        ))
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        advice: Column<Advice>,
+        row: usize,
+        value: V,
+    ) -> Result<(), Error>
+    where
+        VR: Into<Rational<F>>,
+        AR: Into<String>,
+        V: FnOnce() -> Value<VR>,
+        A: FnOnce() -> AR,
+    {
+        self.touch(row);
+        if let Some(value) = steal(value().map(|v| v.into().evaluate())) {
+            self.advice.insert((advice.index(), row), value);
+        }
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        fixed: Column<Fixed>,
+        row: usize,
+        value: V,
+    ) -> Result<(), Error>
+    where
+        VR: Into<Rational<F>>,
+        AR: Into<String>,
+        V: FnOnce() -> Value<VR>,
+        A: FnOnce() -> AR,
+    {
+        self.touch(row);
+        if let Some(value) = steal(value().map(|v| v.into().evaluate())) {
+            self.fixed.insert((fixed.index(), row), value);
+        }
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        from: Column<Any>,
+        from_row: usize,
+        to: Column<Any>,
+        to_row: usize,
+    ) -> Result<(), Error> {
+        self.copies.push(((from, from_row), (to, to_row)));
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        value: Value<Rational<F>>,
+    ) -> Result<(), Error> {
+        // Only the first row is kept; the padding of tables never appears in
+        // the IR.
+        self.touch(row);
+        if let Some(value) = steal(value.map(|v| v.evaluate())) {
+            self.fixed.insert((column.index(), row), value);
+        }
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+
+    fn annotate_column<A, AR>(&mut self, _: A, _: Column<Any>)
+    where
+        AR: Into<String>,
+        A: FnOnce() -> AR,
+    {
+    }
+
+    fn get_challenge(&self, challenge: Challenge) -> Value<F> {
+        self.challenges
+            .get(challenge.index())
+            .map_or_else(Value::unknown, |value| Value::known(*value))
+    }
+
+    fn enter_group<NR, N, K>(&mut self, _: N, _: K)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+        K: GroupKey,
+    {
+    }
+
+    fn exit_group(&mut self, _: RegionsGroup) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use midnight_proofs::{
+        plonk::{FirstPhase, SecondPhase},
+        poly::Rotation,
+    };
+
+    use super::*;
+    use crate::fields::Blstrs;
+
+    #[test]
+    fn sample_values_widths() {
+        assert_eq!(sample_values::<Blstrs>(0, 3), vec![Blstrs::ZERO; 3]);
+        assert_eq!(sample_values::<Blstrs>(1, 3), vec![Blstrs::ONE; 3]);
+        let bits = sample_values::<Blstrs>(4, 16);
+        assert!(bits.iter().all(|v| *v == Blstrs::ZERO || *v == Blstrs::ONE));
+        assert!(bits.contains(&Blstrs::ONE));
+        let bytes = sample_values::<Blstrs>(5, 16);
+        assert!(bytes.iter().all(|v| *v < Blstrs::from(256)));
+        assert_eq!(bytes, sample_values::<Blstrs>(5, 16));
+    }
+
+    #[test]
+    fn challenges_queried_by_gates() {
+        let mut cs = ConstraintSystem::<Blstrs>::default();
+        let a = cs.advice_column();
+        let b = cs.advice_column_in(SecondPhase);
+        let unused = cs.challenge_usable_after(FirstPhase);
+        let c = cs.challenge_usable_after(FirstPhase);
+        cs.create_gate("rlc", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_challenge(c);
+            vec![b - a * c.clone() * c]
+        });
+        assert_eq!(queried_challenges(&cs), [c]);
+        assert_ne!(unused, c);
+    }
+}
@@ -0,0 +1,124 @@
+//! Placement of the regions and constants of the circuit.
+//!
+//! The [`ExtractionLayouter`](super::layouter::ExtractionLayouter) delegates
+//! to a [`Planner`] the row where each region starts and the cell where each
+//! constant is stored. The planners mirror the floor planners of
+//! [`midnight_proofs`] so the rows in the extracted IR are the ones of the
+//! proving circuit.
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use midnight_proofs::{
+    circuit::layouter::{RegionColumn, RegionShape},
+    plonk::{Any, Column, Error, Fixed},
+};
+use serde::{Deserialize, Serialize};
+
+pub use v1::V1Planner;
+
+mod v1;
+
+/// Strategy for placing the regions and constants of the circuit.
+pub trait Planner: fmt::Debug {
+    /// Whether the planner needs the shape of every region before placing
+    /// the first one. If so, the circuit is synthesized twice and the first
+    /// pass only reports the shapes to [`Planner::measure`].
+    fn measures(&self) -> bool {
+        false
+    }
+
+    /// Records the shape of the next region during the measurement pass.
+    fn measure(&mut self, _shape: RegionShape) {}
+
+    /// Lays out the measured regions. Called once, after the measurement pass.
+    fn plan(&mut self, _constants: &[Column<Fixed>]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns the row where the region starts.
+    fn place_region(&mut self, shape: &RegionShape) -> Result<usize, Error>;
+
+    /// Returns the cell where the next constant is stored.
+    fn place_constant(
+        &mut self,
+        constants: &[Column<Fixed>],
+    ) -> Result<(Column<Fixed>, usize), Error>;
+}
+
+/// Floor planner used to lay out the harnesses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FloorPlanning {
+    /// Mirrors `SimpleFloorPlanner`.
+    #[default]
+    Simple,
+    /// Mirrors the `V1` floor planner.
+    V1,
+}
+
+impl FloorPlanning {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FloorPlanning::Simple => "simple",
+            FloorPlanning::V1 => "v1",
+        }
+    }
+
+    /// Creates a planner for a single synthesis of the circuit.
+    pub fn planner(&self) -> Box<dyn Planner> {
+        match self {
+            FloorPlanning::Simple => Box::new(SimplePlanner::default()),
+            FloorPlanning::V1 => Box::new(V1Planner::default()),
+        }
+    }
+}
+
+impl fmt::Display for FloorPlanning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for FloorPlanning {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [FloorPlanning::Simple, FloorPlanning::V1]
+            .into_iter()
+            .find(|planning| planning.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown floor planner '{s}', expected 'simple' or 'v1'"))
+    }
+}
+
+/// Places each region at the earliest row where none of its columns are in
+/// use, and the constants in order in the first constants column.
+#[derive(Debug, Default)]
+pub struct SimplePlanner {
+    /// Stores the first empty row for each column.
+    columns: HashMap<RegionColumn, usize>,
+}
+
+impl Planner for SimplePlanner {
+    fn place_region(&mut self, shape: &RegionShape) -> Result<usize, Error> {
+        let start = shape
+            .columns()
+            .iter()
+            .map(|column| self.columns.get(column).copied().unwrap_or(0))
+            .max()
+            .unwrap_or(0);
+        for column in shape.columns() {
+            self.columns.insert(*column, start + shape.row_count());
+        }
+        Ok(start)
+    }
+
+    fn place_constant(
+        &mut self,
+        constants: &[Column<Fixed>],
+    ) -> Result<(Column<Fixed>, usize), Error> {
+        let column = *constants.first().ok_or(Error::NotEnoughColumnsForConstants)?;
+        let row = self.columns.entry(Column::<Any>::from(column).into()).or_default();
+        *row += 1;
+        Ok((column, *row - 1))
+    }
+}
@@ -0,0 +1,248 @@
+//! Planner that mirrors the `V1` floor planner of [`midnight_proofs`].
+//!
+//! The regions are sorted by the area they take in advice columns, biggest
+//! first, and each one is placed at the first rows where all its columns are
+//! free. The constants fill the free rows of the constants columns below the
+//! last row used by any region.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    hash::Hash,
+};
+
+use midnight_proofs::{
+    circuit::layouter::{RegionColumn, RegionShape},
+    plonk::{Any, Column, Error, Fixed},
+};
+
+use super::Planner;
+
+#[derive(Debug, Default)]
+pub struct V1Planner {
+    /// Shapes reported by the measurement pass.
+    shapes: Vec<RegionShape>,
+    /// Start of each region, in the order they are assigned.
+    starts: Vec<usize>,
+    /// Free cells of the constants columns, in the order they are used.
+    constants: VecDeque<(Column<Fixed>, usize)>,
+}
+
+impl Planner for V1Planner {
+    fn measures(&self) -> bool {
+        true
+    }
+
+    fn measure(&mut self, shape: RegionShape) {
+        self.shapes.push(shape);
+    }
+
+    fn plan(&mut self, constants: &[Column<Fixed>]) -> Result<(), Error> {
+        let shapes: Vec<_> = self
+            .shapes
+            .iter()
+            .map(|shape| {
+                let advice = shape
+                    .columns()
+                    .iter()
+                    .filter(|column| {
+                        matches!(column, RegionColumn::Column(c) if matches!(c.column_type(), Any::Advice(_)))
+                    })
+                    .count();
+                Shape {
+                    columns: shape.columns().iter().copied().collect(),
+                    rows: shape.row_count(),
+                    advice,
+                }
+            })
+            .collect();
+        let (starts, allocations) = slot_in_biggest_advice_first(&shapes);
+        let first_unassigned_row =
+            allocations.values().map(Allocations::unbounded_start).max().unwrap_or(0);
+        self.starts = starts;
+        self.constants = constants
+            .iter()
+            .flat_map(|column| {
+                let free = allocations
+                    .get(&Column::<Any>::from(*column).into())
+                    .cloned()
+                    .unwrap_or_default()
+                    .free_intervals(0, Some(first_unassigned_row));
+                free.into_iter()
+                    .flat_map(move |(start, end)| start..end.unwrap_or(first_unassigned_row))
+                    .map(move |row| (*column, row))
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn place_region(&mut self, shape: &RegionShape) -> Result<usize, Error> {
+        self.starts.get(*shape.region_index()).copied().ok_or_else(|| {
+            Error::Synthesis(format!(
+                "Region {} was not measured by the floor planner",
+                *shape.region_index()
+            ))
+        })
+    }
+
+    fn place_constant(&mut self, _: &[Column<Fixed>]) -> Result<(Column<Fixed>, usize), Error> {
+        self.constants.pop_front().ok_or(Error::NotEnoughColumnsForConstants)
+    }
+}
+
+/// Columns and size of a region.
+#[derive(Debug, Clone)]
+struct Shape<K> {
+    columns: Vec<K>,
+    rows: usize,
+    /// Number of advice columns.
+    advice: usize,
+}
+
+/// Rows allocated in a column, as a map from the first row to the row after
+/// the last one.
+#[derive(Debug, Clone, Default)]
+struct Allocations(BTreeMap<usize, usize>);
+
+impl Allocations {
+    /// Free rows between `start` and `end`, as half-open intervals. The last
+    /// interval has no end if `end` is `None`.
+    fn free_intervals(&self, start: usize, end: Option<usize>) -> Vec<(usize, Option<usize>)> {
+        let mut row = start;
+        let mut free = vec![];
+        for (&allocated_start, &allocated_end) in &self.0 {
+            if end.is_some_and(|end| allocated_start >= end) {
+                break;
+            }
+            if row < allocated_start {
+                free.push((row, Some(allocated_start)));
+            }
+            row = row.max(allocated_end);
+        }
+        if end.is_none_or(|end| row < end) {
+            free.push((row, end));
+        }
+        free
+    }
+
+    /// First row after every allocation.
+    fn unbounded_start(&self) -> usize {
+        self.0.last_key_value().map_or(0, |(_, end)| *end)
+    }
+}
+
+/// Finds the first row from `start` where `rows` rows are free in every
+/// column, and allocates them.
+///
+/// If `slack` is given, the region must end at most `slack` rows after
+/// `start + rows`.
+fn first_fit<K: Copy + Eq + Hash>(
+    allocations: &mut HashMap<K, Allocations>,
+    columns: &[K],
+    rows: usize,
+    start: usize,
+    slack: Option<usize>,
+) -> Option<usize> {
+    let Some((column, rest)) = columns.split_first() else {
+        return Some(start);
+    };
+    let end = slack.map(|slack| start + rows + slack);
+    let free = allocations.entry(*column).or_default().free_intervals(start, end);
+    for (free_start, free_end) in free {
+        let free_slack =
+            free_end.map(|free_end| free_end as isize - free_start as isize - rows as isize);
+        if free_slack.unwrap_or(0) < 0 {
+            continue;
+        }
+        let fit = first_fit(
+            allocations,
+            rest,
+            rows,
+            free_start,
+            free_slack.map(|slack| slack as usize),
+        );
+        if let Some(row) = fit {
+            allocations.entry(*column).or_default().0.insert(row, row + rows);
+            return Some(row);
+        }
+    }
+    None
+}
+
+/// Places the regions biggest advice area first and returns the start of each
+/// region in the original order.
+fn slot_in_biggest_advice_first<K: Copy + Ord + Hash>(
+    shapes: &[Shape<K>],
+) -> (Vec<usize>, HashMap<K, Allocations>) {
+    let mut order: Vec<_> = (0..shapes.len()).collect();
+    // The sort is stable so regions with the same area are placed in reverse
+    // order, like in the floor planner.
+    order.sort_by_cached_key(|n| shapes[*n].advice * shapes[*n].rows);
+    order.reverse();
+
+    let mut allocations = HashMap::new();
+    let mut starts = vec![0; shapes.len()];
+    for n in order {
+        let mut columns = shapes[n].columns.clone();
+        columns.sort_unstable();
+        starts[n] = first_fit(&mut allocations, &columns, shapes[n].rows, 0, None)
+            .expect("A region always fits after the last allocated row");
+    }
+    (starts, allocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(columns: &[usize], rows: usize, advice: usize) -> Shape<usize> {
+        Shape {
+            columns: columns.to_vec(),
+            rows,
+            advice,
+        }
+    }
+
+    #[test]
+    fn free_intervals() {
+        let allocations = Allocations(BTreeMap::from([(2, 4), (6, 7)]));
+        assert_eq!(
+            allocations.free_intervals(0, None),
+            [(0, Some(2)), (4, Some(6)), (7, None)]
+        );
+        assert_eq!(allocations.free_intervals(3, Some(6)), [(4, Some(6))]);
+        assert_eq!(allocations.free_intervals(0, Some(2)), [(0, Some(2))]);
+        assert_eq!(allocations.unbounded_start(), 7);
+    }
+
+    #[test]
+    fn biggest_advice_first() {
+        // The second region has the biggest advice area so it goes first and
+        // the others are placed after it.
+        let shapes = [
+            shape(&[0], 2, 1),
+            shape(&[0, 1], 3, 2),
+            shape(&[1, 2], 1, 1),
+            shape(&[2], 4, 0),
+        ];
+        let (starts, allocations) = slot_in_biggest_advice_first(&shapes);
+        assert_eq!(starts, [3, 0, 3, 4]);
+        assert_eq!(allocations[&2].unbounded_start(), 8);
+    }
+
+    #[test]
+    fn fills_gaps() {
+        // The second region is placed after the third one in column 1, which
+        // leaves rows free in column 0 for the first one.
+        let shapes = [
+            shape(&[0], 2, 1),
+            shape(&[0, 1], 2, 2),
+            shape(&[1, 2], 3, 2),
+        ];
+        let (starts, allocations) = slot_in_biggest_advice_first(&shapes);
+        assert_eq!(starts, [0, 3, 0]);
+        assert_eq!(
+            allocations[&0].free_intervals(0, None),
+            [(2, Some(3)), (5, None)]
+        );
+    }
+}
@@ -2,7 +2,8 @@
 
 use std::marker::PhantomData;
 
-use ff::PrimeField;
+use ff::{Field, PrimeField};
+pub use halo2curves::bn256::Fr as Bn254;
 pub use midnight_curves::{
     secp256k1::{Fp as Secp256k1Fp, Fq as Secp256k1Fq, Secp256k1},
     Fp as MidnightFp, Fq as Blstrs, Fr as JubjubFr, G1Projective as G1, JubjubExtended as Jubjub,
@@ -23,12 +24,35 @@ use mdnt_support::{
     circuit::injected::InjectedIR,
 };
 
+use crate::harness::metadata::{ConstantKind, ReadsConstants};
+
+/// A field that circuits can be defined over.
+///
+/// Harnesses are registered with the field of their circuit. The name is how
+/// the field is shown in harness names and matched by selections.
+pub trait NativeField: PrimeField {
+    const NAME: &'static str;
+}
+
+impl NativeField for Blstrs {
+    const NAME: &'static str = "blstrs";
+}
+
+impl NativeField for Bn254 {
+    const NAME: &'static str = "bn254";
+}
+
+/// Name of the field of the harnesses whose names don't mention one.
+pub const DEFAULT_FIELD: &str = <Blstrs as NativeField>::NAME;
+
 pub struct Zero<T>(PhantomData<T>);
 
 impl<T> CellReprSize for Zero<T> {
     const SIZE: usize = 0;
 }
 
+/// A value of the field `F` read from the constants. It can be loaded in circuits
+/// over any field.
 #[repr(transparent)]
 pub struct Loaded<F>(pub F);
 
@@ -36,13 +60,17 @@ impl<F: PrimeField> CellReprSize for Loaded<F> {
     const SIZE: usize = 0;
 }
 
-impl<C, L, F: PrimeField> LoadFromCells<Blstrs, C, ExtractionSupport, L> for Loaded<F> {
+impl<N: Field, C, L, F: PrimeField> LoadFromCells<N, C, ExtractionSupport, L> for Loaded<F> {
     fn load(
-        ctx: &mut ICtx<Blstrs, ExtractionSupport>,
+        ctx: &mut ICtx<N, ExtractionSupport>,
         _chip: &C,
-        _layouter: &mut impl LayoutAdaptor<Blstrs, ExtractionSupport, Adaptee = L>,
-        _injected_ir: &mut InjectedIR<RegionIndex, Expression<Blstrs>>,
+        _layouter: &mut impl LayoutAdaptor<N, ExtractionSupport, Adaptee = L>,
+        _injected_ir: &mut InjectedIR<RegionIndex, Expression<N>>,
     ) -> Result<Self, Error> {
         Ok(Self(ctx.field_constant()?))
     }
 }
+
+impl<F: PrimeField> ReadsConstants for Loaded<F> {
+    const CONSTANTS: &'static [ConstantKind] = &[ConstantKind::Field];
+}
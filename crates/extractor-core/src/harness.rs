@@ -1,25 +1,52 @@
+use std::{cell::RefCell, collections::HashSet, sync::OnceLock};
+
 use anyhow::Context as _;
-use ff::PrimeField;
+use ff::{FromUniformBytes, PrimeField};
 use haloumi::driver::Driver;
 use haloumi_core::info_traits::ConstraintSystemInfo;
 use haloumi_ir_gen::circuit::resolved::ResolvedIRCircuit;
-use haloumi_ir_gen::lookups::callbacks::LookupCallbacks;
+use haloumi_ir_gen::lookups::callbacks::{LookupCallbacks, LookupResult};
+use haloumi_ir_gen::lookups::table::LookupTableGenerator;
+use haloumi_ir_gen::temps::Temps;
 use haloumi_ir_gen::IRGenParams;
+use haloumi_synthesis::lookups::Lookup;
 use haloumi_synthesis::CircuitSynthesis;
 use mdnt_support::circuit::ChipArgs;
-use midnight_proofs::plonk::Expression;
+use midnight_proofs::plonk::{Circuit, Expression};
 
 use crate::{
-    circuit::{AbstractCircuitIO, CircuitImpl},
+    cells::layout::IoLayout,
+    circuit::{
+        configuration::Config,
+        mock::{self, WitnessCheck},
+        planner::FloorPlanning,
+        AbstractCircuitIO, CircuitImpl,
+    },
+    fields::DEFAULT_FIELD,
+    lookups::{LookupHandlers, LookupRecord, LookupStatus, TableModule},
     patterns::Patterns,
+    shuffles::{lower_shuffles, ShuffleCallbacks, ShuffleModule},
+    taxonomy::HarnessId,
 };
 
+pub mod metadata;
+
+use metadata::Metadata;
+
 /// Information required for executing a harness.
 pub struct Ctx<'s> {
     constants: &'s [String],
     debug_comments: bool,
     disable_decomposition_pattern: bool,
     allow_injected_ir_for_outputs: bool,
+    witness_samples: usize,
+    floor_planner: FloorPlanning,
+    strict_lookups: bool,
+    io_layout: RefCell<Option<IoLayout>>,
+    lookups: RefCell<Vec<LookupRecord>>,
+    table_modules: RefCell<Vec<TableModule>>,
+    shuffle_modules: RefCell<Vec<ShuffleModule>>,
+    witness_check: RefCell<Option<WitnessCheck>>,
 }
 
 impl<'s> Ctx<'s> {
@@ -28,20 +55,35 @@ impl<'s> Ctx<'s> {
         debug_comments: bool,
         disable_decomposition_pattern: bool,
         allow_injected_ir_for_outputs: bool,
+        witness_samples: usize,
+        floor_planner: FloorPlanning,
+        strict_lookups: bool,
     ) -> Self {
         Self {
             constants,
             debug_comments,
             disable_decomposition_pattern,
             allow_injected_ir_for_outputs,
+            witness_samples,
+            floor_planner,
+            strict_lookups,
+            io_layout: RefCell::default(),
+            lookups: RefCell::default(),
+            table_modules: RefCell::default(),
+            shuffle_modules: RefCell::default(),
+            witness_check: RefCell::default(),
         }
     }
 
     /// Lowers the circuit to Picus using the driver.
+    ///
+    /// The lookups are passed to the lookup handlers, if any, and the shuffles
+    /// to the shuffle callbacks.
     pub fn lower_circuit<'c, F, C, M, CS>(
         &self,
         circuit: CircuitImpl<'c, F, C, M>,
-        lookups: Option<&dyn LookupCallbacks<F, Expression<F>>>,
+        lookups: Option<&dyn LookupHandlers<F>>,
+        shuffles: &dyn ShuffleCallbacks<F>,
     ) -> anyhow::Result<ResolvedIRCircuit>
     where
         F: PrimeField + Ord,
@@ -63,11 +105,21 @@ impl<'s> Ctx<'s> {
         if self.debug_comments {
             ir_params = ir_params.with_debug_comments();
         }
-        if let Some(lookups) = lookups {
-            ir_params = ir_params.lookup_callbacks(lookups);
+        let returned = RefCell::default();
+        let handlers = lookups.map(|handlers| Handlers {
+            handlers,
+            returned: &returned,
+        });
+        if let Some(handlers) = &handlers {
+            ir_params = ir_params.lookup_callbacks(handlers);
         }
 
-        let mut unresolved = driver.generate_ir(&syn, ir_params).context("IR generation failed")?;
+        let generated = driver.generate_ir(&syn, ir_params).context("IR generation failed");
+        let tracked = self.track_lookups(syn.lookups(), lookups, &returned.take());
+        let mut unresolved = generated?;
+        tracked?;
+        self.table_modules
+            .replace(lookups.map(|lookups| lookups.table_modules()).unwrap_or_default());
         let status = unresolved.validate();
         if let Err(err) = status {
             log::error!("{err}");
@@ -76,6 +128,10 @@ impl<'s> Ctx<'s> {
         }
 
         log::info!("Generated unresolved IR");
+        let shuffle_ir = lower_shuffles(&circuit.take_shuffles(), syn.groups(), shuffles)
+            .context("Shuffle lowering failed")?;
+        self.shuffle_modules.replace(shuffles.modules());
+        unresolved.inject_ir(shuffle_ir, &syn).context("Shuffle injection failed")?;
         let injected = circuit.take_injected_ir();
         unresolved.inject_ir(injected, &syn).context("IR injection failed")?;
         log::info!("Injected additional IR");
@@ -90,6 +146,120 @@ impl<'s> Ctx<'s> {
         Ok(resolved)
     }
 
+    /// Records the handler of each lookup of the circuit and warns about the
+    /// lookups that are left out of the IR, or that were passed to callbacks
+    /// that can't tell whether they encode them. In strict mode those lookups
+    /// fail the harness instead.
+    ///
+    /// Only the lookups in `returned`, whose callbacks returned their IR, are
+    /// recorded with their handler. The others are unmatched.
+    fn track_lookups<F: PrimeField>(
+        &self,
+        circuit_lookups: &[Lookup<Expression<F>>],
+        handlers: Option<&dyn LookupHandlers<F>>,
+        returned: &HashSet<usize>,
+    ) -> anyhow::Result<()> {
+        let records: Vec<_> = circuit_lookups
+            .iter()
+            .map(|lookup| {
+                let handler = handlers
+                    .filter(|_| returned.contains(&lookup.idx()))
+                    .and_then(|handlers| handlers.handler(lookup.name()));
+                LookupRecord::new(lookup.name(), lookup.idx(), handler)
+            })
+            .collect();
+        let unhandled: Vec<_> = records
+            .iter()
+            .filter(|record| record.status() != LookupStatus::Handled)
+            .map(ToString::to_string)
+            .collect();
+        self.lookups.replace(records);
+        if unhandled.is_empty() {
+            return Ok(());
+        }
+        if self.strict_lookups {
+            anyhow::bail!("Unhandled lookups in strict mode: {}", unhandled.join(", "));
+        }
+        for lookup in unhandled {
+            log::warn!("The IR may not constrain {lookup}");
+        }
+        Ok(())
+    }
+
+    /// Checks the IR against witnesses of the circuit computed by
+    /// [`MockProver`](midnight_proofs::dev::MockProver).
+    ///
+    /// Does nothing unless the context was created with witness samples.
+    pub fn check_witnesses<F, C, AC>(
+        &self,
+        ir: &ResolvedIRCircuit,
+        circuit: &C,
+        lookups: Option<&dyn LookupHandlers<F>>,
+    ) -> anyhow::Result<()>
+    where
+        F: PrimeField + FromUniformBytes<64> + Ord,
+        C: Circuit<F, Config = Config<AC>>,
+        AC: AbstractCircuitIO,
+    {
+        if self.witness_samples == 0 {
+            return Ok(());
+        }
+        let check = mock::check_witnesses(
+            ir,
+            circuit,
+            lookups,
+            &self.shuffle_modules(),
+            self.witness_samples,
+            self.floor_planner,
+        )
+        .context("Witness check failed")?;
+        log::info!(
+            "Checked {} witnesses against the IR ({} rejected by the circuit)",
+            check.accepted(),
+            check.rejected()
+        );
+        if let Some(reason) = check.skipped() {
+            log::warn!("Stopped checking witnesses: {reason}");
+        } else if check.accepted() == 0 {
+            log::warn!("The circuit rejected every sampled witness, nothing was checked");
+        }
+        self.witness_check.replace(Some(check));
+        Ok(())
+    }
+
+    /// Returns the outcome of the witness check, if the witnesses were checked.
+    pub fn witness_check(&self) -> Option<WitnessCheck> {
+        self.witness_check.borrow().clone()
+    }
+
+    /// Records the layout of the inputs and outputs of the harness.
+    pub fn set_io_layout(&self, layout: IoLayout) {
+        self.io_layout.replace(Some(layout));
+    }
+
+    /// Returns the layout of the inputs and outputs recorded by the harness.
+    pub fn io_layout(&self) -> Option<IoLayout> {
+        self.io_layout.borrow().clone()
+    }
+
+    /// Returns the lookups of the circuit and their handlers, recorded when the
+    /// circuit was lowered.
+    pub fn lookups(&self) -> Vec<LookupRecord> {
+        self.lookups.borrow().clone()
+    }
+
+    /// Returns the modules generated from the tables of the lookups, recorded
+    /// when the circuit was lowered.
+    pub fn table_modules(&self) -> Vec<TableModule> {
+        self.table_modules.borrow().clone()
+    }
+
+    /// Returns the modules called by the IR of the shuffles, recorded when the
+    /// circuit was lowered.
+    pub fn shuffle_modules(&self) -> Vec<ShuffleModule> {
+        self.shuffle_modules.borrow().clone()
+    }
+
     pub fn constants(&self) -> &[String] {
         self.constants
     }
@@ -97,6 +267,45 @@ impl<'s> Ctx<'s> {
     pub fn allow_injected_ir_for_outputs(&self) -> bool {
         self.allow_injected_ir_for_outputs
     }
+
+    /// Floor planner used to lay out the regions and constants.
+    pub fn floor_planner(&self) -> FloorPlanning {
+        self.floor_planner
+    }
+}
+
+/// Passes the lookups to the handlers and records the lookups whose IR they
+/// returned.
+///
+/// Trait objects can't be upcast in the minimum supported Rust version, so the
+/// handlers are wrapped to be passed to IR generation as [`LookupCallbacks`].
+struct Handlers<'a, F: PrimeField> {
+    handlers: &'a dyn LookupHandlers<F>,
+    returned: &'a RefCell<HashSet<usize>>,
+}
+
+impl<F: PrimeField> LookupCallbacks<F, Expression<F>> for Handlers<'_, F> {
+    fn on_lookups<'syn>(
+        &self,
+        lookups: &[&'syn Lookup<Expression<F>>],
+        tables: &[&dyn LookupTableGenerator<F>],
+        temps: &mut Temps,
+    ) -> LookupResult<'syn, Expression<F>> {
+        let ir = self.handlers.on_lookups(lookups, tables, temps)?;
+        self.returned.borrow_mut().extend(lookups.iter().map(|lookup| lookup.idx()));
+        Ok(ir)
+    }
+
+    fn on_lookup<'syn>(
+        &self,
+        lookup: &'syn Lookup<Expression<F>>,
+        table: &dyn LookupTableGenerator<F>,
+        temps: &mut Temps,
+    ) -> LookupResult<'syn, Expression<F>> {
+        let ir = self.handlers.on_lookup(lookup, table, temps)?;
+        self.returned.borrow_mut().insert(lookup.idx());
+        Ok(ir)
+    }
 }
 
 /// Output produced by a harness function.
@@ -105,33 +314,124 @@ pub type Output = ResolvedIRCircuit;
 /// Type representing the harness logic.
 pub type Harness = fn(&Ctx) -> anyhow::Result<Output>;
 
+/// Harness function and its metadata.
+///
+/// Harnesses are registered by the path of a function that returns their
+/// definition, so the harness macros can attach the metadata to the function
+/// they generate.
+#[derive(Debug, Copy, Clone)]
+pub struct Definition {
+    harness: Harness,
+    metadata: fn() -> Metadata,
+}
+
+impl Definition {
+    pub const fn new(harness: Harness, metadata: fn() -> Metadata) -> Self {
+        Self { harness, metadata }
+    }
+
+    /// Returns the harness function.
+    pub fn harness(&self) -> Harness {
+        self.harness
+    }
+
+    /// Returns the metadata of the harness.
+    pub fn metadata(&self) -> Metadata {
+        (self.metadata)()
+    }
+}
+
 /// Entry in the harness table.
-#[derive(Copy, Clone, Debug)]
-pub struct Entry(&'static str, Harness);
+#[derive(Debug)]
+pub struct Entry {
+    id: HarnessId,
+    field: &'static str,
+    definition: fn() -> Definition,
+    qualified_name: OnceLock<&'static str>,
+}
 
 impl Entry {
-    /// Creates a new entry
-    pub const fn new(name: &'static str, harness: Harness) -> Self {
-        Self(name, harness)
+    /// Creates a new entry for a harness whose circuit is over the field
+    /// named `field`.
+    pub const fn new(id: HarnessId, field: &'static str, definition: fn() -> Definition) -> Self {
+        Self {
+            id,
+            field,
+            definition,
+            qualified_name: OnceLock::new(),
+        }
     }
 
     /// Returns the name of the entry.
     pub fn name(&self) -> &'static str {
-        self.0
+        self.id.name()
+    }
+
+    /// Returns the components of the name of the entry.
+    pub fn id(&self) -> HarnessId {
+        self.id
+    }
+
+    /// Returns the name of the field of the circuit.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// Returns the name that identifies the entry among the entries of every
+    /// field. It is the name followed by `@<field>`, unless the field is
+    /// [`DEFAULT_FIELD`].
+    pub fn qualified_name(&'static self) -> &'static str {
+        self.qualified_name.get_or_init(|| {
+            if self.field == DEFAULT_FIELD {
+                self.name()
+            } else {
+                // Entries live as long as the program, so this is only
+                // allocated once per entry.
+                String::leak(format!("{}@{}", self.name(), self.field))
+            }
+        })
+    }
+
+    /// Returns the definition of the harness.
+    pub fn definition(&self) -> Definition {
+        (self.definition)()
     }
 
     /// Returns the harness function.
     pub fn harness(&self) -> Harness {
-        self.1
+        self.definition().harness()
+    }
+
+    /// Returns the metadata of the harness.
+    pub fn metadata(&self) -> Metadata {
+        self.definition().metadata()
     }
 }
 
 inventory::collect!(Entry);
 
 /// Registers a harness in the registry.
+///
+/// The harness is given by the path of a function that returns its
+/// [`Definition`], which is what the harness macros generate.
+///
+/// The name is parsed with [`HarnessId::parse`] while compiling, so a name
+/// whose instruction, chip or type is not in the
+/// [taxonomy](crate::taxonomy) fails to build.
+///
+/// The field of the circuit defaults to [`DEFAULT_FIELD`]. Harnesses over
+/// other fields pass the field type, which must implement
+/// [`NativeField`](crate::fields::NativeField).
 #[macro_export]
 macro_rules! entry {
     ($name:literal, $harness:path) => {
-        inventory::submit!($crate::harness::Entry::new($name, $harness));
+        $crate::entry!($name, $harness, $crate::fields::Blstrs);
+    };
+    ($name:literal, $harness:path, $field:ty) => {
+        inventory::submit!($crate::harness::Entry::new(
+            $crate::taxonomy::HarnessId::parse($name),
+            <$field as $crate::fields::NativeField>::NAME,
+            $harness
+        ));
     };
 }
@@ -0,0 +1,294 @@
+//! Information about a harness that is known without running it.
+//!
+//! The harness macros fill the metadata from the signature and the attributes
+//! of the harness. It is shown when listing the harnesses and used to check
+//! the constants before extracting.
+
+use std::{fmt, marker::PhantomData};
+
+use ff::PrimeField;
+use num_bigint::BigUint;
+
+use crate::lookups::{Handler, LookupHandlers};
+
+/// How a constant of a harness is parsed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConstantKind {
+    /// `true` or `false`.
+    Bool,
+    /// An integer that fits in a byte.
+    U8,
+    /// An integer that fits in a `usize`.
+    Usize,
+    /// An integer of any size.
+    Integer,
+    /// An element of a field, written in decimal.
+    Field,
+    /// Length of a vector. The constants read by its elements follow.
+    Length,
+    /// `true` if an optional value is present, in which case the constants
+    /// read by the value follow.
+    Flag,
+}
+
+impl ConstantKind {
+    /// Returns true if the constant can be parsed as this kind.
+    pub fn accepts(self, constant: &str) -> bool {
+        match self {
+            Self::Bool | Self::Flag => constant.parse::<bool>().is_ok(),
+            Self::U8 => constant.parse::<u8>().is_ok(),
+            Self::Usize | Self::Length => constant.parse::<usize>().is_ok(),
+            Self::Integer => constant.parse::<BigUint>().is_ok(),
+            Self::Field => !constant.is_empty() && constant.bytes().all(|b| b.is_ascii_digit()),
+        }
+    }
+
+    /// Returns true if the constants that follow depend on the value of this
+    /// one.
+    pub fn is_variable(self) -> bool {
+        matches!(self, Self::Length | Self::Flag)
+    }
+}
+
+impl fmt::Display for ConstantKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bool => "bool",
+            Self::U8 => "u8",
+            Self::Usize => "usize",
+            Self::Integer => "integer",
+            Self::Field => "field element",
+            Self::Length => "length",
+            Self::Flag => "flag",
+        })
+    }
+}
+
+/// Inputs that read constants when they are loaded.
+///
+/// The harness macros build the constants schema of a harness from the
+/// implementations of its inputs. Inputs that don't implement this trait are
+/// assumed to read no constants.
+pub trait ReadsConstants {
+    /// Kind of each constant read, in order.
+    const CONSTANTS: &'static [ConstantKind];
+}
+
+macro_rules! reads_primitive {
+    ($t:ty, $kind:ident) => {
+        impl ReadsConstants for $t {
+            const CONSTANTS: &'static [ConstantKind] = &[ConstantKind::$kind];
+        }
+    };
+}
+
+reads_primitive!(bool, Bool);
+reads_primitive!(u8, U8);
+reads_primitive!(usize, Usize);
+reads_primitive!(BigUint, Integer);
+
+/// Looks up the constants read by `T`, falling back to none if `T` doesn't
+/// implement [`ReadsConstants`].
+///
+/// The harness macros call `(&Probe::<T>(PhantomData)).constants()` with
+/// [`ProbeReads`] and [`ProbeFallback`] in scope. Method resolution picks
+/// [`ProbeReads`] if it applies because it needs one less reference.
+#[doc(hidden)]
+pub struct Probe<T>(pub PhantomData<T>);
+
+#[doc(hidden)]
+pub trait ProbeReads {
+    fn constants(&self) -> &'static [ConstantKind];
+}
+
+impl<T: ReadsConstants> ProbeReads for Probe<T> {
+    fn constants(&self) -> &'static [ConstantKind] {
+        T::CONSTANTS
+    }
+}
+
+#[doc(hidden)]
+pub trait ProbeFallback {
+    fn constants(&self) -> &'static [ConstantKind];
+}
+
+impl<T> ProbeFallback for &Probe<T> {
+    fn constants(&self) -> &'static [ConstantKind] {
+        &[]
+    }
+}
+
+/// Error returned when the constants don't match the schema of a harness.
+#[derive(Debug, thiserror::Error)]
+pub enum ConstantsError {
+    #[error("Expected at least {expected} constants but got {actual}")]
+    Missing { expected: usize, actual: usize },
+    #[error("Constant {index} ('{constant}') is not a valid {kind}")]
+    Invalid {
+        index: usize,
+        constant: String,
+        kind: ConstantKind,
+    },
+}
+
+/// Description of a harness and of what it needs to be extracted.
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    description: Option<&'static str>,
+    constants: Vec<ConstantKind>,
+    lookups: Vec<Handler>,
+    modules: Vec<&'static str>,
+    tags: Vec<&'static str>,
+}
+
+impl Metadata {
+    pub fn with_description(mut self, description: &'static str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Sets the kind of each constant read by the inputs, in order.
+    pub fn with_constants(mut self, constants: Vec<ConstantKind>) -> Self {
+        self.constants = constants;
+        self
+    }
+
+    /// Records the handlers of the lookup callbacks of the harness and the
+    /// modules they call.
+    pub fn with_lookups<F: PrimeField>(mut self, lookups: Option<&dyn LookupHandlers<F>>) -> Self {
+        if let Some(lookups) = lookups {
+            self.lookups = lookups.handlers();
+            self.modules = lookups.modules();
+        }
+        self
+    }
+
+    pub fn with_tags(mut self, tags: &[&'static str]) -> Self {
+        self.tags = tags.to_vec();
+        self
+    }
+
+    /// Returns the documentation of the harness.
+    pub fn description(&self) -> Option<&'static str> {
+        self.description
+    }
+
+    /// Returns the kind of each constant read by the inputs, in order.
+    ///
+    /// The constants read by a vector or an optional value depend on the
+    /// value of its [variable](ConstantKind::is_variable) constant, so they
+    /// are not part of the schema and the constants after it are only
+    /// indicative.
+    pub fn constants(&self) -> &[ConstantKind] {
+        &self.constants
+    }
+
+    /// Returns the handlers that the lookup callbacks of the harness can use.
+    pub fn lookups(&self) -> &[Handler] {
+        &self.lookups
+    }
+
+    /// Returns the modules called by the lookup handlers that have to be
+    /// declared by a prelude.
+    pub fn modules(&self) -> &[&'static str] {
+        &self.modules
+    }
+
+    pub fn tags(&self) -> &[&'static str] {
+        &self.tags
+    }
+
+    /// Returns the number of constants that are always read.
+    pub fn required_constants(&self) -> usize {
+        self.constants
+            .iter()
+            .position(|kind| kind.is_variable())
+            .map_or(self.constants.len(), |idx| idx + 1)
+    }
+
+    /// Checks that there are enough constants and that each can be parsed as
+    /// its kind.
+    ///
+    /// Extra constants are accepted because inputs that don't implement
+    /// [`ReadsConstants`] may read them.
+    pub fn check_constants(&self, constants: &[String]) -> Result<(), ConstantsError> {
+        let required = self.required_constants();
+        if constants.len() < required {
+            return Err(ConstantsError::Missing {
+                expected: required,
+                actual: constants.len(),
+            });
+        }
+        std::iter::zip(&self.constants[..required], constants)
+            .enumerate()
+            .find(|(_, (kind, constant))| !kind.accepts(constant))
+            .map_or(Ok(()), |(index, (&kind, constant))| {
+                Err(ConstantsError::Invalid {
+                    index,
+                    constant: constant.clone(),
+                    kind,
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constants(constants: &[&str]) -> Vec<String> {
+        constants.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn kinds_accept_their_values() {
+        assert!(ConstantKind::Bool.accepts("true"));
+        assert!(!ConstantKind::Bool.accepts("1"));
+        assert!(ConstantKind::U8.accepts("255"));
+        assert!(!ConstantKind::U8.accepts("256"));
+        assert!(ConstantKind::Integer.accepts("340282366920938463463374607431768211456"));
+        assert!(ConstantKind::Field.accepts("0"));
+        assert!(!ConstantKind::Field.accepts(""));
+        assert!(!ConstantKind::Field.accepts("-1"));
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn primitives_read_one_constant() {
+        assert_eq!(
+            (&Probe::<usize>(PhantomData)).constants(),
+            &[ConstantKind::Usize]
+        );
+        assert_eq!((&Probe::<String>(PhantomData)).constants(), &[]);
+    }
+
+    #[test]
+    fn checks_count_and_kind() {
+        let metadata =
+            Metadata::default().with_constants(vec![ConstantKind::Field, ConstantKind::U8]);
+        assert!(metadata.check_constants(&constants(&["12", "3", "extra"])).is_ok());
+        assert!(matches!(
+            metadata.check_constants(&constants(&["12"])),
+            Err(ConstantsError::Missing {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            metadata.check_constants(&constants(&["12", "x"])),
+            Err(ConstantsError::Invalid { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn stops_checking_after_variable_constants() {
+        let metadata = Metadata::default().with_constants(vec![
+            ConstantKind::Flag,
+            ConstantKind::Field,
+            ConstantKind::Field,
+        ]);
+        assert_eq!(metadata.required_constants(), 1);
+        assert!(metadata.check_constants(&constants(&["false"])).is_ok());
+        assert!(metadata.check_constants(&constants(&["1"])).is_err());
+    }
+}
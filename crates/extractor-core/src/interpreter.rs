@@ -0,0 +1,829 @@
+//! Evaluation of the resolved IR over concrete values.
+//!
+//! The [`Interpreter`] checks that an assignment of the circuit satisfies every
+//! statement of its IR. Calls to other groups are checked recursively with the
+//! values of their inputs and outputs at the callsite. Calls to modules that
+//! have no body, like the ones the lookup callbacks emit, are evaluated with a
+//! [`ReferenceModule`] registered under the name of the module.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+};
+
+use ff::PrimeField;
+use haloumi_core::{
+    cmp::CmpOp,
+    felt::{Felt, Prime},
+    slot::{arg::ArgNo, output::OutputId, Slot},
+};
+use haloumi_ir::{
+    expr::IRAexpr,
+    groups::IRGroup,
+    meta::{HasMeta as _, Meta},
+    stmt::IRStmt,
+};
+use haloumi_ir_gen::circuit::resolved::ResolvedIRCircuit;
+use haloumi_lowering::{
+    backend_err,
+    lowerable::{LowerableExpr as _, LowerableStmt as _},
+    ExprLowering, Lowering,
+};
+
+use reference::ReferenceModule;
+
+use crate::lookups::{LookupHandlers, LookupTable};
+
+pub mod reference;
+
+/// Values assigned to the circuit.
+///
+/// The inputs and outputs follow the order of the main group, instance cells
+/// first and advice cells second. Any other cell the IR refers to must be set
+/// explicitly. Temporaries and the outputs of calls are computed by the
+/// interpreter.
+///
+/// Challenges are indexed like in the constraint system and are shared by
+/// every group, regardless of the argument they are bound to.
+#[derive(Debug, Clone, Default)]
+pub struct Witness {
+    inputs: Vec<Felt>,
+    outputs: Vec<Felt>,
+    cells: HashMap<Slot, Felt>,
+    challenges: Vec<Felt>,
+}
+
+impl Witness {
+    pub fn new(
+        inputs: impl IntoIterator<Item = Felt>,
+        outputs: impl IntoIterator<Item = Felt>,
+    ) -> Self {
+        Self {
+            inputs: inputs.into_iter().collect(),
+            outputs: outputs.into_iter().collect(),
+            cells: HashMap::new(),
+            challenges: vec![],
+        }
+    }
+
+    /// Sets the value of a cell.
+    pub fn set(&mut self, slot: impl Into<Slot>, value: Felt) {
+        self.cells.insert(slot.into(), value);
+    }
+
+    /// Sets the value of an advice cell by its absolute coordinates.
+    pub fn set_advice(&mut self, col: usize, row: usize, value: Felt) {
+        self.set(Slot::advice_abs(col, row), value);
+    }
+
+    pub fn with_cells(mut self, cells: impl IntoIterator<Item = (Slot, Felt)>) -> Self {
+        self.cells.extend(cells);
+        self
+    }
+
+    /// Sets the values of the challenges the advice cells were computed with.
+    pub fn with_challenges(mut self, challenges: impl IntoIterator<Item = Felt>) -> Self {
+        self.challenges = challenges.into_iter().collect();
+        self
+    }
+}
+
+/// A statement that the witness doesn't satisfy.
+#[derive(Debug)]
+pub struct Violation {
+    group: String,
+    meta: Meta,
+    statement: String,
+    reason: String,
+}
+
+impl Violation {
+    /// Name of the group the statement belongs to.
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    /// Metadata of the statement, with the region and row it came from.
+    pub fn meta(&self) -> Meta {
+        self.meta
+    }
+
+    /// The violated statement as printed by the IR.
+    pub fn statement(&self) -> &str {
+        &self.statement
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Statement violated in group {} at {}: {}\n  {}",
+            self.group, self.meta, self.reason, self.statement
+        )
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InterpreterError {
+    #[error("{0}")]
+    Violation(Box<Violation>),
+    #[error("No value for {slot} in group {group} at {meta}")]
+    Unassigned {
+        slot: Slot,
+        group: String,
+        meta: Meta,
+    },
+    #[error("No reference implementation for module {0}")]
+    UnknownModule(String),
+    #[error("Module {module} returned {returned} outputs but the call expects {expected}")]
+    ModuleArity {
+        module: String,
+        returned: usize,
+        expected: usize,
+    },
+    #[error("Group {group} expects {expected:?} inputs and outputs but got {found:?}")]
+    GroupArity {
+        group: String,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    #[error("Expected a {expected} expression in group {group} at {meta}")]
+    IllTyped {
+        expected: &'static str,
+        group: String,
+        meta: Meta,
+    },
+    #[error("{0} is not supported by the interpreter")]
+    Unsupported(&'static str),
+    #[error("Circuit has no main group")]
+    MissingMain,
+    #[error("Callsite {0} refers to a group that is not in the circuit")]
+    MissingGroup(String),
+    #[error(transparent)]
+    Lowering(#[from] haloumi_lowering::error::Error),
+}
+
+/// Checks witnesses against the IR of a circuit.
+pub struct Interpreter<'ir> {
+    groups: &'ir [IRGroup<IRAexpr>],
+    prime: Prime,
+    modules: HashMap<String, Box<dyn ReferenceModule + 'ir>>,
+}
+
+impl<'ir> Interpreter<'ir> {
+    pub fn new(ir: &'ir ResolvedIRCircuit) -> Self {
+        Self::from_groups(ir.groups(), ir.prime())
+    }
+
+    fn from_groups(groups: &'ir [IRGroup<IRAexpr>], prime: Prime) -> Self {
+        Self {
+            groups,
+            prime,
+            modules: HashMap::new(),
+        }
+    }
+
+    /// Evaluates the calls to the module with the given name using `module`.
+    pub fn with_module(
+        mut self,
+        name: impl Into<String>,
+        module: impl ReferenceModule + 'ir,
+    ) -> Self {
+        self.modules.insert(name.into(), Box::new(module));
+        self
+    }
+
+    /// Evaluates the calls to the modules of the lookup handlers with their
+    /// reference implementations.
+    pub fn with_lookup_modules<F: PrimeField>(
+        mut self,
+        lookups: &dyn LookupHandlers<F>,
+        tables: &[LookupTable],
+    ) -> Self {
+        self.modules.extend(
+            lookups
+                .reference_modules(tables)
+                .into_iter()
+                .map(|(name, module)| (name.to_owned(), module)),
+        );
+        self
+    }
+
+    /// Checks every statement of the circuit with the given witness.
+    ///
+    /// Returns [`InterpreterError::Violation`] with the first statement that
+    /// doesn't hold.
+    pub fn check(&self, witness: &Witness) -> Result<(), InterpreterError> {
+        let main = self.groups.iter().find(|g| g.is_main()).ok_or(InterpreterError::MissingMain)?;
+        let normalize = |values: &[Felt]| values.iter().map(|v| *v % self.prime).collect();
+        self.check_group(
+            main,
+            normalize(&witness.inputs),
+            normalize(&witness.outputs),
+            witness,
+        )
+    }
+
+    fn check_group(
+        &self,
+        group: &IRGroup<IRAexpr>,
+        args: Vec<Felt>,
+        outputs: Vec<Felt>,
+        witness: &Witness,
+    ) -> Result<(), InterpreterError> {
+        if (args.len(), outputs.len()) != (group.input_count(), group.output_count()) {
+            return Err(InterpreterError::GroupArity {
+                group: group.name().to_owned(),
+                expected: (group.input_count(), group.output_count()),
+                found: (args.len(), outputs.len()),
+            });
+        }
+        log::debug!("Checking group {}", group.name());
+        Frame {
+            interpreter: self,
+            group,
+            witness,
+            args,
+            outputs,
+            locals: Default::default(),
+            phase: Cell::new(Phase::Calls),
+            current: Cell::new(None),
+            constraints: Cell::new(0),
+            failure: Default::default(),
+        }
+        .run()
+    }
+
+    fn eval_module(
+        &self,
+        name: &str,
+        inputs: &[Felt],
+    ) -> Result<Result<Vec<Felt>, String>, InterpreterError> {
+        let module = self
+            .modules
+            .get(name)
+            .ok_or_else(|| InterpreterError::UnknownModule(name.to_owned()))?;
+        let inputs = inputs.iter().map(|i| i.as_ref().clone()).collect::<Vec<_>>();
+        Ok(module.eval(&inputs).map(|outputs| {
+            outputs.into_iter().map(|o| self.prime.felt(o % self.prime.value())).collect()
+        }))
+    }
+}
+
+/// Value of an expression.
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Field(Felt),
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Field(felt) => write!(f, "{felt}"),
+            Value::Bool(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    /// Computes the outputs of the calls to modules. Statements are evaluated
+    /// in order until no call can be evaluated anymore since a call can use
+    /// temporaries that are outputs of later calls.
+    Calls,
+    /// Checks every statement.
+    Check,
+}
+
+/// Evaluation of a single group.
+struct Frame<'a, 'ir> {
+    interpreter: &'a Interpreter<'ir>,
+    group: &'a IRGroup<IRAexpr>,
+    witness: &'a Witness,
+    args: Vec<Felt>,
+    outputs: Vec<Felt>,
+    /// Temporaries and outputs of calls.
+    locals: RefCell<HashMap<Slot, Felt>>,
+    phase: Cell<Phase>,
+    /// Statement being evaluated.
+    current: Cell<Option<&'a IRStmt<IRAexpr>>>,
+    constraints: Cell<usize>,
+    /// Reason why the evaluation of the statement stopped.
+    failure: RefCell<Option<InterpreterError>>,
+}
+
+/// Result type of the lowering methods.
+type LResult<T> = haloumi_lowering::Result<T>;
+
+#[derive(Debug, thiserror::Error)]
+#[error("evaluation stopped")]
+struct Stopped;
+
+impl<'a> Frame<'a, '_> {
+    fn run(&self) -> Result<(), InterpreterError> {
+        for callsite in self.group.callsites() {
+            let eval_all = |exprs: &[IRAexpr]| {
+                exprs.iter().map(|e| self.eval_field(e.clone())).collect::<Result<Vec<_>, _>>()
+            };
+            let inputs = eval_all(callsite.inputs())?;
+            let outputs = eval_all(callsite.outputs())?;
+            let callee = self
+                .interpreter
+                .groups
+                .iter()
+                .find(|g| g.id() == callsite.callee_id())
+                .ok_or_else(|| InterpreterError::MissingGroup(callsite.name().to_owned()))?;
+            self.interpreter.check_group(callee, inputs, outputs.clone(), self.witness)?;
+            self.locals
+                .borrow_mut()
+                .extend(callsite.output_vars().iter().copied().zip(outputs));
+        }
+
+        loop {
+            let assigned = self.locals.borrow().len();
+            for stmt in self.group.statements() {
+                // Statements that use values not computed yet are retried in the
+                // next iteration.
+                let _ = self.eval_stmt(stmt);
+            }
+            if self.locals.borrow().len() == assigned {
+                break;
+            }
+        }
+
+        self.phase.set(Phase::Check);
+        for stmt in self.group.statements() {
+            self.eval_stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn eval_stmt(&self, stmt: &'a IRStmt<IRAexpr>) -> Result<(), InterpreterError> {
+        self.current.set(Some(stmt));
+        self.failure.take();
+        stmt.clone()
+            .lower(self)
+            .map_err(|err| self.failure.take().unwrap_or(err.into()))
+    }
+
+    fn eval_field(&self, expr: IRAexpr) -> Result<Felt, InterpreterError> {
+        self.current.set(None);
+        self.failure.take();
+        expr.lower(self)
+            .and_then(|value| self.field(&value))
+            .map_err(|err| self.failure.take().unwrap_or(err.into()))
+    }
+
+    /// Records the reason of the failure and returns an error that stops the
+    /// lowering of the statement.
+    fn fail(&self, err: InterpreterError) -> haloumi_lowering::error::Error {
+        *self.failure.borrow_mut() = Some(err);
+        backend_err!(Stopped)
+    }
+
+    fn violation(&self, reason: String) -> haloumi_lowering::error::Error {
+        self.fail(InterpreterError::Violation(Box::new(Violation {
+            group: self.group.name().to_owned(),
+            meta: self.meta(),
+            statement: self.current.get().map(|stmt| format!("{stmt:?}")).unwrap_or_default(),
+            reason,
+        })))
+    }
+
+    fn meta(&self) -> Meta {
+        self.current.get().map(|stmt| *stmt.meta()).unwrap_or_default()
+    }
+
+    fn check(&self, holds: bool, reason: impl FnOnce() -> String) -> LResult<()> {
+        if self.phase.get() == Phase::Check && !holds {
+            return Err(self.violation(reason()));
+        }
+        Ok(())
+    }
+
+    fn ill_typed(&self, expected: &'static str) -> haloumi_lowering::error::Error {
+        self.fail(InterpreterError::IllTyped {
+            expected,
+            group: self.group.name().to_owned(),
+            meta: self.meta(),
+        })
+    }
+
+    fn field(&self, value: &Value) -> LResult<Felt> {
+        match value {
+            Value::Field(felt) => Ok(*felt),
+            Value::Bool(_) => Err(self.ill_typed("field")),
+        }
+    }
+
+    fn bool(&self, value: &Value) -> LResult<bool> {
+        match value {
+            Value::Bool(b) => Ok(*b),
+            Value::Field(_) => Err(self.ill_typed("boolean")),
+        }
+    }
+
+    fn arith(&self, lhs: &Value, rhs: &Value, op: impl Fn(Felt, Felt) -> Felt) -> LResult<Value> {
+        Ok(Value::Field(op(self.field(lhs)?, self.field(rhs)?)))
+    }
+
+    fn logic(&self, lhs: &Value, rhs: &Value, op: impl Fn(bool, bool) -> bool) -> LResult<Value> {
+        Ok(Value::Bool(op(self.bool(lhs)?, self.bool(rhs)?)))
+    }
+
+    fn cmp(&self, op: CmpOp, lhs: &Value, rhs: &Value) -> LResult<bool> {
+        // Inequalities compare the canonical representatives of the elements,
+        // as Picus does.
+        let (lhs, rhs) = (self.field(lhs)?, self.field(rhs)?);
+        let (lhs, rhs) = (lhs.as_ref(), rhs.as_ref());
+        Ok(match op {
+            CmpOp::Eq => lhs == rhs,
+            CmpOp::Lt => lhs < rhs,
+            CmpOp::Le => lhs <= rhs,
+            CmpOp::Gt => lhs > rhs,
+            CmpOp::Ge => lhs >= rhs,
+            CmpOp::Ne => lhs != rhs,
+        })
+    }
+
+    fn slot_value(&self, slot: Slot) -> Option<Felt> {
+        match slot {
+            Slot::Arg(arg) => self.args.get(*arg).copied(),
+            Slot::Output(output) => self.outputs.get(*output).copied(),
+            Slot::Temp(_) | Slot::CallOutput(..) => self.locals.borrow().get(&slot).copied(),
+            Slot::Challenge(index, ..) => {
+                self.witness.challenges.get(index).map(|v| *v % self.interpreter.prime)
+            }
+            _ => self.witness.cells.get(&slot).map(|v| *v % self.interpreter.prime),
+        }
+    }
+}
+
+impl Lowering for Frame<'_, '_> {
+    fn generate_constraint(&self, op: CmpOp, lhs: &Value, rhs: &Value) -> LResult<()> {
+        self.constraints.set(self.constraints.get() + 1);
+        let holds = self.phase.get() == Phase::Calls || self.cmp(op, lhs, rhs)?;
+        self.check(holds, || format!("{lhs} {op} {rhs} is false"))
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.constraints.get()
+    }
+
+    fn generate_comment(&self, _: String) -> LResult<()> {
+        Ok(())
+    }
+
+    fn generate_assume_deterministic(&self, _: Slot) -> LResult<()> {
+        Ok(())
+    }
+
+    fn generate_call(&self, name: &str, inputs: &[Value], outputs: &[Slot]) -> LResult<()> {
+        if self.phase.get() == Phase::Calls && outputs.iter().all(|o| self.slot_value(*o).is_some())
+        {
+            return Ok(());
+        }
+        let inputs = inputs.iter().map(|i| self.field(i)).collect::<LResult<Vec<_>>>()?;
+        let results =
+            match self.interpreter.eval_module(name, &inputs).map_err(|err| self.fail(err))? {
+                Ok(results) => results,
+                Err(reason) => {
+                    return match self.phase.get() {
+                        Phase::Calls => Ok(()),
+                        Phase::Check => {
+                            Err(self.violation(format!("call to {name} failed: {reason}")))
+                        }
+                    }
+                }
+            };
+        if results.len() != outputs.len() {
+            return Err(self.fail(InterpreterError::ModuleArity {
+                module: name.to_owned(),
+                returned: results.len(),
+                expected: outputs.len(),
+            }));
+        }
+        for (slot, result) in outputs.iter().zip(results) {
+            match self.slot_value(*slot) {
+                Some(value) => self.check(value == result, || {
+                    format!("{name} returned {result} but {slot} is {value}")
+                })?,
+                None => {
+                    self.locals.borrow_mut().insert(*slot, result);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn generate_assert(&self, expr: &Value) -> LResult<()> {
+        let holds = self.bool(expr)?;
+        self.check(holds, || "assertion is false".to_owned())
+    }
+
+    fn generate_post_condition(&self, expr: &Value) -> LResult<()> {
+        let holds = self.bool(expr)?;
+        self.check(holds, || "post-condition is false".to_owned())
+    }
+}
+
+impl ExprLowering for Frame<'_, '_> {
+    type CellOutput = Value;
+
+    fn lower_sum(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.arith(lhs, rhs, |l, r| l + r)
+    }
+
+    fn lower_product(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.arith(lhs, rhs, |l, r| l * r)
+    }
+
+    fn lower_neg(&self, expr: &Value) -> LResult<Value> {
+        Ok(Value::Field(-self.field(expr)?))
+    }
+
+    fn lower_constant(&self, f: Felt) -> LResult<Value> {
+        Ok(Value::Field(f % self.interpreter.prime))
+    }
+
+    fn lower_eq(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.cmp(CmpOp::Eq, lhs, rhs).map(Value::Bool)
+    }
+
+    fn lower_lt(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.cmp(CmpOp::Lt, lhs, rhs).map(Value::Bool)
+    }
+
+    fn lower_le(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.cmp(CmpOp::Le, lhs, rhs).map(Value::Bool)
+    }
+
+    fn lower_gt(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.cmp(CmpOp::Gt, lhs, rhs).map(Value::Bool)
+    }
+
+    fn lower_ge(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.cmp(CmpOp::Ge, lhs, rhs).map(Value::Bool)
+    }
+
+    fn lower_ne(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.cmp(CmpOp::Ne, lhs, rhs).map(Value::Bool)
+    }
+
+    fn lower_and(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.logic(lhs, rhs, |l, r| l && r)
+    }
+
+    fn lower_or(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.logic(lhs, rhs, |l, r| l || r)
+    }
+
+    fn lower_not(&self, value: &Value) -> LResult<Value> {
+        Ok(Value::Bool(!self.bool(value)?))
+    }
+
+    fn lower_true(&self) -> LResult<Value> {
+        Ok(Value::Bool(true))
+    }
+
+    fn lower_false(&self) -> LResult<Value> {
+        Ok(Value::Bool(false))
+    }
+
+    fn lower_det(&self, _: &Value) -> LResult<Value> {
+        // Determinism is a property of all the witnesses of the circuit and
+        // holds trivially for a single one.
+        Ok(Value::Bool(true))
+    }
+
+    fn lower_implies(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.logic(lhs, rhs, |l, r| !l || r)
+    }
+
+    fn lower_iff(&self, lhs: &Value, rhs: &Value) -> LResult<Value> {
+        self.logic(lhs, rhs, |l, r| l == r)
+    }
+
+    fn lower_function_input(&self, i: usize) -> Slot {
+        ArgNo::from(i).into()
+    }
+
+    fn lower_function_output(&self, o: usize) -> Slot {
+        OutputId::from(o).into()
+    }
+
+    fn lower_funcio<IO>(&self, io: IO) -> LResult<Value>
+    where
+        IO: Into<Slot>,
+    {
+        let slot = io.into();
+        self.slot_value(slot).map(Value::Field).ok_or_else(|| {
+            self.fail(InterpreterError::Unassigned {
+                slot,
+                group: self.group.name().to_owned(),
+                meta: self.meta(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use haloumi_ir::{expr::IRBexpr, groups::callsite::CallSite};
+    use num_bigint::BigUint;
+
+    use super::{reference::Spread, *};
+    use crate::{
+        fields::Blstrs,
+        lookups::callbacks::{
+            automaton::AutomatonLookup, mux::LookupMux, plain_spread::PlainSpreadLookup,
+        },
+    };
+
+    fn felt(n: u64) -> Felt {
+        Felt::new(Blstrs::from(n))
+    }
+
+    fn arg(n: usize) -> IRAexpr {
+        IRAexpr::slot(ArgNo::from(n))
+    }
+
+    fn out(n: usize) -> IRAexpr {
+        IRAexpr::slot(OutputId::from(n))
+    }
+
+    /// Sets the copy constraints of the group to a statement located at a gate.
+    ///
+    /// The statement is set directly since building a sequence drops the
+    /// metadata of its statements.
+    fn with_gate_stmt(
+        mut group: IRGroup<IRAexpr>,
+        mut stmt: IRStmt<IRAexpr>,
+        name: &str,
+        row: usize,
+    ) -> IRGroup<IRAexpr> {
+        stmt.meta_mut().at_gate(name, "region", None, Some(row));
+        *group.eq_constraints_mut() = stmt;
+        group
+    }
+
+    /// Main group computing `out0 = in0 * in0` and `out1 = Spread(in0)` in
+    /// a subgroup.
+    fn groups() -> Vec<IRGroup<IRAexpr>> {
+        let main = IRGroup::new("main".to_owned(), 0)
+            .with_input_count(1)
+            .with_output_count(2)
+            .with_callsites([CallSite::new(
+                "spread".to_owned(),
+                1,
+                1,
+                vec![arg(0)],
+                vec![Slot::CallOutput(0, 0)],
+                vec![out(1)],
+            )]);
+        let spread = IRGroup::new("spread".to_owned(), 1)
+            .with_key(Some(1))
+            .with_input_count(1)
+            .with_output_count(1)
+            .with_lookups([
+                IRStmt::assert(IRBexpr::lt(arg(0), IRAexpr::constant(felt(256)))),
+                IRStmt::call("Spread", [arg(0)], [Slot::Temp(0)]),
+            ]);
+        vec![
+            with_gate_stmt(main, IRStmt::eq(out(0), arg(0) * arg(0)), "square", 3),
+            with_gate_stmt(
+                spread,
+                IRStmt::eq(out(0), IRAexpr::slot(Slot::Temp(0))),
+                "spread",
+                5,
+            ),
+        ]
+    }
+
+    fn check(inputs: &[u64], outputs: &[u64]) -> Result<(), InterpreterError> {
+        let groups = groups();
+        let interpreter = Interpreter::from_groups(&groups, Prime::new::<Blstrs>())
+            .with_module("Spread", Spread::new(2));
+        interpreter.check(&Witness::new(
+            inputs.iter().copied().map(felt),
+            outputs.iter().copied().map(felt),
+        ))
+    }
+
+    #[test]
+    fn accepts_valid_witness() {
+        check(&[3], &[9, 0b101]).unwrap();
+    }
+
+    #[test]
+    fn reports_first_violation() {
+        let Err(InterpreterError::Violation(violation)) = check(&[3], &[10, 0b100]) else {
+            panic!("Expected a violation");
+        };
+        assert_eq!(violation.group(), "spread");
+        assert_eq!(
+            violation.meta().to_string(),
+            "gate 'spread' @ region 'region'(unk) @ row 5"
+        );
+
+        let Err(InterpreterError::Violation(violation)) = check(&[3], &[10, 0b101]) else {
+            panic!("Expected a violation");
+        };
+        assert_eq!(violation.group(), "main");
+        assert_eq!(
+            violation.meta().to_string(),
+            "gate 'square' @ region 'region'(unk) @ row 3"
+        );
+
+        let Err(InterpreterError::Violation(violation)) = check(&[300], &[90000, 66640]) else {
+            panic!("Expected a violation");
+        };
+        assert_eq!(violation.group(), "spread");
+    }
+
+    #[test]
+    fn evaluates_challenges() {
+        // `out0 = in0 * challenge0`, with the challenge bound to the argument
+        // after the inputs as the IR generator does.
+        let challenge = IRAexpr::slot(Slot::Challenge(0, 1, ArgNo::from(1)));
+        let main = IRGroup::new("main".to_owned(), 0).with_input_count(1).with_output_count(1);
+        let groups = [with_gate_stmt(
+            main,
+            IRStmt::eq(out(0), arg(0) * challenge),
+            "rlc",
+            0,
+        )];
+        let interpreter = Interpreter::from_groups(&groups, Prime::new::<Blstrs>());
+        let witness = |output| Witness::new([felt(3)], [felt(output)]);
+
+        interpreter.check(&witness(21).with_challenges([felt(7)])).unwrap();
+        assert!(matches!(
+            interpreter.check(&witness(21).with_challenges([felt(8)])),
+            Err(InterpreterError::Violation(_))
+        ));
+        assert!(matches!(
+            interpreter.check(&witness(21)),
+            Err(InterpreterError::Unassigned { .. })
+        ));
+    }
+
+    #[test]
+    fn evaluates_modules_of_lookup_handlers() {
+        let groups = groups();
+        let witness = Witness::new([felt(3)], [felt(9), felt(0b101)]);
+        let interpreter = Interpreter::from_groups(&groups, Prime::new::<Blstrs>());
+        assert!(matches!(
+            interpreter.check(&witness),
+            Err(InterpreterError::UnknownModule(module)) if module == "Spread"
+        ));
+
+        let lookups = LookupMux::<Blstrs>::default()
+            .with_handlers("spread", PlainSpreadLookup::new("Spread", "Unspread"));
+        let interpreter = interpreter.with_lookup_modules(&lookups, &[]);
+        interpreter.check(&witness).unwrap();
+        assert!(matches!(
+            interpreter.check(&Witness::new([felt(3)], [felt(9), felt(0b110)])),
+            Err(InterpreterError::Violation(_))
+        ));
+    }
+
+    #[test]
+    fn automaton_transitions_come_from_tables() {
+        // `(out0, out1, out2) = Automaton(in0)`.
+        let temp = |n| IRAexpr::slot(Slot::Temp(n));
+        let main = IRGroup::new("main".to_owned(), 0)
+            .with_input_count(1)
+            .with_output_count(3)
+            .with_lookups([
+                IRStmt::call("Automaton", [arg(0)], (0..3).map(Slot::Temp)),
+                IRStmt::eq(out(0), temp(0)),
+                IRStmt::eq(out(1), temp(1)),
+                IRStmt::eq(out(2), temp(2)),
+            ]);
+        let groups = [main];
+        let row = |values: [u64; 4]| values.map(BigUint::from).to_vec();
+        let tables = [
+            LookupTable::new("automaton", vec![row([1, 2, 3, 4]), row([2, 3, 1, 0])]),
+            LookupTable::new("range", vec![row([5, 6, 7, 8])]),
+        ];
+        let lookups = LookupMux::<Blstrs>::default()
+            .with_handlers("automaton", AutomatonLookup::new("Automaton", 8));
+        let interpreter = Interpreter::from_groups(&groups, Prime::new::<Blstrs>())
+            .with_lookup_modules(&lookups, &tables);
+        let check = |input, outputs: [u64; 3]| {
+            interpreter.check(&Witness::new([felt(input)], outputs.map(felt)))
+        };
+
+        check(1, [2, 3, 4]).unwrap();
+        check(2, [3, 1, 0]).unwrap();
+        assert!(matches!(
+            check(2, [3, 1, 1]),
+            Err(InterpreterError::Violation(_))
+        ));
+        // Only the tables of the lookups dispatched to the handler are read.
+        assert!(check(5, [6, 7, 8]).is_err());
+    }
+}
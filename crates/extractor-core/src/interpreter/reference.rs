@@ -0,0 +1,233 @@
+//! Reference implementations of the modules that the lookup and shuffle
+//! callbacks call.
+//!
+//! These modules have no body in the IR. The Picus preludes only declare them
+//! and leave their semantics to the solver, so the interpreter needs a Rust
+//! implementation to compute their outputs.
+
+use std::collections::{HashMap, HashSet};
+
+use num_bigint::BigUint;
+
+/// Computes the outputs of a module from its inputs.
+pub trait ReferenceModule {
+    /// Returns the outputs of the module or a description of why the inputs
+    /// are not valid for it.
+    fn eval(&self, inputs: &[BigUint]) -> Result<Vec<BigUint>, String>;
+}
+
+impl<F> ReferenceModule for F
+where
+    F: Fn(&[BigUint]) -> Result<Vec<BigUint>, String>,
+{
+    fn eval(&self, inputs: &[BigUint]) -> Result<Vec<BigUint>, String> {
+        self(inputs)
+    }
+}
+
+fn single_input<'i>(module: &str, inputs: &'i [BigUint]) -> Result<&'i BigUint, String> {
+    match inputs {
+        [input] => Ok(input),
+        _ => Err(format!("{module} takes 1 input but got {}", inputs.len())),
+    }
+}
+
+/// Interleaves the bits of the input with zeros.
+///
+/// Bit `i` of the input becomes bit `stride * i` of the output. The SHA-256
+/// chips use a stride of 2 and the SHA-3 chips a stride of 3.
+#[derive(Debug, Clone, Copy)]
+pub struct Spread {
+    stride: u64,
+}
+
+impl Spread {
+    pub fn new(stride: u64) -> Self {
+        assert!(stride > 0, "Spread stride must be positive");
+        Self { stride }
+    }
+}
+
+impl ReferenceModule for Spread {
+    fn eval(&self, inputs: &[BigUint]) -> Result<Vec<BigUint>, String> {
+        let dense = single_input("Spread", inputs)?;
+        let mut spread = BigUint::ZERO;
+        for bit in 0..dense.bits() {
+            if dense.bit(bit) {
+                spread.set_bit(bit * self.stride, true);
+            }
+        }
+        Ok(vec![spread])
+    }
+}
+
+/// Inverse of [`Spread`].
+///
+/// Fails if any bit of the input is set outside the positions `Spread` writes.
+#[derive(Debug, Clone, Copy)]
+pub struct Unspread {
+    stride: u64,
+}
+
+impl Unspread {
+    pub fn new(stride: u64) -> Self {
+        assert!(stride > 0, "Unspread stride must be positive");
+        Self { stride }
+    }
+}
+
+impl ReferenceModule for Unspread {
+    fn eval(&self, inputs: &[BigUint]) -> Result<Vec<BigUint>, String> {
+        let spread = single_input("Unspread", inputs)?;
+        let mut dense = BigUint::ZERO;
+        for bit in 0..spread.bits() {
+            if !spread.bit(bit) {
+                continue;
+            }
+            if bit % self.stride != 0 {
+                return Err(format!(
+                    "{spread} is not a spread value with stride {}",
+                    self.stride
+                ));
+            }
+            dense.set_bit(bit / self.stride, true);
+        }
+        Ok(vec![dense])
+    }
+}
+
+/// Transitions of a parsing automaton.
+///
+/// Maps the value the automaton module receives to the three values it
+/// outputs, in the order of the `Automaton` prelude.
+#[derive(Debug, Clone, Default)]
+pub struct Automaton {
+    transitions: HashMap<BigUint, [BigUint; 3]>,
+}
+
+impl Automaton {
+    pub fn new(transitions: impl IntoIterator<Item = (BigUint, [BigUint; 3])>) -> Self {
+        Self {
+            transitions: transitions.into_iter().collect(),
+        }
+    }
+}
+
+impl ReferenceModule for Automaton {
+    fn eval(&self, inputs: &[BigUint]) -> Result<Vec<BigUint>, String> {
+        let state = single_input("Automaton", inputs)?;
+        self.transitions
+            .get(state)
+            .map(|outputs| outputs.to_vec())
+            .ok_or_else(|| format!("no transition for {state}"))
+    }
+}
+
+/// Rows of a lookup table.
+///
+/// Has no outputs and fails if the inputs are not a row of the table, like
+/// the modules generated by [`TableLookup`](crate::lookups::callbacks::table::TableLookup).
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    rows: HashSet<Vec<BigUint>>,
+}
+
+impl Table {
+    pub fn new(rows: impl IntoIterator<Item = Vec<BigUint>>) -> Self {
+        Self {
+            rows: rows.into_iter().collect(),
+        }
+    }
+}
+
+impl ReferenceModule for Table {
+    fn eval(&self, inputs: &[BigUint]) -> Result<Vec<BigUint>, String> {
+        if self.rows.contains(inputs) {
+            return Ok(vec![]);
+        }
+        let inputs = inputs.iter().map(ToString::to_string).collect::<Vec<_>>();
+        Err(format!("({}) is not a row of the table", inputs.join(", ")))
+    }
+}
+
+/// Multiset equality of two sequences of rows.
+///
+/// Has no outputs and fails unless the second half of the inputs has the same
+/// rows of `width` values as the first half in some order, like the modules
+/// called by [`MultisetEqShuffle`](crate::shuffles::MultisetEqShuffle).
+#[derive(Debug, Clone, Copy)]
+pub struct MultisetEq {
+    width: usize,
+}
+
+impl MultisetEq {
+    pub fn new(width: usize) -> Self {
+        assert!(width > 0, "MultisetEq width must be positive");
+        Self { width }
+    }
+}
+
+impl ReferenceModule for MultisetEq {
+    fn eval(&self, inputs: &[BigUint]) -> Result<Vec<BigUint>, String> {
+        if inputs.len() % (2 * self.width) != 0 {
+            return Err(format!(
+                "MultisetEq with rows of {} values got {} inputs",
+                self.width,
+                inputs.len()
+            ));
+        }
+        let (lhs, rhs) = inputs.split_at(inputs.len() / 2);
+        let mut lhs = lhs.chunks(self.width).collect::<Vec<_>>();
+        let mut rhs = rhs.chunks(self.width).collect::<Vec<_>>();
+        lhs.sort();
+        rhs.sort();
+        if lhs == rhs {
+            return Ok(vec![]);
+        }
+        Err("the shuffled rows are not a permutation of the input rows".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(module: &impl ReferenceModule, input: u64) -> Result<u64, String> {
+        let outputs = module.eval(&[BigUint::from(input)])?;
+        Ok(outputs[0].iter_u64_digits().next().unwrap_or_default())
+    }
+
+    #[test]
+    fn spread_roundtrip() {
+        assert_eq!(eval(&Spread::new(2), 0b1011), Ok(0b1000101));
+        assert_eq!(eval(&Spread::new(3), 0b11), Ok(0b1001));
+        assert_eq!(eval(&Unspread::new(2), 0b1000101), Ok(0b1011));
+        assert_eq!(eval(&Unspread::new(3), 0b1001), Ok(0b11));
+        assert_eq!(eval(&Spread::new(2), 0), Ok(0));
+        assert!(eval(&Unspread::new(2), 0b10).is_err());
+    }
+
+    #[test]
+    fn table_membership() {
+        let row = |a: u64, b: u64| vec![BigUint::from(a), BigUint::from(b)];
+        let table = Table::new([row(0, 1), row(2, 3)]);
+        assert_eq!(table.eval(&row(2, 3)), Ok(vec![]));
+        assert_eq!(
+            table.eval(&row(1, 0)),
+            Err("(1, 0) is not a row of the table".to_owned())
+        );
+        assert!(table.eval(&[BigUint::from(0u64)]).is_err());
+    }
+
+    #[test]
+    fn multiset_equality() {
+        let values = |values: &[u64]| values.iter().copied().map(BigUint::from).collect::<Vec<_>>();
+        let pairs = MultisetEq::new(2);
+        assert_eq!(pairs.eval(&values(&[1, 2, 3, 4, 3, 4, 1, 2])), Ok(vec![]));
+        assert!(pairs.eval(&values(&[1, 2, 3, 4, 1, 4, 3, 2])).is_err());
+        assert!(pairs.eval(&values(&[1, 2, 1, 2, 1, 2])).is_err());
+        let single = MultisetEq::new(1);
+        assert_eq!(single.eval(&values(&[1, 1, 2, 2, 1, 1])), Ok(vec![]));
+        assert!(single.eval(&values(&[1, 1, 2, 1, 2, 2])).is_err());
+    }
+}
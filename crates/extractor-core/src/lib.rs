@@ -3,6 +3,9 @@ pub mod chips;
 pub mod circuit;
 pub mod fields;
 pub mod harness;
+pub mod interpreter;
 pub mod lookups;
 mod patterns;
+pub mod shuffles;
+pub mod taxonomy;
 mod utils;
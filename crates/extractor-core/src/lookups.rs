@@ -1 +1,223 @@
+use std::fmt;
+
+use ff::PrimeField;
+use haloumi_ir_gen::lookups::callbacks::LookupCallbacks;
+use midnight_proofs::plonk::Expression;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use crate::interpreter::reference::ReferenceModule;
+
 pub mod callbacks;
+
+/// Handler that a lookup callback uses for a lookup.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Handler {
+    name: &'static str,
+    status: LookupStatus,
+}
+
+impl Handler {
+    /// Handler that encodes the lookup in the IR.
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            status: LookupStatus::Handled,
+        }
+    }
+
+    /// Handler that leaves the lookup out of the IR.
+    pub const fn ignoring(name: &'static str) -> Self {
+        Self {
+            name,
+            status: LookupStatus::Ignored,
+        }
+    }
+
+    /// Handler that can't tell whether it encodes the lookup in the IR.
+    pub const fn unverified(name: &'static str) -> Self {
+        Self {
+            name,
+            status: LookupStatus::Unverified,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn ignores(&self) -> bool {
+        self.status == LookupStatus::Ignored
+    }
+
+    /// Status of the lookups processed by the handler.
+    pub fn status(&self) -> LookupStatus {
+        self.status
+    }
+}
+
+/// Lookup callbacks that can tell which handler processes each lookup.
+pub trait LookupHandlers<F: PrimeField>: LookupCallbacks<F, Expression<F>> {
+    /// Returns the handler for the lookup with the given name, or `None` if
+    /// no handler matches it.
+    fn handler(&self, lookup: &str) -> Option<Handler>;
+
+    /// Returns every handler that the callbacks can use.
+    fn handlers(&self) -> Vec<Handler>;
+
+    /// Returns the modules that the IR of the handlers calls but doesn't
+    /// define. They have to be declared by a Picus prelude.
+    fn modules(&self) -> Vec<&'static str> {
+        vec![]
+    }
+
+    /// Returns the implementations of the [`modules`](Self::modules) that the
+    /// interpreter evaluates their calls with, given the tables of the lookups
+    /// the callbacks handle.
+    fn reference_modules(&self, _tables: &[LookupTable]) -> Vec<NamedReferenceModule> {
+        vec![]
+    }
+
+    /// Returns the modules that the handlers defined from the tables of the
+    /// lookups they handled. Unlike the [`modules`](Self::modules), their
+    /// body is known, so the output defines them instead of a prelude.
+    fn table_modules(&self) -> Vec<TableModule> {
+        vec![]
+    }
+}
+
+/// Reference implementation of a module and the name of the module.
+pub type NamedReferenceModule = (&'static str, Box<dyn ReferenceModule>);
+
+/// Rows of the table of a lookup, with the columns in the order of the
+/// inputs of the lookup.
+#[derive(Debug, Clone)]
+pub struct LookupTable {
+    name: String,
+    rows: Vec<Vec<BigUint>>,
+}
+
+impl LookupTable {
+    pub fn new(name: impl Into<String>, rows: Vec<Vec<BigUint>>) -> Self {
+        Self {
+            name: name.into(),
+            rows,
+        }
+    }
+
+    /// Name of the lookup.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rows(&self) -> &[Vec<BigUint>] {
+        &self.rows
+    }
+}
+
+/// Module generated from the table of a lookup.
+///
+/// The module takes the inputs of the lookup and asserts that they are equal
+/// to one of the rows of the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableModule {
+    name: &'static str,
+    inputs: usize,
+    rows: Vec<Vec<BigUint>>,
+}
+
+impl TableModule {
+    pub fn new(name: &'static str, inputs: usize, rows: Vec<Vec<BigUint>>) -> Self {
+        Self { name, inputs, rows }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Number of inputs of the module, which is the number of columns of the
+    /// table.
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    pub fn rows(&self) -> &[Vec<BigUint>] {
+        &self.rows
+    }
+}
+
+/// What happened to a lookup of the circuit during IR generation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupStatus {
+    Handled,
+    Ignored,
+    /// Processed by callbacks that don't say whether they encode the lookup.
+    Unverified,
+    Unmatched,
+}
+
+/// A lookup of the circuit and the handler that processed it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookupRecord {
+    name: String,
+    idx: usize,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    handler: Option<String>,
+    status: LookupStatus,
+}
+
+impl LookupRecord {
+    pub fn new(name: &str, idx: usize, handler: Option<Handler>) -> Self {
+        Self {
+            name: name.to_owned(),
+            idx,
+            handler: handler.map(|handler| handler.name().to_owned()),
+            status: handler.map_or(LookupStatus::Unmatched, |handler| handler.status()),
+        }
+    }
+
+    pub fn status(&self) -> LookupStatus {
+        self.status
+    }
+}
+
+impl fmt::Display for LookupRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "lookup {} \"{}\"", self.idx, self.name)?;
+        match (&self.handler, self.status) {
+            (Some(handler), LookupStatus::Ignored) => write!(f, " ignored by {handler}"),
+            (Some(handler), LookupStatus::Unverified) => {
+                write!(f, " passed to {handler}, which may not encode it")
+            }
+            (Some(handler), _) => write!(f, " handled by {handler}"),
+            (None, _) => write!(f, " has no handler"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_status() {
+        let handled = LookupRecord::new("range", 0, Some(Handler::new("tag-range")));
+        let ignored = LookupRecord::new("spread", 1, Some(Handler::ignoring("ignore")));
+        let unverified = LookupRecord::new("table", 3, Some(Handler::unverified("Plain")));
+        let unmatched = LookupRecord::new("automaton", 2, None);
+        assert_eq!(handled.status(), LookupStatus::Handled);
+        assert_eq!(ignored.status(), LookupStatus::Ignored);
+        assert_eq!(unverified.status(), LookupStatus::Unverified);
+        assert_eq!(unmatched.status(), LookupStatus::Unmatched);
+        assert_eq!(ignored.to_string(), "lookup 1 \"spread\" ignored by ignore");
+        assert_eq!(
+            unverified.to_string(),
+            "lookup 3 \"table\" passed to Plain, which may not encode it"
+        );
+        assert_eq!(
+            unmatched.to_string(),
+            "lookup 2 \"automaton\" has no handler"
+        );
+    }
+}
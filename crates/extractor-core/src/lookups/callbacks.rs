@@ -4,3 +4,4 @@ pub mod mux;
 pub mod plain_spread;
 pub mod plain_spread3;
 pub mod range;
+pub mod table;
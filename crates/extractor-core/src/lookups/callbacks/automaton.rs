@@ -11,6 +11,11 @@ use haloumi_ir_gen::lookups::callbacks::LookupResult;
 use haloumi_synthesis::lookups::Lookup;
 use midnight_proofs::plonk::Expression;
 
+use crate::{
+    interpreter::reference::Automaton,
+    lookups::{Handler, LookupHandlers, LookupTable, NamedReferenceModule},
+};
+
 /// Lookup callback that handles parsing automata.
 #[derive(Debug, Clone)]
 pub struct AutomatonLookup {
@@ -64,3 +69,27 @@ impl<F: PrimeField> LookupCallbacks<F, Expression<F>> for AutomatonLookup {
         Ok(stmt)
     }
 }
+
+impl<F: PrimeField> LookupHandlers<F> for AutomatonLookup {
+    fn handler(&self, _: &str) -> Option<Handler> {
+        Some(Handler::new("automaton"))
+    }
+
+    fn handlers(&self) -> Vec<Handler> {
+        vec![Handler::new("automaton")]
+    }
+
+    fn modules(&self) -> Vec<&'static str> {
+        vec![self.automaton_module]
+    }
+
+    /// The transitions are read from the tables, which map the value in the
+    /// first column to the values in the other three.
+    fn reference_modules(&self, tables: &[LookupTable]) -> Vec<NamedReferenceModule> {
+        let transitions = tables.iter().flat_map(LookupTable::rows).filter_map(|row| {
+            let (state, outputs) = row.split_first()?;
+            Some((state.clone(), outputs.to_vec().try_into().ok()?))
+        });
+        vec![(self.automaton_module, Box::new(Automaton::new(transitions)))]
+    }
+}
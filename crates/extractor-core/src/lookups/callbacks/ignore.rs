@@ -10,6 +10,8 @@ use haloumi_ir_gen::lookups::callbacks::LookupResult;
 use haloumi_synthesis::lookups::Lookup;
 use midnight_proofs::plonk::Expression;
 
+use crate::lookups::{Handler, LookupHandlers};
+
 /// Lookup callback that emits an empty statement.
 #[derive(Debug, Copy, Clone)]
 #[allow(dead_code)]
@@ -26,3 +28,13 @@ impl<F: PrimeField> LookupCallbacks<F, Expression<F>> for IgnoreLookup {
         Ok(IRStmt::empty())
     }
 }
+
+impl<F: PrimeField> LookupHandlers<F> for IgnoreLookup {
+    fn handler(&self, _: &str) -> Option<Handler> {
+        Some(Handler::ignoring("ignore"))
+    }
+
+    fn handlers(&self) -> Vec<Handler> {
+        vec![Handler::ignoring("ignore")]
+    }
+}
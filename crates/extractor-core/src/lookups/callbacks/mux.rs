@@ -11,6 +11,8 @@ use haloumi_ir_gen::{
 use haloumi_synthesis::lookups::Lookup;
 use midnight_proofs::plonk::Expression;
 
+use crate::lookups::{Handler, LookupHandlers, LookupTable, NamedReferenceModule, TableModule};
+
 pub trait LookupName: sealed::LookupNameSealed {
     fn check(&self, name: &str) -> bool;
 }
@@ -36,35 +38,58 @@ impl<T: Fn(&str) -> bool> LookupName for T {
 
 pub type LookupHandler<'a, F> = (
     Box<dyn LookupName + 'static>,
-    Box<dyn LookupCallbacks<F, Expression<F>> + 'a>,
+    Box<dyn LookupHandlers<F> + 'a>,
 );
 
 /// Stores several lookup callbacks and dispatches them based on the name of the lookup.
 #[derive(Default)]
 pub struct LookupMux<'a, F: PrimeField> {
     handlers: Vec<LookupHandler<'a, F>>,
-    fallback: Option<Box<dyn LookupCallbacks<F, Expression<F>> + 'a>>,
+    fallback: Option<Box<dyn LookupHandlers<F> + 'a>>,
 }
 
 impl<'a, F: PrimeField> LookupMux<'a, F> {
+    /// Dispatches the lookups that match the name to the callbacks.
+    ///
+    /// The mux can't tell how the callbacks handle the lookups, so they are
+    /// reported as unverified by a handler named after the type of the
+    /// callbacks, and fail the harness with strict lookups. Use
+    /// [`with_handlers`](Self::with_handlers) for callbacks that implement
+    /// [`LookupHandlers`].
     pub fn with(
+        self,
+        name: impl LookupName + 'static,
+        callbacks: impl LookupCallbacks<F, Expression<F>> + 'a,
+    ) -> Self {
+        self.with_handlers(name, Callbacks(callbacks))
+    }
+
+    /// Dispatches the lookups that match the name to the handlers.
+    pub fn with_handlers(
         mut self,
         name: impl LookupName + 'static,
-        handler: impl LookupCallbacks<F, Expression<F>> + 'a,
+        handlers: impl LookupHandlers<F> + 'a,
     ) -> Self {
-        self.handlers.push((Box::new(name), Box::new(handler)));
+        self.handlers.push((Box::new(name), Box::new(handlers)));
         self
     }
 
-    pub fn fallback(mut self, handler: impl LookupCallbacks<F, Expression<F>> + 'a) -> Self {
-        self.fallback = Some(Box::new(handler));
+    /// Dispatches the lookups that match no name to the callbacks, like
+    /// [`with`](Self::with).
+    pub fn fallback(self, callbacks: impl LookupCallbacks<F, Expression<F>> + 'a) -> Self {
+        self.fallback_handlers(Callbacks(callbacks))
+    }
+
+    /// Dispatches the lookups that match no name to the handlers.
+    pub fn fallback_handlers(mut self, handlers: impl LookupHandlers<F> + 'a) -> Self {
+        self.fallback = Some(Box::new(handlers));
         self
     }
 
     fn handler_for<'s, 'n: 's>(
         &'s self,
         name: &'n str,
-    ) -> Result<&'s (dyn LookupCallbacks<F, Expression<F>> + 'a), Error> {
+    ) -> Result<&'s (dyn LookupHandlers<F> + 'a), Error> {
         self.handlers
             .iter()
             .find_map(|(n, h)| n.check(name).then_some(h))
@@ -75,12 +100,7 @@ impl<'a, F: PrimeField> LookupMux<'a, F> {
 
     fn all_handlers(
         &self,
-    ) -> impl Iterator<
-        Item = (
-            &(dyn LookupName + 'static),
-            &(dyn LookupCallbacks<F, Expression<F>> + 'a),
-        ),
-    > {
+    ) -> impl Iterator<Item = (&(dyn LookupName + 'static), &(dyn LookupHandlers<F> + 'a))> {
         self.handlers
             .iter()
             .map(|(n, h)| (n.as_ref(), h.as_ref()))
@@ -136,6 +156,100 @@ impl<F: PrimeField> LookupCallbacks<F, Expression<F>> for LookupMux<'_, F> {
     }
 }
 
+impl<F: PrimeField> LookupHandlers<F> for LookupMux<'_, F> {
+    fn handler(&self, lookup: &str) -> Option<Handler> {
+        self.handler_for(lookup).ok()?.handler(lookup)
+    }
+
+    fn handlers(&self) -> Vec<Handler> {
+        let mut handlers = vec![];
+        for handler in self.all_handlers().flat_map(|(_, h)| h.handlers()) {
+            if !handlers.contains(&handler) {
+                handlers.push(handler);
+            }
+        }
+        handlers
+    }
+
+    fn modules(&self) -> Vec<&'static str> {
+        let mut modules = vec![];
+        for module in self.all_handlers().flat_map(|(_, h)| h.modules()) {
+            if !modules.contains(&module) {
+                modules.push(module);
+            }
+        }
+        modules
+    }
+
+    /// Each handler gets the tables of the lookups dispatched to it.
+    fn reference_modules(&self, tables: &[LookupTable]) -> Vec<NamedReferenceModule> {
+        let mut tables = tables.iter().map(Some).collect::<Vec<_>>();
+        let mut modules: Vec<NamedReferenceModule> = vec![];
+        for (name, handler) in self.all_handlers() {
+            let selected = tables
+                .iter_mut()
+                .filter_map(|t| t.take_if(|t| name.check(t.name())))
+                .cloned()
+                .collect::<Vec<_>>();
+            for (module, reference) in handler.reference_modules(&selected) {
+                if !modules.iter().any(|(m, _)| *m == module) {
+                    modules.push((module, reference));
+                }
+            }
+        }
+        modules
+    }
+
+    fn table_modules(&self) -> Vec<TableModule> {
+        let mut modules: Vec<TableModule> = vec![];
+        for module in self.all_handlers().flat_map(|(_, h)| h.table_modules()) {
+            if !modules.iter().any(|m| m.name() == module.name()) {
+                modules.push(module);
+            }
+        }
+        modules
+    }
+}
+
+/// Lookup callbacks that don't implement [`LookupHandlers`].
+///
+/// The handler is named after the type of the callbacks. Nothing says whether
+/// it encodes the lookups, so they are unverified. It is assumed to call no
+/// modules.
+struct Callbacks<C>(C);
+
+impl<F: PrimeField, C: LookupCallbacks<F, Expression<F>>> LookupCallbacks<F, Expression<F>>
+    for Callbacks<C>
+{
+    fn on_lookup<'syn>(
+        &self,
+        lookup: &'syn Lookup<Expression<F>>,
+        table: &dyn LookupTableGenerator<F>,
+        temps: &mut Temps,
+    ) -> LookupResult<'syn, Expression<F>> {
+        self.0.on_lookup(lookup, table, temps)
+    }
+
+    fn on_lookups<'syn>(
+        &self,
+        lookups: &[&'syn Lookup<Expression<F>>],
+        tables: &[&dyn LookupTableGenerator<F>],
+        temps: &mut Temps,
+    ) -> LookupResult<'syn, Expression<F>> {
+        self.0.on_lookups(lookups, tables, temps)
+    }
+}
+
+impl<F: PrimeField, C: LookupCallbacks<F, Expression<F>>> LookupHandlers<F> for Callbacks<C> {
+    fn handler(&self, _: &str) -> Option<Handler> {
+        Some(Handler::unverified(std::any::type_name::<C>()))
+    }
+
+    fn handlers(&self) -> Vec<Handler> {
+        vec![Handler::unverified(std::any::type_name::<C>())]
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 enum Error {
     #[error("Lookups {0} did not match any handler!")]
@@ -154,3 +268,63 @@ impl<F: PrimeField> std::fmt::Debug for LookupMux<'_, F> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fields::Blstrs,
+        lookups::callbacks::{
+            automaton::AutomatonLookup, ignore::IgnoreLookup, table::TableLookup,
+        },
+    };
+
+    #[test]
+    fn handlers_and_modules_are_deduplicated() {
+        let mux = LookupMux::<Blstrs>::default()
+            .with_handlers("a", TableLookup::new(8).with_module("Table"))
+            .with_handlers("b", AutomatonLookup::new("Automaton", 8))
+            .with_handlers("c", AutomatonLookup::new("Automaton", 8))
+            .with_handlers("d", TableLookup::new(8))
+            .fallback_handlers(IgnoreLookup);
+        assert_eq!(
+            mux.handlers(),
+            vec![
+                Handler::new("table"),
+                Handler::new("automaton"),
+                Handler::ignoring("ignore")
+            ]
+        );
+        // Table modules are defined by the output, so no prelude declares them.
+        assert_eq!(mux.modules(), vec!["Automaton"]);
+        assert!(mux.table_modules().is_empty());
+    }
+
+    /// Callbacks that only implement `LookupCallbacks`.
+    struct Plain;
+
+    impl LookupCallbacks<Blstrs, Expression<Blstrs>> for Plain {
+        fn on_lookup<'syn>(
+            &self,
+            _: &'syn Lookup<Expression<Blstrs>>,
+            _: &dyn LookupTableGenerator<Blstrs>,
+            _: &mut Temps,
+        ) -> LookupResult<'syn, Expression<Blstrs>> {
+            Ok(IRStmt::empty())
+        }
+    }
+
+    #[test]
+    fn plain_callbacks_are_unverified() {
+        let mux = LookupMux::<Blstrs>::default()
+            .with("a", Plain)
+            .with_handlers("b", TableLookup::new(8))
+            .fallback(Plain);
+        let plain = Handler::unverified(std::any::type_name::<Plain>());
+        assert_eq!(mux.handler("a"), Some(plain));
+        assert_eq!(mux.handler("b"), Some(Handler::new("table")));
+        assert_eq!(mux.handler("c"), Some(plain));
+        assert_eq!(mux.handlers(), vec![plain, Handler::new("table")]);
+        assert!(mux.modules().is_empty());
+    }
+}
@@ -15,7 +15,13 @@ use haloumi_ir_gen::{
 use haloumi_synthesis::lookups::Lookup;
 use midnight_proofs::plonk::Expression;
 
-use crate::lookups::callbacks::range::TagRangeLookup;
+use crate::{
+    interpreter::reference::{Spread, Unspread},
+    lookups::{
+        callbacks::range::TagRangeLookup, Handler, LookupHandlers, LookupTable,
+        NamedReferenceModule,
+    },
+};
 
 /// Lookup handler that adds a range check for a plain-spread pair and
 /// calls a module that declares that the latter is a functional dependency of the former.
@@ -132,6 +138,27 @@ impl<F: PrimeField> LookupCallbacks<F, Expression<F>> for PlainSpreadLookup<F> {
     }
 }
 
+impl<F: PrimeField> LookupHandlers<F> for PlainSpreadLookup<F> {
+    fn handler(&self, _: &str) -> Option<Handler> {
+        Some(Handler::new("plain-spread"))
+    }
+
+    fn handlers(&self) -> Vec<Handler> {
+        vec![Handler::new("plain-spread")]
+    }
+
+    fn modules(&self) -> Vec<&'static str> {
+        vec![self.spread_module, self.unspread_module]
+    }
+
+    fn reference_modules(&self, _: &[LookupTable]) -> Vec<NamedReferenceModule> {
+        vec![
+            (self.spread_module, Box::new(Spread::new(2))),
+            (self.unspread_module, Box::new(Unspread::new(2))),
+        ]
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 enum Error {
     #[error("Unexpected input. Was expecting {expected} lookups but got {actual}: {names:?}")]
@@ -12,6 +12,11 @@ use haloumi_synthesis::lookups::Lookup;
 use midnight_proofs::plonk::Expression;
 use std::borrow::Cow;
 
+use crate::{
+    interpreter::reference::{Spread, Unspread},
+    lookups::{Handler, LookupHandlers, LookupTable, NamedReferenceModule},
+};
+
 /// Lookup handler that adds a range check for a plain-spread pair and
 /// calls a module that declares that the latter is a functional dependency of the former.
 ///
@@ -365,6 +370,27 @@ impl<F: PrimeField, M: PlainSpreadLookup3Mode> LookupCallbacks<F, Expression<F>>
     }
 }
 
+impl<F: PrimeField, M: PlainSpreadLookup3Mode> LookupHandlers<F> for PlainSpreadLookup3<M> {
+    fn handler(&self, _: &str) -> Option<Handler> {
+        Some(Handler::new("plain-spread3"))
+    }
+
+    fn handlers(&self) -> Vec<Handler> {
+        vec![Handler::new("plain-spread3")]
+    }
+
+    fn modules(&self) -> Vec<&'static str> {
+        vec![self.spread_module, self.unspread_module]
+    }
+
+    fn reference_modules(&self, _: &[LookupTable]) -> Vec<NamedReferenceModule> {
+        vec![
+            (self.spread_module, Box::new(Spread::new(3))),
+            (self.unspread_module, Box::new(Unspread::new(3))),
+        ]
+    }
+}
+
 fn ensure_tag_is_constant_value<F: PrimeField>(
     expr: &Expression<F>,
     value: u64,
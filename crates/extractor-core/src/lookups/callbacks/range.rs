@@ -1,4 +1,4 @@
-use ff::Field;
+use ff::{Field, PrimeField};
 use haloumi_ir::meta::HasMeta;
 use haloumi_ir::{expr::IRBexpr, stmt::IRStmt};
 use haloumi_ir_gen::lookups::callbacks::LookupResult;
@@ -10,6 +10,8 @@ use haloumi_synthesis::lookups::Lookup;
 use midnight_proofs::plonk::Expression;
 use std::{array, borrow::Cow, collections::HashSet};
 
+use crate::lookups::{Handler, LookupHandlers};
+
 /// Maps a set of tag values to a set of ranges
 pub struct TagsToRangesMap<T, V, const TAGS: usize, const VALUES: usize>(
     Vec<([T; TAGS], [V; VALUES])>,
@@ -145,3 +147,15 @@ impl<const TAGS: usize, const VALUES: usize, F: Field> LookupCallbacks<F, Expres
         Ok(stmt)
     }
 }
+
+impl<F: PrimeField, const TAGS: usize, const VALUES: usize> LookupHandlers<F>
+    for TagRangeLookup<F, TAGS, VALUES>
+{
+    fn handler(&self, _: &str) -> Option<Handler> {
+        Some(Handler::new("tag-range"))
+    }
+
+    fn handlers(&self) -> Vec<Handler> {
+        vec![Handler::new("tag-range")]
+    }
+}
@@ -0,0 +1,249 @@
+use std::{borrow::Cow, cell::RefCell, collections::HashSet};
+
+use ff::PrimeField;
+use haloumi_ir::{expr::IRBexpr, meta::HasMeta as _, stmt::IRStmt};
+use haloumi_ir_gen::{
+    lookups::{
+        callbacks::{LookupCallbacks, LookupResult},
+        table::LookupTableGenerator,
+    },
+    temps::{ExprOrTemp, Temps},
+};
+use haloumi_synthesis::lookups::Lookup;
+use mdnt_support::fe_to_big;
+use midnight_proofs::plonk::Expression;
+
+use crate::{
+    interpreter::reference::Table,
+    lookups::{Handler, LookupHandlers, LookupTable, NamedReferenceModule, TableModule},
+};
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("Lookup '{name}' has {rows} distinct rows but at most {max_rows} can be expanded")]
+    TableTooLarge {
+        name: String,
+        rows: usize,
+        max_rows: usize,
+    },
+    #[error("Lookup '{name}' calls module {module} with a different table than a previous lookup")]
+    TableMismatch { name: String, module: &'static str },
+}
+
+/// Lookup callback that reads the fixed table of the lookup and asserts that
+/// the inputs are equal to one of its rows.
+///
+/// Tables with more than `max_rows` distinct rows are passed to the table
+/// module if there is one, and fail otherwise.
+#[derive(Debug, Clone)]
+pub struct TableLookup {
+    max_rows: usize,
+    module: Option<&'static str>,
+    /// Table module defined by the first lookup that called it.
+    defined: RefCell<Option<TableModule>>,
+}
+
+impl TableLookup {
+    /// Creates a new lookup handler that expands tables of up to `max_rows`
+    /// distinct rows.
+    pub fn new(max_rows: usize) -> Self {
+        Self {
+            max_rows,
+            module: None,
+            defined: RefCell::default(),
+        }
+    }
+
+    /// Calls the given module with the inputs of the lookup for the tables
+    /// that are too large to expand. The module is generated from the rows of
+    /// the table, so every lookup that calls it must have the same table.
+    pub fn with_module(mut self, module: &'static str) -> Self {
+        self.module = Some(module);
+        self
+    }
+
+    /// Records the table module called by the lookup, checking that it is the
+    /// same one the previous lookups called.
+    fn define(&self, lookup: &str, module: TableModule) -> Result<(), Error> {
+        let mut defined = self.defined.borrow_mut();
+        match defined.as_ref() {
+            Some(defined) if *defined != module => Err(Error::TableMismatch {
+                name: lookup.to_owned(),
+                module: module.name(),
+            }),
+            Some(_) => Ok(()),
+            None => {
+                *defined = Some(module);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Removes the repeated rows, keeping the first occurrence of each.
+///
+/// Tables are usually padded by repeating a row so the number of distinct rows
+/// is what matters for the size of the disjunction.
+fn distinct_rows<F: PrimeField>(rows: impl IntoIterator<Item = Vec<F>>) -> Vec<Vec<F>> {
+    let mut seen = HashSet::new();
+    rows.into_iter()
+        .filter(|row| {
+            seen.insert(row.iter().map(|f| f.to_repr().as_ref().to_vec()).collect::<Vec<_>>())
+        })
+        .collect()
+}
+
+/// Asserts that the inputs are equal to the values of one of the rows.
+///
+/// This is both the encoding of the expanded lookups and the body of the
+/// table modules.
+fn row_disjunction<E, V, I>(
+    inputs: impl Fn() -> I,
+    rows: impl IntoIterator<Item = Vec<V>>,
+    constant: impl Fn(V) -> E,
+) -> IRBexpr<E>
+where
+    I: Iterator<Item = E>,
+{
+    IRBexpr::or_many(rows.into_iter().map(|row| {
+        IRBexpr::and_many(
+            inputs().zip(row).map(|(input, value)| IRBexpr::eq(input, constant(value))),
+        )
+    }))
+}
+
+impl<F: PrimeField> LookupCallbacks<F, Expression<F>> for TableLookup {
+    fn on_lookup<'syn>(
+        &self,
+        lookup: &'syn Lookup<Expression<F>>,
+        table: &dyn LookupTableGenerator<F>,
+        _temps: &mut Temps,
+    ) -> LookupResult<'syn, Expression<F>> {
+        let queries = lookup.table_queries()?;
+        let rows = distinct_rows(
+            table.table()?.iter().map(|row| queries.iter().map(|q| row[*q]).collect()),
+        );
+        let inputs = || lookup.inputs().iter().map(Cow::Borrowed).map(ExprOrTemp::Expr);
+
+        let mut stmt = if rows.len() <= self.max_rows {
+            IRStmt::assert(row_disjunction(inputs, rows, |value| {
+                ExprOrTemp::Expr(Cow::Owned(Expression::Constant(value)))
+            }))
+        } else if let Some(module) = self.module {
+            let rows = rows.into_iter().map(|row| row.into_iter().map(fe_to_big).collect());
+            self.define(
+                lookup.name(),
+                TableModule::new(module, lookup.inputs().len(), rows.collect()),
+            )?;
+            IRStmt::call(module, inputs(), [])
+        } else {
+            return Err(Error::TableTooLarge {
+                name: lookup.name().to_owned(),
+                rows: rows.len(),
+                max_rows: self.max_rows,
+            }
+            .into());
+        };
+        stmt.meta_mut().at_lookup(lookup.name(), lookup.idx(), None);
+        Ok(stmt)
+    }
+}
+
+impl<F: PrimeField> LookupHandlers<F> for TableLookup {
+    fn handler(&self, _: &str) -> Option<Handler> {
+        Some(Handler::new("table"))
+    }
+
+    fn handlers(&self) -> Vec<Handler> {
+        vec![Handler::new("table")]
+    }
+
+    /// The module is only called for the tables that are too large to
+    /// expand.
+    fn reference_modules(&self, tables: &[LookupTable]) -> Vec<NamedReferenceModule> {
+        let Some(module) = self.module else {
+            return vec![];
+        };
+        let rows = tables
+            .iter()
+            .map(|table| table.rows().iter().cloned().collect::<HashSet<_>>())
+            .filter(|rows| rows.len() > self.max_rows)
+            .flatten();
+        vec![(module, Box::new(Table::new(rows)))]
+    }
+
+    fn table_modules(&self) -> Vec<TableModule> {
+        self.defined.borrow().iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::*;
+    use crate::fields::Blstrs;
+
+    #[test]
+    fn distinct_rows_keeps_first_occurrence() {
+        let row = |a: u64, b: u64| vec![Blstrs::from(a), Blstrs::from(b)];
+        let rows = distinct_rows([row(0, 0), row(1, 2), row(0, 0), row(2, 1), row(1, 2)]);
+        assert_eq!(rows, [row(0, 0), row(1, 2), row(2, 1)]);
+    }
+
+    #[test]
+    fn rows_are_a_disjunction_of_conjunctions() {
+        let inputs = || ["x", "y"].into_iter().map(str::to_owned);
+        let expr = row_disjunction(inputs, [vec![0, 1], vec![2, 3]], |v: u64| v.to_string());
+        let eq = |lhs: &str, rhs: &str| IRBexpr::eq(lhs.to_owned(), rhs.to_owned());
+        assert_eq!(
+            expr,
+            IRBexpr::or_many([
+                IRBexpr::and_many([eq("x", "0"), eq("y", "1")]),
+                IRBexpr::and_many([eq("x", "2"), eq("y", "3")]),
+            ])
+        );
+    }
+
+    #[test]
+    fn lookups_calling_the_module_share_its_table() {
+        let lookup = TableLookup::new(1).with_module("Table");
+        let table = |rows: &[[u64; 2]]| {
+            let rows = rows.iter().map(|row| row.iter().copied().map(BigUint::from).collect());
+            TableModule::new("Table", 2, rows.collect())
+        };
+        lookup.define("a", table(&[[0, 1], [2, 3]])).unwrap();
+        lookup.define("b", table(&[[0, 1], [2, 3]])).unwrap();
+        assert!(matches!(
+            lookup.define("c", table(&[[0, 1]])),
+            Err(Error::TableMismatch {
+                module: "Table",
+                ..
+            })
+        ));
+        assert_eq!(
+            LookupHandlers::<Blstrs>::table_modules(&lookup),
+            [table(&[[0, 1], [2, 3]])]
+        );
+    }
+
+    #[test]
+    fn reference_module_only_has_the_tables_that_are_not_expanded() {
+        let lookup = TableLookup::new(2).with_module("Table");
+        let big = |values: &[u64]| values.iter().copied().map(BigUint::from).collect();
+        let tables = [
+            LookupTable::new("small", vec![big(&[7]), big(&[8])]),
+            LookupTable::new("large", vec![big(&[1]), big(&[2]), big(&[3]), big(&[1])]),
+        ];
+        let modules = LookupHandlers::<Blstrs>::reference_modules(&lookup, &tables);
+        let [(name, table)] = modules.as_slice() else {
+            panic!("Expected a single module");
+        };
+        assert_eq!(*name, "Table");
+        assert!(table.eval(&big(&[3])).is_ok());
+        assert!(table.eval(&big(&[7])).is_err());
+        assert!(
+            LookupHandlers::<Blstrs>::reference_modules(&TableLookup::new(2), &tables).is_empty()
+        );
+    }
+}
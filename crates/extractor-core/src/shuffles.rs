@@ -0,0 +1,329 @@
+//! Encoding of the shuffle arguments of the circuit.
+//!
+//! A shuffle asserts that the rows of its shuffled expressions are a
+//! permutation of the rows of its input expressions. The constraint system
+//! that haloumi synthesizes has no shuffles, so they are read from the
+//! [`ConstraintSystem`](midnight_proofs::plonk::ConstraintSystem) the circuit
+//! is configured with and lowered after the rest of the IR, through
+//! [`ShuffleCallbacks`].
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use ff::{Field, PrimeField};
+use haloumi_core::{
+    info_traits::SelectorInfo as _,
+    table::{Any, Column, RegionIndex},
+};
+use haloumi_ir::stmt::IRStmt;
+use haloumi_ir_gen::expressions::ExpressionInRow;
+use haloumi_synthesis::{groups::Group, regions::RegionData};
+use midnight_proofs::plonk::Expression;
+
+/// Expression of a shuffle in one of the rows of a group, relative to the
+/// first row of the group.
+pub type ShuffleExpr<F> = ExpressionInRow<'static, Expression<F>, F>;
+
+/// Shuffle argument of the circuit, as configured.
+#[derive(Debug, Clone)]
+pub struct ShuffleArgument<F> {
+    name: String,
+    inputs: Vec<Expression<F>>,
+    shuffled: Vec<Expression<F>>,
+}
+
+impl<F> ShuffleArgument<F> {
+    pub fn new(
+        name: impl Into<String>,
+        inputs: Vec<Expression<F>>,
+        shuffled: Vec<Expression<F>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            inputs,
+            shuffled,
+        }
+    }
+}
+
+/// A shuffle argument in the rows of a group.
+///
+/// Rows where every expression is zero are left out, since they add the same
+/// row to both sides of the shuffle.
+#[derive(Debug)]
+pub struct Shuffle<'a, F: Field> {
+    name: &'a str,
+    idx: usize,
+    group: &'a str,
+    width: usize,
+    inputs: Vec<Vec<ShuffleExpr<F>>>,
+    shuffled: Vec<Vec<ShuffleExpr<F>>>,
+}
+
+impl<F: Field> Shuffle<'_, F> {
+    /// Name of the shuffle.
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Index of the shuffle in the constraint system.
+    pub fn idx(&self) -> usize {
+        self.idx
+    }
+
+    /// Name of the group whose rows the shuffle is lowered in.
+    pub fn group(&self) -> &str {
+        self.group
+    }
+
+    /// Number of expressions on each side of the shuffle.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows the shuffle is lowered in.
+    pub fn rows(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Input expressions of each row.
+    pub fn inputs(&self) -> &[Vec<ShuffleExpr<F>>] {
+        &self.inputs
+    }
+
+    /// Shuffled expressions of each row.
+    pub fn shuffled(&self) -> &[Vec<ShuffleExpr<F>>] {
+        &self.shuffled
+    }
+}
+
+/// Callbacks that encode the shuffles of the circuit in the IR.
+pub trait ShuffleCallbacks<F: PrimeField> {
+    /// Returns the IR that encodes the shuffle in the rows of a group.
+    fn on_shuffle(&self, shuffle: &Shuffle<F>) -> anyhow::Result<IRStmt<ShuffleExpr<F>>>;
+
+    /// Returns the modules that the IR of the shuffles calls. The output
+    /// defines them.
+    fn modules(&self) -> Vec<ShuffleModule> {
+        vec![]
+    }
+}
+
+/// Module that asserts that two sequences of rows are the same multiset.
+///
+/// The module takes the `width` values of each of the `rows` input rows,
+/// followed by the values of each of the `rows` shuffled rows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShuffleModule {
+    width: usize,
+    rows: usize,
+}
+
+impl ShuffleModule {
+    pub fn new(width: usize, rows: usize) -> Self {
+        Self { width, rows }
+    }
+
+    /// Name of the module, which depends on its shape since every module has a
+    /// fixed number of inputs.
+    pub fn name(&self) -> String {
+        format!("MultisetEq_{}x{}", self.width, self.rows)
+    }
+
+    /// Number of values in each row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of inputs of the module.
+    pub fn inputs(&self) -> usize {
+        2 * self.width * self.rows
+    }
+}
+
+/// Shuffle callbacks that call a [`ShuffleModule`] with the expressions of
+/// every row.
+#[derive(Debug, Default)]
+pub struct MultisetEqShuffle {
+    defined: RefCell<Vec<ShuffleModule>>,
+}
+
+impl<F: PrimeField> ShuffleCallbacks<F> for MultisetEqShuffle {
+    fn on_shuffle(&self, shuffle: &Shuffle<F>) -> anyhow::Result<IRStmt<ShuffleExpr<F>>> {
+        let module = ShuffleModule::new(shuffle.width(), shuffle.rows());
+        let args = shuffle.inputs().iter().chain(shuffle.shuffled()).flatten().cloned();
+        let call = IRStmt::call(module.name(), args, []);
+        let mut defined = self.defined.borrow_mut();
+        if !defined.contains(&module) {
+            defined.push(module);
+        }
+        Ok(IRStmt::seq([
+            IRStmt::comment(format!("shuffle {} \"{}\"", shuffle.idx(), shuffle.name())),
+            call,
+        ]))
+    }
+
+    fn modules(&self) -> Vec<ShuffleModule> {
+        self.defined.borrow().clone()
+    }
+}
+
+/// Lowers the shuffles in the rows of each group with the callbacks.
+///
+/// The rows of a shuffle are the rows of the regions of the group, and the IR
+/// is injected in the first of them. Selectors are resolved with the regions
+/// that cover each row, and advice cells in columns that none of them assigns
+/// are zero, so the IR only refers to assigned cells.
+///
+/// A group gets its own encoding of the shuffle, which is stronger than the
+/// shuffle if the rows of the circuit that it permutes are in different
+/// groups.
+pub(crate) fn lower_shuffles<F: PrimeField>(
+    shuffles: &[ShuffleArgument<F>],
+    groups: &[Group],
+    callbacks: &dyn ShuffleCallbacks<F>,
+) -> anyhow::Result<Vec<(RegionIndex, IRStmt<ShuffleExpr<F>>)>> {
+    let mut ir = vec![];
+    if shuffles.is_empty() {
+        return Ok(ir);
+    }
+    for group in groups {
+        let regions = group.regions();
+        let Some((first, start)) = regions
+            .iter()
+            .filter_map(|region| Some((region.index()?, region.start()?)))
+            .min_by_key(|(_, start)| *start)
+        else {
+            continue;
+        };
+        let mut rows = BTreeMap::<usize, Vec<RegionData>>::new();
+        for region in &regions {
+            for row in region.rows() {
+                rows.entry(row).or_default().push(*region);
+            }
+        }
+        for (idx, argument) in shuffles.iter().enumerate() {
+            let mut inputs = vec![];
+            let mut shuffled = vec![];
+            for (row, covering) in &rows {
+                let enabled = |selector: usize| {
+                    covering
+                        .iter()
+                        .any(|region| region.selectors_enabled_for_row(*row).contains(selector))
+                };
+                let assigned = |column: usize, rotation: i32| {
+                    let column = Column::new(column, Any::Advice);
+                    row.checked_add_signed(rotation as isize)
+                        .and_then(|row| rows.get(&row))
+                        .is_some_and(|covering| {
+                            covering.iter().any(|region| region.columns().contains(&column))
+                        })
+                };
+                let resolve = |exprs: &[Expression<F>]| -> Vec<_> {
+                    exprs.iter().map(|expr| resolve_row(expr, &enabled, &assigned)).collect()
+                };
+                let (lhs, rhs) = (resolve(&argument.inputs), resolve(&argument.shuffled));
+                if lhs.iter().chain(&rhs).all(is_zero) {
+                    continue;
+                }
+                let in_row = |exprs: Vec<_>| -> Vec<_> {
+                    exprs.into_iter().map(|expr| ExpressionInRow::new(row - start, expr)).collect()
+                };
+                inputs.push(in_row(lhs));
+                shuffled.push(in_row(rhs));
+            }
+            if inputs.is_empty() {
+                continue;
+            }
+            let shuffle = Shuffle {
+                name: &argument.name,
+                idx,
+                group: group.name(),
+                width: argument.inputs.len(),
+                inputs,
+                shuffled,
+            };
+            ir.push((first, callbacks.on_shuffle(&shuffle)?));
+        }
+    }
+    Ok(ir)
+}
+
+/// Replaces the selectors of the expression by whether they are enabled in the
+/// row, and the advice queries of unassigned cells by zero.
+fn resolve_row<F: Field>(
+    expr: &Expression<F>,
+    enabled: &impl Fn(usize) -> bool,
+    assigned: &impl Fn(usize, i32) -> bool,
+) -> Expression<F> {
+    let resolve = |expr: &Expression<F>| Box::new(resolve_row(expr, enabled, assigned));
+    match expr {
+        Expression::Selector(selector) => Expression::Constant(if enabled(selector.id()) {
+            F::ONE
+        } else {
+            F::ZERO
+        }),
+        Expression::Advice(query) if !assigned(query.column_index(), query.rotation().0) => {
+            Expression::Constant(F::ZERO)
+        }
+        Expression::Negated(expr) => Expression::Negated(resolve(expr)),
+        Expression::Sum(lhs, rhs) => Expression::Sum(resolve(lhs), resolve(rhs)),
+        Expression::Product(lhs, rhs) => Expression::Product(resolve(lhs), resolve(rhs)),
+        Expression::Scaled(expr, factor) => Expression::Scaled(resolve(expr), *factor),
+        expr => expr.clone(),
+    }
+}
+
+/// Whether the expression is zero for any value of its queries.
+fn is_zero<F: Field>(expr: &Expression<F>) -> bool {
+    match expr {
+        Expression::Constant(value) => value.is_zero_vartime(),
+        Expression::Negated(expr) => is_zero(expr),
+        Expression::Sum(lhs, rhs) => is_zero(lhs) && is_zero(rhs),
+        Expression::Product(lhs, rhs) => is_zero(lhs) || is_zero(rhs),
+        Expression::Scaled(expr, factor) => factor.is_zero_vartime() || is_zero(expr),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use midnight_proofs::{
+        plonk::{Advice, ColumnType as _, ConstraintSystem, FirstPhase},
+        poly::Rotation,
+    };
+
+    use super::*;
+    use crate::fields::Blstrs as F;
+
+    #[test]
+    fn disabled_selectors_make_the_expression_zero() {
+        let mut cs = ConstraintSystem::<F>::default();
+        let q = Expression::Selector(cs.selector());
+        let x = Advice::new(FirstPhase).query_cell::<F>(0, Rotation::cur());
+        let expr = q.clone() * x + q;
+        assert!(is_zero(&resolve_row(&expr, &|_| false, &|_, _| true)));
+        assert!(!is_zero(&resolve_row(&expr, &|_| true, &|_, _| true)));
+        assert!(!is_zero(&expr));
+    }
+
+    #[test]
+    fn unassigned_cells_are_zero() {
+        let x = Advice::new(FirstPhase).query_cell::<F>(0, Rotation::cur());
+        let y = Advice::new(FirstPhase).query_cell::<F>(1, Rotation::next());
+        let expr = x + y;
+        let assigned = |column: usize, rotation: i32| column == 0 && rotation == 0;
+        assert!(!is_zero(&resolve_row(&expr, &|_| true, &assigned)));
+        assert!(is_zero(&resolve_row(&expr, &|_| true, &|_, _| false)));
+    }
+
+    #[test]
+    fn modules_are_named_after_their_shape() {
+        let module = ShuffleModule::new(2, 3);
+        assert_eq!(module.name(), "MultisetEq_2x3");
+        assert_eq!(module.inputs(), 12);
+    }
+}
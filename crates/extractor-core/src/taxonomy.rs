@@ -0,0 +1,348 @@
+//! Instructions, chips and types that make up the names of the harnesses.
+//!
+//! A harness name has the form `instruction/method/chip/type`. The method is
+//! free-form but the other components must be one of the names declared here.
+//! The names are checked when the harness is registered, which happens at
+//! compile time, so a harness with a misspelled component fails to build
+//! instead of being impossible to select. The extractor CLI uses the same
+//! enums for its selection flags.
+
+use std::fmt;
+
+/// Number of `/`-separated components of a harness name.
+pub const NAME_COMPONENTS: usize = 4;
+
+/// Declares an enum with the name of each variant.
+///
+/// Besides the enum, generates:
+///
+/// - `ALL` with every variant.
+/// - `name` that returns the name of the variant.
+/// - A [`Display`](fmt::Display) implementation that writes the name, or the
+///   name with underscores instead of dashes in alternate form.
+/// - `find` that looks a name up inside a byte range in const context.
+///
+/// With the `clap` feature the enum can be used as a CLI value, which accepts
+/// the same names. The spellings after `|` are accepted as well on the command
+/// line, but not in harness names; they keep the values that the CLI took
+/// before the names were shared.
+macro_rules! taxonomy {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[cfg($cfg:meta)])? $variant:ident => $str:literal $(| $alias:literal)*,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+        #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+        pub enum $name {
+            $(
+                $(#[cfg($cfg)])?
+                #[cfg_attr(feature = "clap", value(name = $str $(, alias = $alias)*))]
+                $variant,
+            )*
+        }
+
+        impl $name {
+            /// Every variant, in declaration order.
+            pub const ALL: &[Self] = &[$($(#[cfg($cfg)])? Self::$variant,)*];
+
+            /// Returns the name used in harness names.
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $($(#[cfg($cfg)])? Self::$variant => $str,)*
+                }
+            }
+
+            /// Returns the variant whose name is `name[start..end]`.
+            const fn find(name: &[u8], start: usize, end: usize) -> Option<Self> {
+                $(
+                    $(#[cfg($cfg)])?
+                    if bytes_eq(name, start, end, $str.as_bytes()) {
+                        return Some(Self::$variant);
+                    }
+                )*
+                None
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                if f.alternate() {
+                    write!(f, "{}", self.name().replace('-', "_"))
+                } else {
+                    write!(f, "{}", self.name())
+                }
+            }
+        }
+    };
+}
+
+taxonomy! {
+    /// Group of related harnesses, usually an instruction trait of
+    /// `midnight-circuits`.
+    pub enum Instructions {
+        Arithmetic => "arithmetic",
+        Assertion => "assertion",
+        Assignment => "assignment",
+        Automaton => "automaton",
+        Base64 => "base64",
+        Base64Var => "base64var" | "base64-var",
+        Biguint => "biguint",
+        Binary => "binary",
+        Bitwise => "bitwise",
+        Canonicity => "canonicity",
+        CommittedInstance => "committed-instance",
+        Comparison => "comparison",
+        ControlFlow => "control-flow",
+        Conversion => "conversion",
+        CoreDecomposition => "core-decomposition",
+        Decomposition => "decomposition",
+        Division => "division",
+        Ecc => "ecc",
+        Equality => "equality",
+        Field => "field",
+        ForeignEcc => "foreign-ecc",
+        Hash => "hash",
+        HashToCurve => "hash-to-curve",
+        Map => "map",
+        MapToCurve => "map-to-curve",
+        Parser => "parser",
+        Pow2Range => "pow2range" | "pow2-range",
+        PublicInput => "public-input",
+        RangeCheck => "range-check",
+        Sha256 => "sha256",
+        Sponge => "sponge",
+        Stdlib => "stdlib",
+        UnsafeConversion => "unsafe-conversion",
+        Varhash => "varhash",
+        Vector => "vector",
+        Zero => "zero",
+        #[cfg(feature = "sha3")]
+        Sha3 => "sha3",
+        #[cfg(feature = "sha3")]
+        Keccakf => "keccakf",
+    }
+}
+
+taxonomy! {
+    /// Chip or gadget that implements the instructions.
+    pub enum Chip {
+        Native => "native",
+        NativeGadget => "native-gadget",
+        Field => "field",
+        Poseidon => "poseidon",
+        Pow2Range => "pow2range" | "pow2-range",
+        P2RDecomposition => "p2r-decomposition",
+        Sha256 => "sha256",
+        Ecc => "ecc",
+        ForeignEccNative => "foreign-ecc-native",
+        ForeignEccField => "foreign-ecc-field",
+        Vector => "vector",
+        Biguint => "biguint",
+        Stdlib => "stdlib",
+        Automaton => "automaton",
+        Base64 => "base64",
+        HashToCurve => "hash-to-curve",
+        Map => "map",
+        Parser => "parser",
+        VarlenPoseidon => "varlen-poseidon",
+        VarlenSha256 => "varlen-sha256",
+        #[cfg(feature = "sha3")]
+        Sha3 => "sha3",
+        #[cfg(feature = "sha3")]
+        Packed => "packed",
+    }
+}
+
+taxonomy! {
+    /// High-level type the harness operates on.
+    pub enum Type {
+        Native => "native",
+        Bit => "bit",
+        Field => "field",
+        Byte => "byte",
+        Biguint => "biguint",
+        Scalar => "scalar",
+        Point => "point",
+    }
+}
+
+/// Returns true if `name[start..end]` is equal to `other`.
+const fn bytes_eq(name: &[u8], start: usize, end: usize, other: &[u8]) -> bool {
+    if end - start != other.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < other.len() {
+        if name[start + i] != other[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Harness name split into its typed components.
+#[derive(Copy, Clone, Debug)]
+pub struct HarnessId {
+    name: &'static str,
+    instruction: Instructions,
+    method: (usize, usize),
+    chip: Chip,
+    r#type: Type,
+}
+
+impl HarnessId {
+    /// Parses a harness name of the form `instruction/method/chip/type`.
+    ///
+    /// # Panics
+    ///
+    /// If the name doesn't have [`NAME_COMPONENTS`] components, the method is
+    /// empty, or the instruction, chip or type is unknown. Harnesses are
+    /// registered in statics so that is a compile error.
+    pub const fn parse(name: &'static str) -> Self {
+        let bytes = name.as_bytes();
+        let mut separators = [0; NAME_COMPONENTS - 1];
+        let mut count = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'/' {
+                if count == separators.len() {
+                    panic!("harness names must have exactly 4 `/`-separated components");
+                }
+                separators[count] = i;
+                count += 1;
+            }
+            i += 1;
+        }
+        if count != separators.len() {
+            panic!("harness names must have exactly 4 `/`-separated components");
+        }
+        let [first, second, third] = separators;
+
+        let Some(instruction) = Instructions::find(bytes, 0, first) else {
+            panic!("unknown instruction in harness name");
+        };
+        if second == first + 1 {
+            panic!("empty method in harness name");
+        }
+        let Some(chip) = Chip::find(bytes, second + 1, third) else {
+            panic!("unknown chip in harness name");
+        };
+        let Some(r#type) = Type::find(bytes, third + 1, bytes.len()) else {
+            panic!("unknown type in harness name");
+        };
+        Self {
+            name,
+            instruction,
+            method: (first + 1, second),
+            chip,
+            r#type,
+        }
+    }
+
+    /// Returns the full name.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn instruction(&self) -> Instructions {
+        self.instruction
+    }
+
+    pub fn method(&self) -> &'static str {
+        &self.name[self.method.0..self.method.1]
+    }
+
+    pub fn chip(&self) -> Chip {
+        self.chip
+    }
+
+    pub fn r#type(&self) -> Type {
+        self.r#type
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn parses_components() {
+        const ID: HarnessId = HarnessId::parse("control-flow/select/native-gadget/native");
+        assert_eq!(ID.name(), "control-flow/select/native-gadget/native");
+        assert_eq!(ID.instruction(), Instructions::ControlFlow);
+        assert_eq!(ID.method(), "select");
+        assert_eq!(ID.chip(), Chip::NativeGadget);
+        assert_eq!(ID.r#type(), Type::Native);
+    }
+
+    #[test]
+    #[should_panic(expected = "exactly 4")]
+    fn rejects_missing_components() {
+        HarnessId::parse("control-flow/select/native");
+    }
+
+    #[test]
+    #[should_panic(expected = "exactly 4")]
+    fn rejects_extra_components() {
+        HarnessId::parse("control-flow/select/native/native/native");
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown chip")]
+    fn rejects_unknown_chip() {
+        HarnessId::parse("control-flow/select/native-gadgets/native");
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown type")]
+    fn rejects_prefix_of_type() {
+        HarnessId::parse("control-flow/select/native/nat");
+    }
+
+    #[test]
+    #[should_panic(expected = "empty method")]
+    fn rejects_empty_method() {
+        HarnessId::parse("control-flow//native/native");
+    }
+
+    #[test]
+    fn names_are_unique() {
+        fn check<T: Copy>(all: &[T], name: fn(T) -> &'static str) {
+            let mut seen = HashSet::new();
+            for &v in all {
+                assert!(seen.insert(name(v)), "duplicated name {}", name(v));
+            }
+        }
+        check(Instructions::ALL, Instructions::name);
+        check(Chip::ALL, Chip::name);
+        check(Type::ALL, Type::name);
+    }
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn cli_accepts_the_previous_spellings() {
+        use clap::ValueEnum as _;
+
+        assert_eq!(
+            Instructions::from_str("base64-var", false),
+            Ok(Instructions::Base64Var)
+        );
+        assert_eq!(
+            Instructions::from_str("pow2range", false),
+            Ok(Instructions::Pow2Range)
+        );
+        assert_eq!(Chip::from_str("pow2-range", false), Ok(Chip::Pow2Range));
+    }
+
+    #[test]
+    fn alternate_display_uses_underscores() {
+        assert_eq!(format!("{}", Chip::P2RDecomposition), "p2r-decomposition");
+        assert_eq!(format!("{:#}", Chip::P2RDecomposition), "p2r_decomposition");
+    }
+}
@@ -1,9 +1,13 @@
 use crate::parse::harness::{HarnessFn, Output, UnitHarnessFn};
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
-use syn::{Ident, ImplGenerics, Lifetime, Type, TypeGenerics, WhereClause};
+use syn::{
+    GenericParam, Generics, Ident, ImplGenerics, Lifetime, LifetimeParam, Type, TypeGenerics,
+    WhereClause,
+};
 
 pub mod cfg;
+mod metadata;
 
 pub use cfg::*;
 
@@ -35,16 +39,98 @@ fn abstract_circuit_io<'g>(
     }
 }
 
+/// Wrapper that implements `Circuit` for the circuit of the harness so it can be
+/// run by the mock prover. It has to be declared by the harness because both
+/// the trait and `CircuitImpl` are foreign.
+fn mock_circuit(
+    generics: &Generics,
+    circuit_ty: &Ident,
+    tag_ty: &Ident,
+    field_ty: &Type,
+    (s, c): (&Lifetime, &Lifetime),
+) -> TokenStream {
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let mut mock_generics = generics.clone();
+    let (r, a) = (
+        Lifetime::new("'__r", Span::call_site()),
+        Lifetime::new("'__a", Span::call_site()),
+    );
+    mock_generics
+        .params
+        .insert(0, GenericParam::Lifetime(LifetimeParam::new(a.clone())));
+    mock_generics
+        .params
+        .insert(0, GenericParam::Lifetime(LifetimeParam::new(r.clone())));
+    let (impl_generics, mock_ty_generics, where_clause) = mock_generics.split_for_impl();
+    quote! {
+        struct MockCircuit #impl_generics(
+            &#r mdnt_extractor_core::circuit::CircuitImpl<#a, #field_ty, #circuit_ty #ty_generics,
+                mdnt_extractor_core::circuit::#tag_ty>
+        ) #where_clause;
+
+        impl #impl_generics midnight_proofs::plonk::Circuit<#field_ty> for MockCircuit #mock_ty_generics #where_clause {
+            type Config = mdnt_extractor_core::circuit::configuration::Config<#circuit_ty #ty_generics>;
+            type FloorPlanner = midnight_proofs::circuit::SimpleFloorPlanner;
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                Self(self.0)
+            }
+
+            fn configure(meta: &mut midnight_proofs::plonk::ConstraintSystem<#field_ty>) -> Self::Config {
+                <Self::Config>::configure::<
+                    mdnt_extractor_core::circuit::layouter::ExtractionLayouter<#s, #c, #field_ty>,
+                    #field_ty
+                >(meta)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                layouter: impl midnight_proofs::circuit::Layouter<#field_ty>,
+            ) -> std::result::Result<(), midnight_proofs::plonk::Error> {
+                self.0.synthesize_inner(config, layouter)
+            }
+        }
+    }
+}
+
+/// Expression that builds the `CellLayout` of a harness IO type, decomposing
+/// arrays and tuples.
+fn cell_layout(ty: &Type) -> TokenStream {
+    let layout = quote! { mdnt_extractor_core::cells::layout::CellLayout };
+    match ty {
+        Type::Array(array) => {
+            let elem = cell_layout(&array.elem);
+            let len = &array.len;
+            quote! { #layout::array(#elem, #len) }
+        }
+        Type::Tuple(tuple) => {
+            let elems = tuple.elems.iter().map(cell_layout);
+            quote! { #layout::tuple(vec![#(#elems),*]) }
+        }
+        Type::Paren(paren) => cell_layout(&paren.elem),
+        Type::Group(group) => cell_layout(&group.elem),
+        ty => quote! { #layout::cells::<#ty>() },
+    }
+}
+
+/// Statement that records the layout of the IO of the harness in the context.
+fn set_io_layout(input_ty: &Type, output_ty: &Type) -> TokenStream {
+    let (inputs, outputs) = (cell_layout(input_ty), cell_layout(output_ty));
+    quote! {
+        ctx.set_io_layout(mdnt_extractor_core::cells::layout::IoLayout::new(#inputs, #outputs));
+    }
+}
+
 pub fn harness_impl(
     f: HarnessFn,
     cfg: impl HarnessCfg,
     circuit_cfg: CircuitCfg,
 ) -> syn::Result<TokenStream> {
     let circuit_ty = format_ident!("Circuit");
-    let vis = f.vis();
     let fn_ident = f.ident();
     let user_block = f.block();
-    let fn_attrs = f.attrs();
     let field_ty = f.field_ty();
     let chip_ty = f.chip_ty();
     let chip_pat = f.chip_pat();
@@ -61,6 +147,7 @@ pub fn harness_impl(
     };
 
     let aux_tokens = cfg.aux_tokens();
+    let shuffles = metadata::shuffle_callbacks(f.attrs())?;
     let chip_args = cfg.emit_chip_args_impl(fn_ident, field_ty, f.generics(), &circuit_ty);
 
     let injected_ir = match f.injected_ir() {
@@ -95,10 +182,18 @@ pub fn harness_impl(
         field_ty,
         f.extra_lifetimes(),
     );
+    let mock_circuit = mock_circuit(
+        f.generics(),
+        &circuit_ty,
+        &tag_ty,
+        field_ty,
+        f.extra_lifetimes(),
+    );
+    let io_layout = set_io_layout(input_ty, &syn::parse2(out_ty.clone())?);
     let (s, c) = f.extra_lifetimes();
-    Ok(quote! {
-        #(#fn_attrs)*
-        #vis fn #fn_ident #impl_generics (ctx: &mdnt_extractor_core::harness::Ctx) -> anyhow::Result<mdnt_extractor_core::harness::Output> #where_clause {
+    let harness_fn = quote! {
+        fn __harness #impl_generics (ctx: &mdnt_extractor_core::harness::Ctx) -> anyhow::Result<mdnt_extractor_core::harness::Output> #where_clause {
+            #[derive(Clone, Copy)]
             struct #circuit_ty #impl_generics(
                 std::marker::PhantomData<(&#s (), &#c ())>
             );
@@ -121,19 +216,23 @@ pub fn harness_impl(
             }
             #chip_args
 
-            let circuit = mdnt_extractor_core::circuit::CircuitImpl::<#field_ty, #circuit_ty #ty_generics,
+            #mock_circuit
+
+            let new_circuit = || mdnt_extractor_core::circuit::CircuitImpl::<#field_ty, #circuit_ty #ty_generics,
                 mdnt_extractor_core::circuit::#tag_ty>::new(ctx, #circuit_ty(Default::default()));
-            ctx.lower_circuit(circuit, #aux_tokens)
+            #io_layout
+            let ir = ctx.lower_circuit(new_circuit(), #aux_tokens, #shuffles)?;
+            ctx.check_witnesses(&ir, &MockCircuit(&new_circuit()), #aux_tokens)?;
+            Ok(ir)
         }
-    })
+    };
+    metadata::definition(&f, harness_fn, input_ty, &aux_tokens)
 }
 
 pub fn unit_harness_impl(f: UnitHarnessFn, cfg: impl HarnessCfg) -> syn::Result<TokenStream> {
     let circuit_ty = format_ident!("Circuit");
-    let vis = f.vis();
     let fn_ident = f.ident();
     let user_block = f.block();
-    let fn_attrs = f.attrs();
     let field_ty = f.field_ty();
     let chip_ty = f.chip_ty();
     let chip_pat = f.chip_pat();
@@ -143,8 +242,10 @@ pub fn unit_harness_impl(f: UnitHarnessFn, cfg: impl HarnessCfg) -> syn::Result<
     let output_ty = f.output_ty();
     let output_pat = f.output_pat();
     let (impl_generics, ty_generics, where_clause) = f.generics().split_for_impl();
+    let io_ty: Type = syn::parse2(quote! { (#input_ty, #output_ty) })?;
 
     let aux_tokens = cfg.aux_tokens();
+    let shuffles = metadata::shuffle_callbacks(f.attrs())?;
     let chip_args = cfg.emit_chip_args_impl(fn_ident, field_ty, f.generics(), &circuit_ty);
 
     let injected_ir = match f.injected_ir() {
@@ -158,16 +259,27 @@ pub fn unit_harness_impl(f: UnitHarnessFn, cfg: impl HarnessCfg) -> syn::Result<
         where_clause,
         &circuit_ty,
         chip_ty,
-        &syn::parse2(quote! { (#input_ty, #output_ty) })?,
+        &io_ty,
         &quote! { mdnt_extractor_core::cells::store::FreshVar },
         field_ty,
         f.extra_lifetimes(),
     );
 
+    let mock_circuit = mock_circuit(
+        f.generics(),
+        &circuit_ty,
+        &format_ident!("Function"),
+        field_ty,
+        f.extra_lifetimes(),
+    );
+    let io_layout = set_io_layout(
+        &io_ty,
+        &syn::parse2(quote! { mdnt_extractor_core::cells::store::FreshVar })?,
+    );
     let (s, c) = f.extra_lifetimes();
-    Ok(quote! {
-        #(#fn_attrs)*
-        #vis fn #fn_ident #impl_generics (ctx: &mdnt_extractor_core::harness::Ctx) -> anyhow::Result<mdnt_extractor_core::harness::Output> #where_clause {
+    let harness_fn = quote! {
+        fn __harness #impl_generics (ctx: &mdnt_extractor_core::harness::Ctx) -> anyhow::Result<mdnt_extractor_core::harness::Output> #where_clause {
+            #[derive(Clone, Copy)]
             struct #circuit_ty #impl_generics(
                 std::marker::PhantomData<(&#s (), &#c ())>
             );
@@ -192,8 +304,15 @@ pub fn unit_harness_impl(f: UnitHarnessFn, cfg: impl HarnessCfg) -> syn::Result<
             }
             #chip_args
 
-            let circuit = mdnt_extractor_core::circuit::CircuitImpl::<#field_ty, #circuit_ty #ty_generics, mdnt_extractor_core::circuit::Function>::new(ctx, #circuit_ty(Default::default()));
-            ctx.lower_circuit(circuit, #aux_tokens)
+            #mock_circuit
+
+            let new_circuit = || mdnt_extractor_core::circuit::CircuitImpl::<#field_ty, #circuit_ty #ty_generics,
+                mdnt_extractor_core::circuit::Function>::new(ctx, #circuit_ty(Default::default()));
+            #io_layout
+            let ir = ctx.lower_circuit(new_circuit(), #aux_tokens, #shuffles)?;
+            ctx.check_witnesses(&ir, &MockCircuit(&new_circuit()), #aux_tokens)?;
+            Ok(ir)
         }
-    })
+    };
+    metadata::definition(&f, harness_fn, &io_ty, &aux_tokens)
 }
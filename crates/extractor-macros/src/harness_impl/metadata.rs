@@ -0,0 +1,146 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    punctuated::Punctuated, Attribute, Expr, ExprLit, GenericParam, Lit, LitStr, Meta, Token, Type,
+};
+
+use crate::parse::harness::HarnessFnCommon;
+
+/// Expression that builds the kinds of the constants read by a harness input,
+/// decomposing arrays and tuples like [`super::cell_layout`].
+fn constants_schema(ty: &Type) -> TokenStream {
+    match ty {
+        Type::Array(array) => {
+            let elem = constants_schema(&array.elem);
+            let len = &array.len;
+            quote! { (#elem).repeat(#len) }
+        }
+        Type::Tuple(tuple) if tuple.elems.is_empty() => quote! { vec![] },
+        Type::Tuple(tuple) => {
+            let elems = tuple.elems.iter().map(constants_schema);
+            quote! { [#(#elems),*].concat() }
+        }
+        Type::Paren(paren) => constants_schema(&paren.elem),
+        Type::Group(group) => constants_schema(&group.elem),
+        ty => quote! {
+            (&mdnt_extractor_core::harness::metadata::Probe::<#ty>(std::marker::PhantomData))
+                .constants()
+                .to_vec()
+        },
+    }
+}
+
+/// Joins the doc comments of the harness.
+fn description(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let description = lines
+        .iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let description = description.trim();
+    (!description.is_empty()).then(|| description.to_owned())
+}
+
+/// Splits the `#[tags(...)]` attributes from the rest. The `#[shuffles(...)]`
+/// attribute, read by [`shuffle_callbacks`], is dropped.
+fn tags(attrs: &[Attribute]) -> syn::Result<(Vec<LitStr>, Vec<&Attribute>)> {
+    let mut tags = vec![];
+    let mut rest = vec![];
+    for attr in attrs {
+        if attr.path().is_ident("tags") {
+            tags.extend(attr.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)?);
+        } else if !attr.path().is_ident("shuffles") {
+            rest.push(attr);
+        }
+    }
+    Ok((tags, rest))
+}
+
+/// Expression that borrows the shuffle callbacks given with
+/// `#[shuffles(...)]`, or the default ones if the harness has no such
+/// attribute.
+pub fn shuffle_callbacks(attrs: &[Attribute]) -> syn::Result<TokenStream> {
+    let mut attrs = attrs.iter().filter(|attr| attr.path().is_ident("shuffles"));
+    let callbacks = match attrs.next() {
+        Some(attr) => attr.parse_args::<Expr>()?,
+        None => syn::parse_quote! {
+            mdnt_extractor_core::shuffles::MultisetEqShuffle::default()
+        },
+    };
+    if let Some(attr) = attrs.next() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "the shuffle callbacks are already given",
+        ));
+    }
+    Ok(quote! { &#callbacks })
+}
+
+/// Wraps the function generated for the harness, which must be named
+/// `__harness`, in a function with the name of the harness that returns its
+/// definition.
+///
+/// The metadata is built from the doc comments and tags of the harness, the
+/// constants read by the inputs in `io_ty`, and the lookup callbacks.
+pub fn definition(
+    f: &HarnessFnCommon,
+    harness_fn: TokenStream,
+    io_ty: &Type,
+    aux_tokens: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let vis = f.vis();
+    let fn_ident = f.ident();
+    let field_ty = f.field_ty();
+    let (impl_generics, _, where_clause) = f.generics().split_for_impl();
+    let (tags, fn_attrs) = tags(f.attrs())?;
+    let description = description(f.attrs()).map(|description| {
+        quote! { .with_description(#description) }
+    });
+    let constants = constants_schema(io_ty);
+
+    // Lifetimes are left for inference.
+    let args: Vec<_> = f
+        .generics()
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(&param.ident),
+            GenericParam::Const(param) => Some(&param.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let turbofish = (!args.is_empty()).then(|| quote! { ::<#(#args),*> });
+
+    Ok(quote! {
+        #(#fn_attrs)*
+        #vis fn #fn_ident #impl_generics () -> mdnt_extractor_core::harness::Definition #where_clause {
+            #harness_fn
+
+            #[allow(clippy::needless_borrow)]
+            fn __metadata #impl_generics () -> mdnt_extractor_core::harness::metadata::Metadata #where_clause {
+                #[allow(unused_imports)]
+                use mdnt_extractor_core::harness::metadata::{ProbeFallback as _, ProbeReads as _};
+                mdnt_extractor_core::harness::metadata::Metadata::default()
+                    #description
+                    .with_constants(#constants)
+                    .with_lookups::<#field_ty>(#aux_tokens)
+                    .with_tags(&[#(#tags),*])
+            }
+
+            mdnt_extractor_core::harness::Definition::new(__harness #turbofish, __metadata #turbofish)
+        }
+    })
+}
@@ -4,13 +4,15 @@ use syn::parse_macro_input;
 use crate::{
     error::tokenize,
     harness_impl::{harness_impl, unit_harness_impl, CircuitCfg, NoArgsCfg, WithArgsCfg},
-    parse::harness::{HarnessFn, UnitHarnessFn},
+    parse::harness::{field_of_layouter, HarnessFn, UnitHarnessFn},
+    sweep_impl::{sweep_impl, SweepArgs},
 };
 
 mod delegated_impl;
 mod error;
 mod harness_impl;
 mod parse;
+mod sweep_impl;
 
 #[proc_macro_attribute]
 pub fn harness(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -64,18 +66,58 @@ pub fn unit_harness_with_args(attr: TokenStream, item: TokenStream) -> TokenStre
     ))
 }
 
+/// Registers the harness with the field of its layouter, or with the default
+/// field if the function has no `impl Layouter<F>` argument.
 #[proc_macro_attribute]
 pub fn entry(attr: TokenStream, item: TokenStream) -> TokenStream {
     let f = parse_macro_input!(item as syn::ItemFn);
     let name = parse_macro_input!(attr as syn::LitStr);
     let fname = &f.sig.ident;
+    let entry = match field_of_fn(&f) {
+        Some(field) => quote::quote! { mdnt_extractor_core::entry!(#name, #fname, #field); },
+        None => quote::quote! { mdnt_extractor_core::entry!(#name, #fname); },
+    };
     quote::quote! {
-        mdnt_extractor_core::entry!(#name, #fname);
+        #entry
         #f
     }
     .into()
 }
 
+/// Registers a harness once per value of its const parameters.
+///
+/// The first argument is a template for the names of the entries where `{N}`
+/// stands for the value of the const parameter `N`. It is followed by the
+/// values of each const parameter. If the function has more than one const
+/// parameter, every combination of their values is registered. The template
+/// must have 4 `/`-separated components and mention every parameter.
+///
+/// The function can't have type parameters: the definition of a harness takes
+/// no arguments, so they can't be inferred. Lifetimes are fine.
+///
+/// ```ignore
+/// // Registers `le_bits_lower_than_native::<1>` as
+/// // "canonicity/le_bits_lower_than_1/native/native", and so on.
+/// #[sweep("canonicity/le_bits_lower_than_{N}/native/native", N = [1, 64, 255])]
+/// #[harness]
+/// pub fn le_bits_lower_than_native<const N: usize>(...) -> Result<AssignedBit<F>, Error> { ... }
+/// ```
+#[proc_macro_attribute]
+pub fn sweep(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(item as syn::ItemFn);
+    let args = parse_macro_input!(attr as SweepArgs);
+    let field = field_of_fn(&f);
+    tokenize!(sweep_impl(args, f, field))
+}
+
+/// Returns the field of the `impl Layouter<F>` argument of the function.
+fn field_of_fn(f: &syn::ItemFn) -> Option<syn::Type> {
+    f.sig.inputs.iter().find_map(|arg| match arg {
+        syn::FnArg::Typed(arg) => field_of_layouter(&arg.ty),
+        syn::FnArg::Receiver(_) => None,
+    })
+}
+
 #[proc_macro_attribute]
 pub fn usize_args(attr: TokenStream, item: TokenStream) -> TokenStream {
     let f = parse_macro_input!(item as syn::ItemFn);
@@ -368,6 +368,11 @@ fn extract_field(ty: &Type) -> Result<Type, Error> {
     Ok(ty.clone())
 }
 
+/// Returns `F` if the type is `&mut impl Layouter<F>`.
+pub fn field_of_layouter(ty: &Type) -> Option<Type> {
+    ensure_ref_mut(ty).and_then(|_| extract_field(ty)).ok()
+}
+
 fn extract_field_err<T>(t: impl ToTokens, u: &'static str) -> Result<T, Error> {
     Err(Error(
         ErrorType::FailedExtractLayouterParam(u),
@@ -0,0 +1,305 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Error, GenericParam, Ident, ItemFn, LitInt, LitStr, Token, Type,
+};
+
+/// Number of components of a harness name, as in
+/// `mdnt_extractor_core::taxonomy::NAME_COMPONENTS`.
+const NAME_COMPONENTS: usize = 4;
+
+/// Arguments of `#[sweep]`: a name template followed by the values of each
+/// const parameter, e.g. `"foo/bar_{N}/baz/native", N = [1, 2]`.
+pub struct SweepArgs {
+    template: LitStr,
+    params: Vec<(Ident, Vec<LitInt>)>,
+}
+
+impl Parse for SweepArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let template = input.parse()?;
+        let mut params = vec![];
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let content;
+            bracketed!(content in input);
+            let values = Punctuated::<LitInt, Token![,]>::parse_terminated(&content)?;
+            if values.is_empty() {
+                return Err(Error::new_spanned(name, "expected at least one value"));
+            }
+            params.push((name, values.into_iter().collect()));
+        }
+        Ok(Self { template, params })
+    }
+}
+
+impl SweepArgs {
+    fn values(&self, param: &Ident) -> Option<&[LitInt]> {
+        self.params
+            .iter()
+            .find_map(|(name, values)| (name == param).then_some(values.as_slice()))
+    }
+}
+
+/// Returns the names of the placeholders of the template.
+fn placeholders(template: &LitStr) -> syn::Result<Vec<String>> {
+    let value = template.value();
+    let mut names = vec![];
+    let mut rest = value.as_str();
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err(Error::new_spanned(template, "unclosed `{` in the template"));
+        };
+        names.push(rest[start + 1..start + len].to_string());
+        rest = &rest[start + len + 1..];
+    }
+    Ok(names)
+}
+
+fn check_template(args: &SweepArgs) -> syn::Result<()> {
+    let template = &args.template;
+    let components = template.value().split('/').count();
+    if components != NAME_COMPONENTS {
+        return Err(Error::new_spanned(
+            template,
+            format!(
+                "harness names must have exactly {NAME_COMPONENTS} `/`-separated components but the template has {components}"
+            ),
+        ));
+    }
+    let placeholders = placeholders(template)?;
+    if let Some(name) = placeholders
+        .iter()
+        .find(|name| !args.params.iter().any(|(param, _)| param == name.as_str()))
+    {
+        return Err(Error::new_spanned(
+            template,
+            format!("`{{{name}}}` is not a swept parameter"),
+        ));
+    }
+    // Otherwise every value of the parameter would register the same name.
+    if let Some((param, _)) = args
+        .params
+        .iter()
+        .find(|(param, _)| !placeholders.iter().any(|name| param == name.as_str()))
+    {
+        return Err(Error::new_spanned(
+            param,
+            format!("the template does not mention `{{{param}}}`"),
+        ));
+    }
+    Ok(())
+}
+
+/// Registers one entry per combination of the values of the swept parameters.
+pub fn sweep_impl(args: SweepArgs, f: ItemFn, field: Option<Type>) -> syn::Result<TokenStream> {
+    if args.params.is_empty() {
+        return Err(Error::new_spanned(
+            &args.template,
+            "expected the values of at least one const parameter",
+        ));
+    }
+    check_template(&args)?;
+    let mut seen = HashSet::new();
+    if let Some((param, _)) = args.params.iter().find(|(param, _)| !seen.insert(param)) {
+        return Err(Error::new_spanned(param, "parameter swept more than once"));
+    }
+
+    // The entries take a `fn() -> Definition`, which can't tell the type
+    // parameters of the harness. Lifetimes are left for inference.
+    let mut swept = vec![];
+    for param in &f.sig.generics.params {
+        let param = match param {
+            GenericParam::Const(param) => param,
+            GenericParam::Lifetime(_) => continue,
+            GenericParam::Type(param) => {
+                return Err(Error::new_spanned(
+                    param,
+                    format!(
+                        "the entries can't infer the type parameter `{}`, only const parameters can be swept",
+                        param.ident
+                    ),
+                ))
+            }
+        };
+        let Some(values) = args.values(&param.ident) else {
+            return Err(Error::new_spanned(
+                &param.ident,
+                format!("missing the values of `{}`", param.ident),
+            ));
+        };
+        swept.push((&param.ident, values));
+    }
+    if let Some((param, _)) =
+        args.params.iter().find(|(param, _)| !swept.iter().any(|(p, _)| p == &param))
+    {
+        return Err(Error::new_spanned(
+            param,
+            format!("`{param}` is not a const parameter of the function"),
+        ));
+    }
+
+    // Cartesian product of the values, in the order of the generic parameters.
+    let combinations = swept.iter().fold(vec![vec![]], |acc, (_, values)| {
+        acc.into_iter()
+            .flat_map(|prefix: Vec<&LitInt>| {
+                values.iter().map(move |value| {
+                    let mut combination = prefix.clone();
+                    combination.push(value);
+                    combination
+                })
+            })
+            .collect()
+    });
+
+    let fname = &f.sig.ident;
+    let entries = combinations.into_iter().map(|values| {
+        let name = swept.iter().zip(&values).fold(
+            args.template.value(),
+            |name, ((param, _), value)| {
+                name.replace(&format!("{{{param}}}"), value.base10_digits())
+            },
+        );
+        let name = LitStr::new(&name, args.template.span());
+        match &field {
+            Some(field) => {
+                quote::quote! { mdnt_extractor_core::entry!(#name, #fname::<#(#values),*>, #field); }
+            }
+            None => quote::quote! { mdnt_extractor_core::entry!(#name, #fname::<#(#values),*>); },
+        }
+    });
+
+    Ok(quote::quote! {
+        #(#entries)*
+        #f
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn expand(args: TokenStream, f: TokenStream, field: Option<Type>) -> syn::Result<TokenStream> {
+        sweep_impl(syn::parse2(args)?, syn::parse2(f)?, field)
+    }
+
+    fn error(args: TokenStream, f: TokenStream) -> String {
+        expand(args, f, None).expect_err("the sweep was expanded").to_string()
+    }
+
+    #[test]
+    fn registers_an_entry_per_value() {
+        let f = quote! { fn foo<const N: usize>() {} };
+        let expanded = expand(quote! { "a/b_{N}/c/native", N = [1, 64] }, f.clone(), None);
+        let expected = quote! {
+            mdnt_extractor_core::entry!("a/b_1/c/native", foo::<1>);
+            mdnt_extractor_core::entry!("a/b_64/c/native", foo::<64>);
+            #f
+        };
+        assert_eq!(expanded.unwrap().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn entries_are_registered_over_the_field() {
+        let f = quote! { fn foo<const N: usize>() {} };
+        let expanded = expand(
+            quote! { "a/b_{N}/c/d", N = [1] },
+            f.clone(),
+            Some(parse_quote!(F)),
+        );
+        let expected = quote! {
+            mdnt_extractor_core::entry!("a/b_1/c/d", foo::<1>, F);
+            #f
+        };
+        assert_eq!(expanded.unwrap().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn registers_the_cartesian_product_of_the_parameters() {
+        let f = quote! { fn foo<const N: usize, const M: usize>() {} };
+        // The values follow the order of the generic parameters, not the one of
+        // the arguments.
+        let args = quote! { "a/b_{N}_{M}/c/d", M = [3, 4], N = [1, 2] };
+        let expected = quote! {
+            mdnt_extractor_core::entry!("a/b_1_3/c/d", foo::<1, 3>);
+            mdnt_extractor_core::entry!("a/b_1_4/c/d", foo::<1, 4>);
+            mdnt_extractor_core::entry!("a/b_2_3/c/d", foo::<2, 3>);
+            mdnt_extractor_core::entry!("a/b_2_4/c/d", foo::<2, 4>);
+            #f
+        };
+        assert_eq!(
+            expand(args, f, None).unwrap().to_string(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn templates_need_every_component() {
+        let err = error(
+            quote! { "a/b_{N}/c", N = [1] },
+            quote! { fn foo<const N: usize>() {} },
+        );
+        assert_eq!(
+            err,
+            "harness names must have exactly 4 `/`-separated components but the template has 3"
+        );
+    }
+
+    #[test]
+    fn placeholders_must_be_swept() {
+        let err = error(
+            quote! { "a/b_{N}_{M}/c/d", N = [1] },
+            quote! { fn foo<const N: usize>() {} },
+        );
+        assert_eq!(err, "`{M}` is not a swept parameter");
+    }
+
+    #[test]
+    fn swept_parameters_must_be_in_the_template() {
+        let err = error(
+            quote! { "a/b_{N}/c/d", N = [1], M = [2] },
+            quote! { fn foo<const N: usize, const M: usize>() {} },
+        );
+        assert_eq!(err, "the template does not mention `{M}`");
+    }
+
+    #[test]
+    fn swept_parameters_must_be_const_parameters() {
+        let err = error(
+            quote! { "a/b_{N}/c/d", N = [1] },
+            quote! { fn foo<const M: usize>() {} },
+        );
+        assert_eq!(err, "missing the values of `M`");
+        let err = error(
+            quote! { "a/b_{N}/c/d", N = [1] },
+            quote! { fn foo<T, const N: usize>() {} },
+        );
+        assert_eq!(
+            err,
+            "the entries can't infer the type parameter `T`, only const parameters can be swept"
+        );
+    }
+
+    #[test]
+    fn lifetimes_are_left_for_inference() {
+        let f = quote! { fn foo<'a, const N: usize>() {} };
+        let expanded = expand(quote! { "a/b_{N}/c/d", N = [1] }, f.clone(), None);
+        let expected = quote! {
+            mdnt_extractor_core::entry!("a/b_1/c/d", foo::<1>);
+            #f
+        };
+        assert_eq!(expanded.unwrap().to_string(), expected.to_string());
+    }
+}
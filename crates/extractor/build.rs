@@ -1,13 +1,28 @@
 use cargo_lock::{Lockfile, SourceId};
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Crates of the workspace whose sources determine the extracted IR.
+const SOURCE_CRATES: [&str; 7] = [
+    "extractor",
+    "extractor-core",
+    "extractor-macros",
+    "groups-support",
+    "harnesses",
+    "support",
+    "support-macros",
+];
 
 fn main() -> anyhow::Result<()> {
-    let lock = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..").join("Cargo.lock"); //.with_extension("lock");
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let lock = root.join("Cargo.lock"); //.with_extension("lock");
     let packages: HashSet<&str> = ["halo2-llzk-frontend", "midnight-circuits"].into();
 
     eprintln!("Cargo lock path: {}", lock.display());
-    Lockfile::load(lock)?
+    println!("cargo::rerun-if-changed={}", lock.display());
+    println!("cargo::rerun-if-changed=build.rs");
+    let lockfile = Lockfile::load(lock)?;
+    lockfile
         .packages
         .iter()
         .filter(|p| packages.contains(p.name.as_str()))
@@ -18,5 +33,68 @@ fn main() -> anyhow::Result<()> {
                 println!("cargo::rustc-env={name}_CHECKSUM={}", &checksum[0..8]);
             }
         });
+
+    // All the haloumi crates take part in generating the IR, so any of them
+    // changing invalidates the outputs.
+    let mut haloumi = lockfile
+        .packages
+        .iter()
+        .filter(|p| p.name.as_str().starts_with("haloumi"))
+        .map(|p| format!("{} {}", p.name, p.version))
+        .collect::<Vec<_>>();
+    haloumi.sort();
+    println!("cargo::rustc-env=HALOUMI_VERSIONS={}", haloumi.join(", "));
+
+    let mut files = vec![];
+    for krate in SOURCE_CRATES {
+        let dir = root.join("crates").join(krate);
+        println!("cargo::rerun-if-changed={}", dir.display());
+        collect_sources(&dir, &mut files)?;
+    }
+    files.sort();
+    let mut hash = Fnv::default();
+    for file in &files {
+        hash.write(file.strip_prefix(&root)?.to_string_lossy().as_bytes());
+        hash.write(&std::fs::read(file)?);
+    }
+    println!("cargo::rustc-env=SOURCES_FINGERPRINT={:016x}", hash.0);
     Ok(())
 }
+
+/// Collects the Rust sources and manifests in the directory and its
+/// subdirectories.
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_sources(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs")
+            || path.file_name().is_some_and(|name| name == "Cargo.toml")
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 64-bit FNV-1a hash, which is stable across Rust versions unlike
+/// [`std::hash::DefaultHasher`].
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv {
+    /// Hashes the length of the bytes before them, so that moving bytes from
+    /// one write to the next changes the hash.
+    fn write(&mut self, bytes: &[u8]) {
+        let len = (bytes.len() as u64).to_le_bytes();
+        for byte in len.iter().chain(bytes) {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
@@ -1,20 +1,38 @@
-use std::{borrow::Cow, fmt, fs::File, io::Write as _, path::Path};
+use std::{
+    borrow::Cow,
+    fmt,
+    fs::File,
+    io::Write as _,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::Instant,
+};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context as _, Result};
 use haloumi::ir_gen::circuit::resolved::ResolvedIRCircuit;
 use haloumi_picus::PicusParamsBuilder;
 use log::Log;
 
 use crate::{
-    config::{AppConfig, FailMode, LoggingConfig, OutputFormat},
-    picus::{write_picus_output, PicusConfig},
+    config::{AppConfig, LoggingConfig, OutputFormat},
+    picus::{header::Header, write_picus_output, PicusConfig},
     query::Query,
+    smtlib::write_smtlib_output,
 };
-use mdnt_extractor_core::harness::{Ctx, Harness};
+use mdnt_extractor_core::harness::{Ctx, Definition};
 
+mod cache;
 mod error;
+mod listing;
+mod logging;
+mod manifest;
+mod parallel;
 
+use cache::{Cache, CacheKey, CacheStatus, Reason};
 use error::AppError;
+use listing::Details;
+use manifest::{HarnessReport, Manifest};
+use parallel::{extract_parallel, extract_sequential};
 
 pub struct App<Config> {
     config: Config,
@@ -22,6 +40,10 @@ pub struct App<Config> {
 }
 
 fn setup_logging(config: Option<LoggingConfig>) -> Result<()> {
+    // Runs of a project share the logger installed by the first one.
+    if logging::is_initialized() {
+        return Ok(());
+    }
     let env_logger = Box::new(env_logger::Builder::from_default_env().build());
     let mut loggers: Vec<Box<dyn Log>> = vec![env_logger];
     if let Some(config) = config {
@@ -32,7 +54,7 @@ fn setup_logging(config: Option<LoggingConfig>) -> Result<()> {
         ));
     }
 
-    multi_log::MultiLogger::init(loggers, log::Level::Trace)?;
+    logging::init(loggers, log::Level::Trace)?;
     Ok(())
 }
 
@@ -43,7 +65,7 @@ enum OptStep {
 
 impl<Config> App<Config>
 where
-    Config: AppConfig + fmt::Debug,
+    Config: AppConfig + fmt::Debug + Sync,
 {
     pub fn new(mut config: Config) -> Result<Self> {
         setup_logging(config.logging())?;
@@ -59,6 +81,14 @@ where
             config.method_whitelist(),
             config.method_blacklist(),
         );
+        let query = match config.select() {
+            Some(select) => query.and(
+                select
+                    .parse()
+                    .with_context(|| format!("Failed to parse selection '{select}'"))?,
+            ),
+            None => query,
+        };
 
         Ok(Self { config, query })
     }
@@ -88,7 +118,7 @@ where
         name: &'static str,
         output_base: impl AsRef<Path>,
         ir: &ResolvedIRCircuit,
-    ) -> Result<()> {
+    ) -> Result<PathBuf> {
         let output_dir = output_base.as_ref().join(name);
         std::fs::create_dir_all(&output_dir)?;
 
@@ -96,7 +126,7 @@ where
         let mut output_file = File::create(&output_path)?;
         writeln!(output_file, "{}", ir.display())?;
         log::info!("Saved IR dump output in {}", output_path.display());
-        Ok(())
+        Ok(output_path)
     }
 
     fn check_validation(&self, status: Result<()>, step: OptStep) -> Result<()> {
@@ -127,14 +157,15 @@ where
         self.check_validation(Ok(ir.validate()?), OptStep::Canonicalization)
     }
 
-    fn select_harness(&self) -> Vec<(&'static str, Harness)> {
+    fn select_harness(&self) -> Vec<(&'static str, Definition)> {
         mdnt_harnesses::harnesses()
             .filter_map(|entry| {
-                let matched = self.query.matches(entry.name());
+                let name = entry.qualified_name();
+                let matched = self.query.matches(name);
                 if !matched {
-                    log::debug!("Ignoring harness {}", entry.name());
+                    log::debug!("Ignoring harness {name}");
                 }
-                matched.then_some((entry.name(), entry.harness()))
+                matched.then_some((name, entry.definition()))
             })
             .collect()
     }
@@ -143,84 +174,177 @@ where
         log::info!("Selecting harnesses matching {:?}", self.query);
         let harness = self.select_harness();
         match self.config.action() {
-            crate::config::Action::List => {
-                self.print_harness_list(harness);
+            crate::config::Action::List { verbose } => {
+                self.print_harness_list(harness, verbose);
                 Ok(())
             }
             crate::config::Action::Extract => self.extract(harness),
         }
     }
 
-    fn print_harness_list(&self, harness: Vec<(&'static str, Harness)>) {
-        for (name, _) in harness {
+    fn print_harness_list(&self, harness: Vec<(&'static str, Definition)>, verbose: bool) {
+        // Goes to stderr to keep stdout a plain list of names.
+        eprintln!("Query: {:?}", self.query);
+        for (name, definition) in harness {
             println!("{name}");
+            if verbose {
+                print!("{}", Details(&definition.metadata()));
+            }
         }
     }
 
     fn extract_one(
         &self,
         name: &'static str,
-        harness: Harness,
+        definition: Definition,
         ctx: &Ctx,
         output_base: &Path,
         picus_config: &PicusConfig,
+        report: &mut HarnessReport,
     ) -> Result<(), AppError> {
         log::info!("Extracting harness {name}");
 
-        let mut ir = harness(ctx).map_err(AppError::harness(name))?;
+        let ir = definition.harness()(ctx);
+        report.set_lookups(ctx.lookups());
+        let mut ir = ir.map_err(AppError::harness(name))?;
         if self.config.optimize_ir() {
             self.optimize_ir(&mut ir).map_err(AppError::opt(name))?;
         }
+        report.set_ir(&ir);
+        let header = Header::new(ctx.floor_planner());
         if self.config.dump_ir() {
-            self.dump_ir(name, output_base, &ir).map_err(AppError::ir_dump(name))?;
+            let path = self.dump_ir(name, output_base, &ir).map_err(AppError::ir_dump(name))?;
+            report.add_output(path);
         }
         for format in self.config.formats() {
-            match format {
-                OutputFormat::Picus => write_picus_output(
-                    picus_config,
+            let path = match format {
+                OutputFormat::Picus => {
+                    let files = write_picus_output(
+                        picus_config,
+                        &header,
+                        name,
+                        output_base,
+                        &ir,
+                        &ctx.table_modules(),
+                        &ctx.shuffle_modules(),
+                        ctx.io_layout(),
+                        PicusParamsBuilder::new(),
+                    )
+                    .map_err(AppError::picus(name))?;
+                    report.add_preludes(files.preludes);
+                    report.add_output(files.sidecar);
+                    files.output
+                }
+                OutputFormat::Smtlib => write_smtlib_output(
+                    &header,
                     name,
                     output_base,
                     &ir,
-                    PicusParamsBuilder::new(),
+                    &ctx.table_modules(),
+                    &ctx.shuffle_modules(),
                 )
-                .map_err(AppError::picus(name))?,
-            }
+                .map_err(AppError::smtlib(name))?,
+            };
+            report.add_output(path);
         }
 
         Ok(())
     }
 
-    fn handle_extract_result(
-        &self,
-        extract: impl FnOnce() -> Result<(), AppError>,
-        summary: &mut Summary,
-    ) -> Result<()> {
-        summary.generated += 1;
-        match extract() {
-            Err(err) => match self.config.fail_mode() {
-                FailMode::Fast => Err(err.into()),
-                FailMode::Continue => {
-                    log::error!("{err}");
-                    summary.errors += 1;
-                    Ok(())
-                }
-            },
-            _ => Ok(()),
-        }
-    }
-
-    fn extract(&self, harness: Vec<(&'static str, Harness)>) -> Result<()> {
+    fn extract(&self, harness: Vec<(&'static str, Definition)>) -> Result<()> {
         let harness_config = self.config.harness_config();
-        let ctx = harness_config.make_ctx();
         let picus_config = self.config.picus_config();
         let output_base = self.output_base()?;
-        let mut summary = Summary::default();
-        for (name, harness) in harness.into_iter() {
-            self.handle_extract_result(
-                || self.extract_one(name, harness, &ctx, &output_base, &picus_config),
-                &mut summary,
-            )?;
+        let reports = Mutex::new(vec![]);
+        let cache = Mutex::new(Cache::load(&output_base));
+        let settings = [
+            ("harness", serde_json::to_value(&harness_config)?),
+            ("picus", serde_json::to_value(&picus_config)?),
+            ("formats", serde_json::to_value(self.config.formats())?),
+            ("dump_ir", self.config.dump_ir().into()),
+            ("optimize_ir", self.config.optimize_ir().into()),
+        ];
+        let extract_one = |name, definition| {
+            let ctx = harness_config.make_ctx(name);
+            let mut report = HarnessReport::new(name, ctx.constants());
+            let key = CacheKey::new(ctx.constants(), settings.clone());
+            let start = Instant::now();
+            // Checked before looking up the cache so that reused outputs
+            // still meet the requirements of the harness.
+            let checked = definition
+                .metadata()
+                .check_constants(ctx.constants())
+                .map_err(|err| AppError::constants(name)(err.into()));
+            let cached = if self.config.force() {
+                Err(Reason::Forced)
+            } else if harness_config.witness_samples() > 0 {
+                Err(Reason::WitnessCheck)
+            } else {
+                let cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+                cache.check(name, &key).map(|entry| {
+                    (
+                        entry.outputs().to_vec(),
+                        entry.preludes().to_vec(),
+                        entry.lookups().to_vec(),
+                    )
+                })
+            };
+            let result = match checked.map(|()| cached) {
+                Err(err) => Err(err),
+                Ok(Ok((outputs, preludes, lookups))) => {
+                    log::info!("Skipping harness {name}, its outputs are up to date");
+                    report.set_cache(CacheStatus::UpToDate);
+                    report.add_preludes(preludes);
+                    report.set_lookups(lookups);
+                    outputs.into_iter().for_each(|output| report.add_output(output));
+                    Ok(())
+                }
+                Ok(Err(reason)) => {
+                    log::info!("Regenerating harness {name}: {reason}");
+                    report.set_cache(CacheStatus::Regenerated(reason));
+                    let result = self.extract_one(
+                        name,
+                        definition,
+                        &ctx,
+                        &output_base,
+                        &picus_config,
+                        &mut report,
+                    );
+                    let mut cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+                    match result {
+                        Ok(()) => cache.insert(
+                            name,
+                            key,
+                            report.outputs().to_vec(),
+                            report.preludes().to_vec(),
+                            report.lookups().to_vec(),
+                        ),
+                        Err(_) => cache.remove(name),
+                    }
+                    result
+                }
+            };
+            report.finish(start.elapsed(), result.as_ref().map(|_| ()));
+            reports.lock().unwrap_or_else(PoisonError::into_inner).push(report);
+            result
+        };
+        let jobs = self.config.jobs().get().min(harness.len());
+        let fail_mode = self.config.fail_mode();
+        let summary = if jobs > 1 {
+            extract_parallel(&harness, jobs, fail_mode, &extract_one)
+        } else {
+            extract_sequential(harness, fail_mode, &extract_one)
+        };
+        // The manifest and the cache are written even if extraction stopped
+        // early.
+        let reports = reports.into_inner().unwrap_or_else(PoisonError::into_inner);
+        let up_to_date = reports.iter().filter(|report| report.is_up_to_date()).count();
+        if up_to_date > 0 {
+            log::info!("Skipped {up_to_date} harnesses with up to date outputs");
         }
+        cache.into_inner().unwrap_or_else(PoisonError::into_inner).save(&output_base)?;
+        Manifest::new(reports).write(&output_base)?;
+        let summary = summary?;
         if summary.errors > 0 {
             bail!("Extraction failed with {} errors", summary.errors);
         }
@@ -230,9 +354,3 @@ where
         Ok(())
     }
 }
-
-#[derive(Default)]
-struct Summary {
-    errors: usize,
-    generated: usize,
-}
@@ -0,0 +1,303 @@
+//! Record of the harnesses extracted into an output directory, used to skip
+//! the harnesses whose inputs didn't change since the last extraction.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use mdnt_extractor_core::lookups::LookupRecord;
+
+use crate::picus::prelude::Preludes;
+
+const CACHE_FILE: &str = ".extraction-cache.json";
+
+/// Versions of the tools that produced the outputs.
+#[derive(Serialize, Deserialize, PartialEq)]
+struct Toolchain {
+    extractor: String,
+    midnight_circuits: String,
+    midnight_circuits_checksum: Option<String>,
+    #[serde(default)]
+    haloumi: String,
+    /// Fingerprint of the sources of the harnesses and of the extractor.
+    #[serde(default)]
+    sources: String,
+}
+
+impl Toolchain {
+    fn current() -> Self {
+        Self {
+            extractor: env!("CARGO_PKG_VERSION").to_owned(),
+            midnight_circuits: env!("MIDNIGHT_CIRCUITS_VERSION").to_owned(),
+            midnight_circuits_checksum: option_env!("MIDNIGHT_CIRCUITS_CHECKSUM")
+                .map(ToOwned::to_owned),
+            haloumi: env!("HALOUMI_VERSIONS").to_owned(),
+            sources: env!("SOURCES_FINGERPRINT").to_owned(),
+        }
+    }
+}
+
+/// Inputs that determine the outputs of a harness.
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct CacheKey {
+    toolchain: Toolchain,
+    constants: Vec<String>,
+    settings: BTreeMap<String, serde_json::Value>,
+}
+
+impl CacheKey {
+    /// Creates the key of a harness from its constants and the settings of the
+    /// extraction, given as serializable values by name.
+    pub fn new<'a>(
+        constants: &[String],
+        settings: impl IntoIterator<Item = (&'a str, serde_json::Value)>,
+    ) -> Self {
+        Self {
+            toolchain: Toolchain::current(),
+            constants: constants.to_vec(),
+            settings: settings.into_iter().map(|(k, v)| (k.to_owned(), v)).collect(),
+        }
+    }
+}
+
+/// Why a harness had to be extracted again.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    Forced,
+    WitnessCheck,
+    NotCached,
+    ToolchainChanged,
+    SourcesChanged,
+    ConstantsChanged,
+    SettingsChanged(Vec<String>),
+    MissingOutputs,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Forced => write!(f, "cache bypassed with --force"),
+            Reason::WitnessCheck => write!(f, "witnesses are checked on every extraction"),
+            Reason::NotCached => write!(f, "no previous extraction"),
+            Reason::ToolchainChanged => {
+                write!(f, "extractor, haloumi or midnight-circuits version changed")
+            }
+            Reason::SourcesChanged => write!(f, "harness or extractor sources changed"),
+            Reason::ConstantsChanged => write!(f, "constants changed"),
+            Reason::SettingsChanged(settings) => {
+                write!(f, "settings changed ({})", settings.join(", "))
+            }
+            Reason::MissingOutputs => write!(f, "outputs are missing"),
+        }
+    }
+}
+
+/// Whether a harness was extracted or its previous outputs were reused.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    UpToDate,
+    Regenerated(Reason),
+}
+
+/// Outputs of a harness and what is reported about them in the manifest.
+#[derive(Serialize, Deserialize)]
+pub struct CacheEntry {
+    key: CacheKey,
+    outputs: Vec<PathBuf>,
+    #[serde(default)]
+    preludes: Vec<Preludes>,
+    #[serde(default)]
+    lookups: Vec<LookupRecord>,
+}
+
+impl CacheEntry {
+    pub fn outputs(&self) -> &[PathBuf] {
+        &self.outputs
+    }
+
+    pub fn preludes(&self) -> &[Preludes] {
+        &self.preludes
+    }
+
+    pub fn lookups(&self) -> &[LookupRecord] {
+        &self.lookups
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Cache {
+    harnesses: BTreeMap<String, CacheEntry>,
+}
+
+impl Cache {
+    /// Loads the cache of the output directory. Starts with an empty cache if
+    /// there is none or it cannot be read.
+    pub fn load(output_base: &Path) -> Self {
+        let path = output_base.join(CACHE_FILE);
+        let Ok(file) = File::open(&path) else {
+            return Self::default();
+        };
+        serde_json::from_reader(BufReader::new(file)).unwrap_or_else(|err| {
+            log::warn!(
+                "Ignoring invalid extraction cache {}: {err}",
+                path.display()
+            );
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, output_base: &Path) -> Result<()> {
+        let output_file = BufWriter::new(File::create(output_base.join(CACHE_FILE))?);
+        serde_json::to_writer_pretty(output_file, self)?;
+        Ok(())
+    }
+
+    /// Returns the entry of the harness if its outputs were generated from the
+    /// same inputs and still exist, or the reason why they need to be
+    /// generated again.
+    pub fn check(&self, name: &str, key: &CacheKey) -> Result<&CacheEntry, Reason> {
+        let entry = self.harnesses.get(name).ok_or(Reason::NotCached)?;
+        if entry.key.toolchain.sources != key.toolchain.sources {
+            return Err(Reason::SourcesChanged);
+        }
+        if entry.key.toolchain != key.toolchain {
+            return Err(Reason::ToolchainChanged);
+        }
+        if entry.key.constants != key.constants {
+            return Err(Reason::ConstantsChanged);
+        }
+        if entry.key.settings != key.settings {
+            let changed = key
+                .settings
+                .keys()
+                .chain(entry.key.settings.keys())
+                .filter(|k| entry.key.settings.get(*k) != key.settings.get(*k))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            return Err(Reason::SettingsChanged(changed.into_iter().collect()));
+        }
+        if !entry.outputs.iter().all(|output| output.exists()) {
+            return Err(Reason::MissingOutputs);
+        }
+        Ok(entry)
+    }
+
+    pub fn insert(
+        &mut self,
+        name: &str,
+        key: CacheKey,
+        outputs: Vec<PathBuf>,
+        preludes: Vec<Preludes>,
+        lookups: Vec<LookupRecord>,
+    ) {
+        self.harnesses.insert(
+            name.to_owned(),
+            CacheEntry {
+                key,
+                outputs,
+                preludes,
+                lookups,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.harnesses.remove(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(constants: &[&str], debug_comments: bool) -> CacheKey {
+        let constants = constants.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        CacheKey::new(
+            &constants,
+            [
+                ("debug_comments", debug_comments.into()),
+                ("optimize_ir", true.into()),
+            ],
+        )
+    }
+
+    /// Returns a cache with an entry for `a/b/c/d` whose output exists.
+    fn cache_with_entry(dir: &Path) -> Cache {
+        std::fs::create_dir_all(dir).unwrap();
+        let output = dir.join("a_b_c_d.picus");
+        std::fs::write(&output, "").unwrap();
+        let mut cache = Cache::default();
+        cache.insert("a/b/c/d", key(&["1"], false), vec![output], vec![], vec![]);
+        cache
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{name}-test-{}", std::process::id()))
+    }
+
+    #[test]
+    fn reuses_outputs_of_same_inputs() {
+        let dir = temp_dir("cache-hit");
+        cache_with_entry(&dir).save(&dir).unwrap();
+        let cache = Cache::load(&dir);
+        let entry = cache.check("a/b/c/d", &key(&["1"], false)).ok().unwrap();
+        assert_eq!(entry.outputs(), [dir.join("a_b_c_d.picus")]);
+    }
+
+    #[test]
+    fn extracts_harnesses_without_outputs() {
+        let dir = temp_dir("cache-miss");
+        let mut cache = cache_with_entry(&dir);
+        let err = cache.check("a/b/c/e", &key(&["1"], false)).err().unwrap();
+        assert!(matches!(err, Reason::NotCached));
+
+        std::fs::remove_file(dir.join("a_b_c_d.picus")).unwrap();
+        let err = cache.check("a/b/c/d", &key(&["1"], false)).err().unwrap();
+        assert!(matches!(err, Reason::MissingOutputs));
+
+        cache.remove("a/b/c/d");
+        let err = cache.check("a/b/c/d", &key(&["1"], false)).err().unwrap();
+        assert!(matches!(err, Reason::NotCached));
+    }
+
+    #[test]
+    fn changed_inputs_invalidate_outputs() {
+        let cache = cache_with_entry(&temp_dir("cache-invalidation"));
+        let err = cache.check("a/b/c/d", &key(&["2"], false)).err().unwrap();
+        assert!(matches!(err, Reason::ConstantsChanged));
+
+        let err = cache.check("a/b/c/d", &key(&["1"], true)).err().unwrap();
+        assert!(matches!(&err, Reason::SettingsChanged(names) if names == &["debug_comments"]));
+
+        let mut upgraded = key(&["1"], false);
+        upgraded.toolchain.haloumi.push_str(", haloumi-ir 99.0.0");
+        let err = cache.check("a/b/c/d", &upgraded).err().unwrap();
+        assert!(matches!(err, Reason::ToolchainChanged));
+
+        let mut edited = key(&["1"], false);
+        edited.toolchain.sources = "0000000000000000".to_owned();
+        let err = cache.check("a/b/c/d", &edited).err().unwrap();
+        assert!(matches!(err, Reason::SourcesChanged));
+    }
+
+    #[test]
+    fn caches_without_fingerprint_are_invalidated() {
+        let dir = temp_dir("cache-old-format");
+        let mut cache = serde_json::to_value(cache_with_entry(&dir)).unwrap();
+        let toolchain = cache["harnesses"]["a/b/c/d"]["key"]["toolchain"].as_object_mut().unwrap();
+        toolchain.remove("haloumi");
+        toolchain.remove("sources");
+        let cache: Cache = serde_json::from_value(cache).unwrap();
+        let err = cache.check("a/b/c/d", &key(&["1"], false)).err().unwrap();
+        assert!(matches!(err, Reason::SourcesChanged));
+    }
+}
@@ -1,12 +1,17 @@
 use std::fmt;
 
+use serde::Serialize;
+
 #[allow(clippy::enum_variant_names)]
-#[derive(Debug)]
-enum AppErrorKind {
+#[derive(Debug, Copy, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppErrorKind {
+    ConstantsCheckFailed,
     HarnessFailed,
     OptFailed,
     IRDumpFailed,
     PicusWriteFailed,
+    SmtlibWriteFailed,
 }
 
 #[derive(Debug)]
@@ -21,6 +26,18 @@ impl AppError {
         move |err| Self { kind, name, err }
     }
 
+    pub fn kind(&self) -> AppErrorKind {
+        self.kind
+    }
+
+    pub fn error(&self) -> &anyhow::Error {
+        &self.err
+    }
+
+    pub fn constants(name: &'static str) -> impl FnOnce(anyhow::Error) -> Self {
+        Self::create(name, AppErrorKind::ConstantsCheckFailed)
+    }
+
     pub fn harness(name: &'static str) -> impl FnOnce(anyhow::Error) -> Self {
         Self::create(name, AppErrorKind::HarnessFailed)
     }
@@ -35,11 +52,20 @@ impl AppError {
     pub fn picus(name: &'static str) -> impl FnOnce(anyhow::Error) -> Self {
         Self::create(name, AppErrorKind::PicusWriteFailed)
     }
+
+    pub fn smtlib(name: &'static str) -> impl FnOnce(anyhow::Error) -> Self {
+        Self::create(name, AppErrorKind::SmtlibWriteFailed)
+    }
 }
 
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.kind {
+            AppErrorKind::ConstantsCheckFailed => write!(
+                f,
+                "Constants of harness {} don't match its inputs: {:?}",
+                self.name, self.err
+            ),
             AppErrorKind::HarnessFailed => {
                 write!(f, "Harness {} failed: {:?}", self.name, self.err)
             }
@@ -58,6 +84,11 @@ impl fmt::Display for AppError {
                 "Failed to write Picus result of harness {}: {:?}",
                 self.name, self.err
             ),
+            AppErrorKind::SmtlibWriteFailed => write!(
+                f,
+                "Failed to write SMT-LIB result of harness {}: {:?}",
+                self.name, self.err
+            ),
         }
     }
 }
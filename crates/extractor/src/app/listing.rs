@@ -0,0 +1,98 @@
+//! Metadata of the harnesses printed by `--list --verbose`.
+
+use std::fmt;
+
+use mdnt_extractor_core::harness::metadata::Metadata;
+
+use crate::picus::prelude::Preludes;
+
+/// Writes the metadata of a harness indented, to go under its name.
+pub struct Details<'a>(pub &'a Metadata);
+
+impl Details<'_> {
+    fn write_list<T: fmt::Display>(
+        f: &mut fmt::Formatter<'_>,
+        label: &str,
+        items: impl IntoIterator<Item = T>,
+    ) -> fmt::Result {
+        let items: Vec<_> = items.into_iter().map(|item| item.to_string()).collect();
+        if items.is_empty() {
+            return Ok(());
+        }
+        writeln!(f, "    {label}: {}", items.join(", "))
+    }
+}
+
+impl fmt::Display for Details<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metadata = self.0;
+        for line in metadata.description().into_iter().flat_map(str::lines) {
+            if line.is_empty() {
+                writeln!(f)?;
+            } else {
+                writeln!(f, "    {line}")?;
+            }
+        }
+
+        // The constants after a vector length or a flag depend on its value.
+        let constants = &metadata.constants()[..metadata.required_constants()];
+        match constants.last() {
+            None => writeln!(f, "    constants: none")?,
+            Some(kind) if kind.is_variable() => Self::write_list(
+                f,
+                "constants",
+                constants.iter().map(ToString::to_string).chain(["...".to_owned()]),
+            )?,
+            Some(_) => Self::write_list(f, "constants", constants)?,
+        }
+
+        Self::write_list(
+            f,
+            "lookups",
+            metadata.lookups().iter().map(|handler| handler.name()),
+        )?;
+        let mut preludes = vec![];
+        let mut undeclared = vec![];
+        for &module in metadata.modules() {
+            match Preludes::declaring(module) {
+                Some(prelude) if !preludes.contains(&prelude) => preludes.push(prelude),
+                Some(_) => {}
+                None => undeclared.push(module),
+            }
+        }
+        Self::write_list(f, "preludes", preludes.iter().map(Preludes::name))?;
+        Self::write_list(f, "modules without a prelude", undeclared)?;
+        Self::write_list(f, "tags", metadata.tags())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mdnt_extractor_core::harness::metadata::ConstantKind;
+
+    use super::*;
+
+    #[test]
+    fn writes_the_metadata_under_the_name() {
+        let metadata = Metadata::default()
+            .with_description("Hashes the bytes.\n\nSecond paragraph.")
+            .with_constants(vec![
+                ConstantKind::Field,
+                ConstantKind::Length,
+                ConstantKind::Field,
+            ])
+            .with_tags(&["slow"]);
+        assert_eq!(
+            Details(&metadata).to_string(),
+            "    Hashes the bytes.\n\n    Second paragraph.\n    constants: field element, length, ...\n    tags: slow\n"
+        );
+    }
+
+    #[test]
+    fn writes_none_without_constants() {
+        assert_eq!(
+            Details(&Metadata::default()).to_string(),
+            "    constants: none\n"
+        );
+    }
+}
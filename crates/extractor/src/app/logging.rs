@@ -0,0 +1,165 @@
+//! Global logger that can hold back the records emitted by a thread and write
+//! them together later. Used to keep the output of each harness grouped when
+//! extracting in parallel.
+
+use std::{
+    cell::RefCell,
+    sync::{Mutex, OnceLock, PoisonError},
+};
+
+use log::{Log, Metadata, Record, SetLoggerError};
+use multi_log::MultiLogger;
+
+static LOGGER: OnceLock<GroupedLogger> = OnceLock::new();
+/// Serializes the writes to the inner loggers so groups are not interleaved.
+static WRITE: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    static GROUP: RefCell<Option<Vec<BufferedRecord>>> = const { RefCell::new(None) };
+}
+
+/// Owned copy of a [`Record`].
+struct BufferedRecord {
+    level: log::Level,
+    target: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    args: String,
+}
+
+impl From<&Record<'_>> for BufferedRecord {
+    fn from(record: &Record<'_>) -> Self {
+        Self {
+            level: record.level(),
+            target: record.target().to_owned(),
+            module_path: record.module_path().map(ToOwned::to_owned),
+            file: record.file().map(ToOwned::to_owned),
+            line: record.line(),
+            args: record.args().to_string(),
+        }
+    }
+}
+
+struct GroupedLogger {
+    inner: MultiLogger,
+}
+
+impl GroupedLogger {
+    fn write(&self, record: &BufferedRecord) {
+        self.inner.log(
+            &Record::builder()
+                .args(format_args!("{}", record.args))
+                .level(record.level)
+                .target(&record.target)
+                .module_path(record.module_path.as_deref())
+                .file(record.file.as_deref())
+                .line(record.line)
+                .build(),
+        );
+    }
+}
+
+impl Log for GroupedLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let buffered = GROUP.with_borrow_mut(|group| match group {
+            Some(group) => {
+                group.push(record.into());
+                true
+            }
+            None => false,
+        });
+        if !buffered {
+            let _guard = WRITE.lock().unwrap_or_else(PoisonError::into_inner);
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Installs the given loggers as the global logger.
+pub fn init(loggers: Vec<Box<dyn Log>>, level: log::Level) -> Result<(), SetLoggerError> {
+    let logger = LOGGER.get_or_init(|| GroupedLogger {
+        inner: MultiLogger::new(loggers),
+    });
+    log::set_max_level(level.to_level_filter());
+    log::set_logger(logger)
+}
+
+/// Returns true if [`init`] already installed the global logger.
+pub fn is_initialized() -> bool {
+    LOGGER.get().is_some()
+}
+
+/// Writes the records held back by the current thread when dropped, so they
+/// are not lost if the thread panics.
+struct Group;
+
+impl Drop for Group {
+    fn drop(&mut self) {
+        let records = GROUP.take().unwrap_or_default();
+        if let Some(logger) = LOGGER.get() {
+            let _guard = WRITE.lock().unwrap_or_else(PoisonError::into_inner);
+            for record in &records {
+                logger.write(record);
+            }
+        }
+    }
+}
+
+/// Runs the closure holding back the records logged by the current thread and
+/// writes them in one block once it finishes or panics.
+pub fn grouped<R>(f: impl FnOnce() -> R) -> R {
+    GROUP.set(Some(vec![]));
+    let _group = Group;
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{panic, sync::Mutex};
+
+    use super::*;
+
+    static WRITTEN: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+    struct Capture;
+
+    impl Log for Capture {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            WRITTEN.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn writes_records_of_panicking_group() {
+        init(vec![Box::new(Capture)], log::Level::Info).unwrap();
+        let result = panic::catch_unwind(|| {
+            grouped(|| {
+                log::info!("before the panic");
+                let written = WRITTEN.lock().unwrap();
+                assert!(!written.contains(&"before the panic".to_owned()));
+                drop(written);
+                panic!("harness panicked");
+            })
+        });
+        assert!(result.is_err());
+        assert!(WRITTEN.lock().unwrap().contains(&"before the panic".to_owned()));
+    }
+}
@@ -0,0 +1,303 @@
+//! Machine-readable report of an extraction run.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Result;
+use chrono::Utc;
+use haloumi::ir_gen::circuit::resolved::ResolvedIRCircuit;
+use mdnt_extractor_core::lookups::LookupRecord;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    cache::CacheStatus,
+    error::{AppError, AppErrorKind},
+};
+use crate::{picus::prelude::Preludes, query::HarnessName};
+
+#[derive(Serialize)]
+pub struct Manifest {
+    version: &'static str,
+    midnight_circuits: &'static str,
+    midnight_circuits_checksum: Option<&'static str>,
+    timestamp: String,
+    harnesses: Vec<HarnessReport>,
+}
+
+impl Manifest {
+    pub fn new(mut harnesses: Vec<HarnessReport>) -> Self {
+        harnesses.sort_by_key(|h| h.name);
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            midnight_circuits: env!("MIDNIGHT_CIRCUITS_VERSION"),
+            midnight_circuits_checksum: option_env!("MIDNIGHT_CIRCUITS_CHECKSUM"),
+            timestamp: Utc::now().to_rfc3339(),
+            harnesses,
+        }
+    }
+
+    /// Writes the manifest into `<output_base>/manifest.json`.
+    ///
+    /// The harnesses listed by the manifest already in the directory that
+    /// were not selected in this run are kept, so the manifest describes every
+    /// harness extracted into the directory.
+    pub fn write(&self, output_base: &Path) -> Result<()> {
+        let output_path = output_base.join("manifest.json");
+        let mut manifest = serde_json::to_value(self)?;
+        if let Some(Value::Array(harnesses)) = manifest.get_mut("harnesses") {
+            merge_harnesses(harnesses, previous_harnesses(&output_path));
+        }
+        let output_file = BufWriter::new(File::create(&output_path)?);
+        serde_json::to_writer_pretty(output_file, &manifest)?;
+        log::info!("Saved extraction manifest in {}", output_path.display());
+        Ok(())
+    }
+}
+
+/// Harnesses of a manifest written by an earlier run.
+#[derive(Deserialize)]
+struct PreviousManifest {
+    harnesses: Vec<Value>,
+}
+
+/// Reads the harnesses of the manifest at `path`. A missing or unreadable
+/// manifest has none.
+fn previous_harnesses(path: &Path) -> Vec<Value> {
+    let Ok(file) = File::open(path) else {
+        return vec![];
+    };
+    match serde_json::from_reader::<_, PreviousManifest>(BufReader::new(file)) {
+        Ok(manifest) => manifest.harnesses,
+        Err(err) => {
+            log::warn!("Ignoring the previous manifest {}: {err}", path.display());
+            vec![]
+        }
+    }
+}
+
+/// Adds the previous harnesses that are not in `harnesses` and sorts them by
+/// name.
+fn merge_harnesses(harnesses: &mut Vec<Value>, previous: Vec<Value>) {
+    let name = |harness: &Value| harness.get("name").and_then(Value::as_str).map(str::to_owned);
+    for harness in previous {
+        let Some(previous_name) = name(&harness) else {
+            continue;
+        };
+        if !harnesses.iter().any(|h| name(h).as_ref() == Some(&previous_name)) {
+            harnesses.push(harness);
+        }
+    }
+    harnesses.sort_by_key(name);
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    #[serde(untagged)]
+    Failed(AppErrorKind),
+}
+
+/// Summary of the IR of a harness.
+#[derive(Serialize, Default)]
+pub struct IrStats {
+    groups: usize,
+    inputs: usize,
+    outputs: usize,
+    statements: usize,
+    injected: usize,
+    callsites: usize,
+}
+
+impl From<&ResolvedIRCircuit> for IrStats {
+    fn from(ir: &ResolvedIRCircuit) -> Self {
+        let main = ir.main();
+        ir.groups().iter().fold(
+            Self {
+                inputs: main.input_count(),
+                outputs: main.output_count(),
+                ..Default::default()
+            },
+            |stats, group| Self {
+                groups: stats.groups + 1,
+                statements: stats.statements
+                    + group.statements().flat_map(|stmt| stmt.iter()).count(),
+                injected: stats.injected + group.injected_count(),
+                callsites: stats.callsites + group.callsites().len(),
+                ..stats
+            },
+        )
+    }
+}
+
+/// What happened to a harness during extraction.
+#[derive(Serialize)]
+pub struct HarnessReport {
+    name: &'static str,
+    instruction: &'static str,
+    method: &'static str,
+    chip: &'static str,
+    r#type: &'static str,
+    field: &'static str,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    outputs: Vec<PathBuf>,
+    constants: Vec<String>,
+    preludes: Vec<Preludes>,
+    lookups: Vec<LookupRecord>,
+    wall_time_secs: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache: Option<CacheStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ir: Option<IrStats>,
+}
+
+impl HarnessReport {
+    pub fn new(name: &'static str, constants: &[String]) -> Self {
+        let parts = HarnessName::parse(name)
+            .expect("names of registered harnesses are checked when they are compiled");
+        Self {
+            name,
+            instruction: parts.instruction(),
+            method: parts.method(),
+            chip: parts.chip(),
+            r#type: parts.r#type(),
+            field: parts.field(),
+            status: Status::Ok,
+            error: None,
+            outputs: vec![],
+            constants: constants.to_vec(),
+            preludes: vec![],
+            lookups: vec![],
+            wall_time_secs: 0.0,
+            cache: None,
+            ir: None,
+        }
+    }
+
+    pub fn add_output(&mut self, path: PathBuf) {
+        self.outputs.push(path);
+    }
+
+    pub fn outputs(&self) -> &[PathBuf] {
+        &self.outputs
+    }
+
+    pub fn set_cache(&mut self, status: CacheStatus) {
+        self.cache = Some(status);
+    }
+
+    pub fn is_up_to_date(&self) -> bool {
+        matches!(self.cache, Some(CacheStatus::UpToDate))
+    }
+
+    pub fn preludes(&self) -> &[Preludes] {
+        &self.preludes
+    }
+
+    pub fn add_preludes(&mut self, preludes: impl IntoIterator<Item = Preludes>) {
+        for prelude in preludes {
+            if !self.preludes.contains(&prelude) {
+                self.preludes.push(prelude);
+            }
+        }
+    }
+
+    pub fn lookups(&self) -> &[LookupRecord] {
+        &self.lookups
+    }
+
+    pub fn set_lookups(&mut self, lookups: Vec<LookupRecord>) {
+        self.lookups = lookups;
+    }
+
+    pub fn set_ir(&mut self, ir: &ResolvedIRCircuit) {
+        self.ir = Some(ir.into());
+    }
+
+    pub fn finish(&mut self, wall_time: Duration, result: Result<(), &AppError>) {
+        self.wall_time_secs = wall_time.as_secs_f64();
+        if let Err(err) = result {
+            self.status = Status::Failed(err.kind());
+            self.error = Some(format!("{:#}", err.error()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn read(dir: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(dir.join("manifest.json")).unwrap()).unwrap()
+    }
+
+    fn names(manifest: &Value) -> Vec<&str> {
+        manifest["harnesses"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|h| h["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn reports_status_and_error() {
+        let mut ok = HarnessReport::new("a/b/c/d", &["1".to_owned()]);
+        ok.add_preludes([Preludes::Spread, Preludes::Spread]);
+        ok.finish(Duration::from_millis(1500), Ok(()));
+        let mut failed = HarnessReport::new("a/a/c/d", &[]);
+        let err = AppError::picus("a/a/c/d")(anyhow::anyhow!("boom"));
+        failed.finish(Duration::ZERO, Err(&err));
+
+        let manifest = serde_json::to_value(Manifest::new(vec![ok, failed])).unwrap();
+        assert_eq!(names(&manifest), ["a/a/c/d", "a/b/c/d"]);
+        let [failed, ok] = manifest["harnesses"].as_array().unwrap().as_slice() else {
+            panic!("expected two harnesses");
+        };
+        assert_eq!(failed["status"], "picus_write_failed");
+        assert_eq!(failed["error"], "boom");
+        assert_eq!(ok["status"], "ok");
+        assert_eq!(ok["instruction"], "a");
+        assert_eq!(ok["constants"], serde_json::json!(["1"]));
+        assert_eq!(ok["preludes"], serde_json::json!(["spread"]));
+        assert_eq!(ok["wall_time_secs"], 1.5);
+        assert!(ok.get("error").is_none());
+    }
+
+    #[test]
+    fn keeps_harnesses_of_earlier_runs() {
+        let dir = std::env::temp_dir().join(format!("manifest-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let _ = fs::remove_file(dir.join("manifest.json"));
+
+        let first = vec![
+            HarnessReport::new("a/b/c/d", &["1".to_owned()]),
+            HarnessReport::new("x/y/z/w", &[]),
+        ];
+        Manifest::new(first).write(&dir).unwrap();
+        let second = vec![HarnessReport::new("a/b/c/d", &["2".to_owned()])];
+        Manifest::new(second).write(&dir).unwrap();
+
+        let manifest = read(&dir);
+        assert_eq!(names(&manifest), ["a/b/c/d", "x/y/z/w"]);
+        // The harness extracted again takes the report of the latest run.
+        assert_eq!(
+            manifest["harnesses"][0]["constants"],
+            serde_json::json!(["2"])
+        );
+
+        fs::write(dir.join("manifest.json"), "not json").unwrap();
+        Manifest::new(vec![HarnessReport::new("x/y/z/w", &[])]).write(&dir).unwrap();
+        assert_eq!(names(&read(&dir)), ["x/y/z/w"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+//! Runs the extraction of the selected harnesses, either one after the other
+//! or on a pool of threads.
+
+use std::{
+    ops::AddAssign,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+use anyhow::Result;
+
+use super::{error::AppError, logging};
+use crate::config::FailMode;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub errors: usize,
+    pub generated: usize,
+}
+
+impl AddAssign for Summary {
+    fn add_assign(&mut self, rhs: Self) {
+        self.errors += rhs.errors;
+        self.generated += rhs.generated;
+    }
+}
+
+fn handle_extract_result(
+    fail_mode: FailMode,
+    extract: impl FnOnce() -> Result<(), AppError>,
+    summary: &mut Summary,
+) -> Result<()> {
+    summary.generated += 1;
+    match extract() {
+        Err(err) => match fail_mode {
+            FailMode::Fast => Err(err.into()),
+            FailMode::Continue => {
+                log::error!("{err}");
+                summary.errors += 1;
+                Ok(())
+            }
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Extracts the harnesses in order.
+pub fn extract_sequential<H>(
+    harness: Vec<(&'static str, H)>,
+    fail_mode: FailMode,
+    extract_one: impl Fn(&'static str, H) -> Result<(), AppError>,
+) -> Result<Summary> {
+    harness
+        .into_iter()
+        .try_fold(Summary::default(), |mut summary, (name, harness)| {
+            handle_extract_result(fail_mode, || extract_one(name, harness), &mut summary)?;
+            Ok(summary)
+        })
+}
+
+/// Extracts the harnesses on a pool of `jobs` threads.
+///
+/// The log output of each harness is written in one block once the harness
+/// finishes. In [`FailMode::Fast`] the first failure prevents the remaining
+/// harnesses from starting.
+pub fn extract_parallel<H: Copy + Sync>(
+    harness: &[(&'static str, H)],
+    jobs: usize,
+    fail_mode: FailMode,
+    extract_one: impl Fn(&'static str, H) -> Result<(), AppError> + Sync,
+) -> Result<Summary> {
+    log::info!("Extracting {} harnesses with {jobs} jobs", harness.len());
+    let next = AtomicUsize::new(0);
+    let cancelled = AtomicBool::new(false);
+    let summary = Mutex::new(Summary::default());
+    let failure = Mutex::new(None);
+    std::thread::scope(|s| {
+        for _ in 0..jobs {
+            s.spawn(|| {
+                while !cancelled.load(Ordering::Relaxed) {
+                    let Some(&(name, h)) = harness.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+                    let mut local = Summary::default();
+                    let result = logging::grouped(|| {
+                        handle_extract_result(fail_mode, || extract_one(name, h), &mut local)
+                    });
+                    *summary.lock().unwrap_or_else(PoisonError::into_inner) += local;
+                    if let Err(err) = result {
+                        cancelled.store(true, Ordering::Relaxed);
+                        failure.lock().unwrap_or_else(PoisonError::into_inner).get_or_insert(err);
+                    }
+                }
+            });
+        }
+    });
+
+    if let Some(err) = failure.into_inner().unwrap_or_else(PoisonError::into_inner) {
+        let outstanding = harness.len().saturating_sub(next.load(Ordering::Relaxed));
+        if outstanding > 0 {
+            log::info!("Cancelled {outstanding} outstanding harnesses");
+        }
+        return Err(err);
+    }
+    Ok(summary.into_inner().unwrap_or_else(PoisonError::into_inner))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    fn harness(n: usize) -> Vec<(&'static str, usize)> {
+        (0..n).map(|i| ("a/b/c/d", i)).collect()
+    }
+
+    /// Fails the harnesses whose index is a multiple of 3.
+    fn fail_every_third(name: &'static str, i: usize) -> Result<(), AppError> {
+        if i % 3 == 0 {
+            return Err(AppError::harness(name)(anyhow::anyhow!("failed")));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn merges_summaries_of_each_thread() {
+        let summary =
+            extract_parallel(&harness(10), 4, FailMode::Continue, fail_every_third).unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                errors: 4,
+                generated: 10
+            }
+        );
+        let sequential =
+            extract_sequential(harness(10), FailMode::Continue, fail_every_third).unwrap();
+        assert_eq!(summary, sequential);
+    }
+
+    #[test]
+    fn fail_fast_cancels_outstanding_harnesses() {
+        let started = AtomicUsize::new(0);
+        let result = extract_parallel(&harness(50), 2, FailMode::Fast, |name, i| {
+            started.fetch_add(1, Ordering::Relaxed);
+            if i == 0 {
+                return Err(AppError::harness(name)(anyhow::anyhow!("failed")));
+            }
+            thread::sleep(Duration::from_millis(10));
+            Ok(())
+        });
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("a/b/c/d"), "{err}");
+        assert!(started.load(Ordering::Relaxed) < 50);
+    }
+}
@@ -1,64 +1,131 @@
 use log::Level;
 use std::{
-    fs::File,
-    io::BufReader,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
 };
 
 use crate::{
-    chips::{Chip, Type},
     config::{Action, AppConfig, FailMode, HarnessConfig, LoggingConfig, OutputFormat},
-    instructions::Instructions,
+    constants::{Constants, ConstantsError},
     picus::{prelude::Preludes, PicusConfig},
-    utils::parse_constants_file,
+    verify::DEFAULT_SOLVER,
+};
+use clap::{ArgGroup, Parser, Subcommand};
+use mdnt_extractor_core::{
+    circuit::planner::FloorPlanning,
+    taxonomy::{Chip, Instructions, Type},
 };
-use clap::Parser;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+#[command(group(ArgGroup::new("extraction").multiple(true)))]
 pub struct Cli {
-    #[arg(value_enum)]
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[arg(value_enum, group = "extraction")]
     instructions: Vec<Instructions>,
-    #[arg(long, value_enum)]
+    #[arg(long, value_enum, group = "extraction")]
     chip: Option<Chip>,
-    #[arg(long, value_delimiter = ',')]
+    #[arg(long, value_delimiter = ',', group = "extraction")]
     ignore_chips: Vec<Chip>,
-    #[arg(long, value_delimiter = ',')]
+    #[arg(long, value_delimiter = ',', group = "extraction")]
     format: Vec<OutputFormat>,
-    #[arg(long, value_enum)]
+    #[arg(long, value_enum, group = "extraction")]
     r#type: Option<Type>,
-    #[arg(short, long)]
+    #[arg(short, long, group = "extraction")]
     output: Option<PathBuf>,
-    #[arg(long, value_delimiter = ',')]
+    #[arg(long, value_delimiter = ',', group = "extraction")]
     constants: Vec<String>,
-    #[arg(long)]
+    #[arg(long, group = "extraction")]
     constants_file: Option<PathBuf>,
-    #[arg(long, value_delimiter = ',')]
+    #[arg(long, value_delimiter = ',', group = "extraction")]
     method_whitelist: Vec<String>,
-    #[arg(long, value_delimiter = ',')]
+    #[arg(long, value_delimiter = ',', group = "extraction")]
     method_blacklist: Vec<String>,
+    #[arg(long, group = "extraction")]
+    select: Option<String>,
+    /// Overrides the log file of a project file
     #[arg(long)]
     pub log: Option<PathBuf>,
-    #[arg(long, default_value_t = Level::Info)]
-    pub log_level: Level,
+    /// Log level [default: info]. Overrides the one of a project file
     #[arg(long)]
+    pub log_level: Option<Level>,
+    #[arg(long, group = "extraction")]
     pub disable_decomposition_rewrite: bool,
-    #[arg(long)]
+    #[arg(long, group = "extraction")]
     pub debug_comments: bool,
-    #[arg(long)]
+    #[arg(long, group = "extraction")]
     pub picus_no_opt: bool,
-    #[arg(long)]
+    #[arg(long, group = "extraction")]
     pub no_opt: bool,
-    #[arg(long)]
+    #[arg(long, group = "extraction")]
     pub fail_fast: bool,
-    #[arg(long)]
-    pub prelude: Option<Preludes>,
-    #[arg(long)]
+    /// Number of harnesses extracted in parallel [default: 1]. Overrides the
+    /// one of a project file
+    #[arg(short, long)]
+    pub jobs: Option<NonZeroUsize>,
+    #[arg(long, value_delimiter = ',', group = "extraction")]
+    pub prelude: Vec<Preludes>,
+    #[arg(long, group = "extraction")]
     pub dump_ir: bool,
     #[arg(long)]
-    pub list: bool,
+    pub force: bool,
     #[arg(long)]
+    pub list: bool,
+    /// Print the description, constants, lookups, preludes and tags of each
+    /// listed harness
+    #[arg(long, requires = "list")]
+    pub verbose: bool,
+    #[arg(long, group = "extraction")]
     allow_injected_ir_for_outputs: bool,
+    /// Check the IR of each harness against this many witnesses computed by
+    /// the mock prover
+    #[arg(
+        long,
+        value_name = "SAMPLES",
+        num_args = 0..=1,
+        default_value_t = 0,
+        default_missing_value = "8",
+        group = "extraction"
+    )]
+    check_witnesses: usize,
+    /// Floor planner whose layout the extraction reproduces
+    #[arg(long, value_name = "PLANNER", default_value_t = FloorPlanning::Simple, group = "extraction")]
+    floor_planner: FloorPlanning,
+    /// Fail the harnesses with lookups that are ignored or have no handler
+    #[arg(long, group = "extraction")]
+    strict_lookups: bool,
+    #[arg(long, conflicts_with = "extraction")]
+    pub config: Option<PathBuf>,
+    #[arg(long, value_delimiter = ',', requires = "config")]
+    pub run: Vec<String>,
+    #[arg(skip)]
+    resolved_constants: Constants,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Compare the outputs of two extractions harness by harness
+    Diff { old: PathBuf, new: PathBuf },
+    /// Run a solver on the Picus outputs of an extraction
+    Verify {
+        #[arg(default_value = "picus_files")]
+        dir: PathBuf,
+        /// Selection expression of the harnesses to verify
+        #[arg(long)]
+        select: Option<String>,
+        /// Command that runs the solver on one output
+        #[arg(long, default_value = DEFAULT_SOLVER)]
+        solver: String,
+        /// Time limit of each output in seconds
+        #[arg(long, default_value_t = 600)]
+        time_limit: u64,
+        #[arg(short, long, default_value = "1")]
+        jobs: NonZeroUsize,
+        /// File to write the results to, <dir>/verify.json by default
+        #[arg(long)]
+        results: Option<PathBuf>,
+    },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -66,14 +133,16 @@ pub enum CliError {
     #[error("Cannot set --constants and --constants-file at the same time")]
     ConstantsConfigErr,
     #[error(transparent)]
-    IOError(#[from] std::io::Error),
+    ConstantsError(#[from] ConstantsError),
 }
 
 impl AppConfig for Cli {
     type Error = CliError;
 
     fn logging(&self) -> Option<LoggingConfig> {
-        self.log.as_deref().map(|path| LoggingConfig::new(path, self.log_level))
+        self.log
+            .as_deref()
+            .map(|path| LoggingConfig::new(path, self.log_level.unwrap_or(Level::Info)))
     }
 
     fn fail_mode(&self) -> FailMode {
@@ -84,18 +153,23 @@ impl AppConfig for Cli {
         }
     }
 
+    fn jobs(&self) -> NonZeroUsize {
+        self.jobs.unwrap_or(NonZeroUsize::MIN)
+    }
+
+    fn force(&self) -> bool {
+        self.force
+    }
+
     fn setup(&mut self) -> std::result::Result<(), Self::Error> {
         match (self.constants.is_empty(), self.constants_file.as_ref()) {
             (false, Some(_)) => return Err(CliError::ConstantsConfigErr),
-            (true, Some(path)) => {
-                let f = File::open(path)?;
-                let reader = BufReader::new(f);
-                self.constants = parse_constants_file(reader)?
-            }
-            (true, None) => {
-                log::warn!("No constants provided! Some circuits may fail to extract due to this.");
-            }
-            (false, None) => {} // Don't do anything
+            (true, Some(path)) => self.resolved_constants = Constants::from_file(path)?,
+            (true, None) => {}
+            (false, None) => self.resolved_constants = Constants::new(self.constants.clone()),
+        }
+        if self.resolved_constants.is_empty() {
+            log::warn!("No constants provided! Some circuits may fail to extract due to this.");
         }
         Ok(())
     }
@@ -124,20 +198,24 @@ impl AppConfig for Cli {
         &self.method_blacklist
     }
 
-    fn constants(&self) -> &[String] {
-        &self.constants
+    fn select(&self) -> Option<&str> {
+        self.select.as_deref()
+    }
+
+    fn constants(&self) -> &Constants {
+        &self.resolved_constants
     }
 
     fn output(&self) -> Option<&Path> {
         self.output.as_deref()
     }
 
-    fn prelude(&self) -> Option<Preludes> {
-        self.prelude
+    fn preludes(&self) -> &[Preludes] {
+        &self.prelude
     }
 
     fn picus_config(&self) -> PicusConfig {
-        PicusConfig::new(!(self.picus_no_opt || self.no_opt), self.prelude)
+        PicusConfig::new(!(self.picus_no_opt || self.no_opt), self.prelude.clone())
     }
 
     fn dump_ir(&self) -> bool {
@@ -146,7 +224,9 @@ impl AppConfig for Cli {
 
     fn action(&self) -> Action {
         if self.list {
-            Action::List
+            Action::List {
+                verbose: self.verbose,
+            }
         } else {
             Action::Extract
         }
@@ -161,10 +241,13 @@ impl AppConfig for Cli {
 
     fn harness_config(&self) -> HarnessConfig {
         HarnessConfig::new(
-            &self.constants,
+            &self.resolved_constants,
             self.debug_comments,
             !self.disable_decomposition_rewrite,
             self.allow_injected_ir_for_outputs,
+            self.check_witnesses,
+            self.floor_planner,
+            self.strict_lookups,
         )
     }
 
@@ -204,3 +287,40 @@ impl AppConfig for Cli {
 //
 //    pub fn prepare_constants(&mut self) -> Result<()> {}
 //}
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn config_conflicts_with_extraction_options() {
+        let cli = Cli::try_parse_from(["extractor", "--config", "p.toml", "--list", "--force"]);
+        assert!(cli.is_ok());
+        let cli = Cli::try_parse_from(["extractor", "--config", "p.toml", "--run", "native"]);
+        assert!(cli.is_ok());
+        let err = Cli::try_parse_from(["extractor", "--config", "p.toml", "--no-opt"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn invocation_settings_are_allowed_with_config() {
+        let cli = Cli::try_parse_from([
+            "extractor",
+            "--jobs",
+            "8",
+            "--log",
+            "run.log",
+            "--log-level",
+            "debug",
+            "--config",
+            "p.toml",
+        ])
+        .unwrap();
+        assert_eq!(cli.jobs, NonZeroUsize::new(8));
+        assert_eq!(cli.log_level, Some(Level::Debug));
+        let cli = Cli::try_parse_from(["extractor"]).unwrap();
+        assert_eq!(cli.jobs(), NonZeroUsize::MIN);
+    }
+}
@@ -1,27 +1,37 @@
-use std::path::{Path, PathBuf};
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
 
-use mdnt_extractor_core::harness::Ctx;
+use mdnt_extractor_core::{
+    circuit::planner::FloorPlanning,
+    harness::Ctx,
+    taxonomy::{Chip, Instructions, Type},
+};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    chips::{Chip, Type},
-    instructions::Instructions,
+    constants::Constants,
     picus::{prelude::Preludes, PicusConfig},
 };
 
 pub enum Action {
-    List,
+    List { verbose: bool },
     Extract,
 }
 
+#[derive(Copy, Clone, Debug)]
 pub enum FailMode {
     Fast,
     Continue,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     Picus,
+    Smtlib,
 }
 
 pub trait AppConfig {
@@ -43,11 +53,19 @@ pub trait AppConfig {
 
     fn method_blacklist(&self) -> &[String];
 
-    fn constants(&self) -> &[String];
+    /// Selection expression that the harnesses have to match on top of the
+    /// other filters.
+    fn select(&self) -> Option<&str> {
+        None
+    }
+
+    fn constants(&self) -> &Constants;
 
     fn output(&self) -> Option<&Path>;
 
-    fn prelude(&self) -> Option<Preludes>;
+    fn preludes(&self) -> &[Preludes] {
+        &[]
+    }
 
     fn picus_config(&self) -> PicusConfig;
 
@@ -57,6 +75,16 @@ pub trait AppConfig {
 
     fn fail_mode(&self) -> FailMode;
 
+    /// Number of harnesses extracted in parallel. Defaults to one at a time.
+    fn jobs(&self) -> NonZeroUsize {
+        NonZeroUsize::MIN
+    }
+
+    /// Extract the harnesses even if their outputs are up to date.
+    fn force(&self) -> bool {
+        false
+    }
+
     fn action(&self) -> Action;
 
     fn formats(&self) -> &[OutputFormat];
@@ -64,6 +92,7 @@ pub trait AppConfig {
     fn optimize_ir(&self) -> bool;
 }
 
+#[derive(Clone, Debug)]
 pub struct LoggingConfig {
     path: PathBuf,
     level: log::Level,
@@ -86,34 +115,54 @@ impl LoggingConfig {
     }
 }
 
+#[derive(Serialize)]
 pub struct HarnessConfig<'s> {
-    constants: &'s [String],
+    #[serde(skip)]
+    constants: &'s Constants,
     debug_comments: bool,
     enable_decomposition_rewrite: bool,
     allow_injected_ir_for_outputs: bool,
+    /// Number of witnesses checked against the IR of each harness.
+    witness_samples: usize,
+    floor_planner: FloorPlanning,
+    /// Fail the harnesses with lookups that are ignored or have no handler.
+    strict_lookups: bool,
 }
 
 impl<'s> HarnessConfig<'s> {
     pub fn new(
-        constants: &'s [String],
+        constants: &'s Constants,
         debug_comments: bool,
         enable_decomposition_rewrite: bool,
         allow_injected_ir_for_outputs: bool,
+        witness_samples: usize,
+        floor_planner: FloorPlanning,
+        strict_lookups: bool,
     ) -> Self {
         Self {
             constants,
             debug_comments,
             enable_decomposition_rewrite,
             allow_injected_ir_for_outputs,
+            witness_samples,
+            floor_planner,
+            strict_lookups,
         }
     }
 
-    pub(crate) fn make_ctx(&self) -> Ctx {
+    pub fn witness_samples(&self) -> usize {
+        self.witness_samples
+    }
+
+    pub(crate) fn make_ctx(&self, name: &str) -> Ctx {
         Ctx::new(
-            self.constants,
+            self.constants.for_harness(name),
             self.debug_comments,
             !self.enable_decomposition_rewrite,
             self.allow_injected_ir_for_outputs,
+            self.witness_samples,
+            self.floor_planner,
+            self.strict_lookups,
         )
     }
 }
@@ -0,0 +1,201 @@
+//! Constants passed to the harnesses.
+//!
+//! Constants are either a single list shared by every harness or a file that
+//! maps selection expressions to lists of constants. For example, in TOML:
+//!
+//! ```toml
+//! default = "1,2,3"
+//!
+//! [[harness]]
+//! select = "*/*/foreign-ecc-native|foreign-ecc-field/point"
+//! constants = ["8765...", "4567...", "0"]
+//! ```
+//!
+//! Each harness takes the constants of the first entry whose selection matches
+//! its name, or the default list if none does.
+
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    query::{Query, QueryParseError},
+    utils::parse_constants_file,
+};
+
+#[derive(Debug, Default)]
+pub struct Constants {
+    default: Vec<String>,
+    harnesses: Vec<(Query, Vec<String>)>,
+}
+
+impl Constants {
+    /// Creates constants shared by every harness.
+    pub fn new(default: Vec<String>) -> Self {
+        Self {
+            default,
+            harnesses: vec![],
+        }
+    }
+
+    /// Loads the constants from a file.
+    ///
+    /// Files ending in `.toml` or `.json` can map harnesses to constants. Any
+    /// other file is read as a plain list of constants separated by commas or
+    /// whitespace.
+    pub fn from_file(path: &Path) -> Result<Self, ConstantsError> {
+        let parse_err = |err| ConstantsError::Parse {
+            path: path.to_owned(),
+            err,
+        };
+        let file = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&std::fs::read_to_string(path)?)
+                .map_err(|err| parse_err(Box::new(err)))?,
+            Some("json") => serde_json::from_reader(BufReader::new(File::open(path)?))
+                .map_err(|err| parse_err(Box::new(err)))?,
+            _ => {
+                return Ok(Self::new(parse_constants_file(BufReader::new(
+                    File::open(path)?,
+                ))?))
+            }
+        };
+        Self::from_parsed(file)
+    }
+
+    fn from_parsed(file: ConstantsFile) -> Result<Self, ConstantsError> {
+        Ok(Self {
+            default: file.default.into_vec()?,
+            harnesses: file
+                .harness
+                .into_iter()
+                .map(|entry| {
+                    let query = entry
+                        .select
+                        .parse()
+                        .map_err(|err| ConstantsError::Selection(entry.select.clone(), err))?;
+                    Ok((query, entry.constants.into_vec()?))
+                })
+                .collect::<Result<_, ConstantsError>>()?,
+        })
+    }
+
+    /// Returns true if no harness gets any constant.
+    pub fn is_empty(&self) -> bool {
+        self.default.is_empty() && self.harnesses.iter().all(|(_, c)| c.is_empty())
+    }
+
+    /// Returns the constants for the given harness.
+    pub fn for_harness(&self, name: &str) -> &[String] {
+        self.harnesses
+            .iter()
+            .find(|(query, _)| query.matches(name))
+            .map(|(_, constants)| constants)
+            .unwrap_or(&self.default)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConstantsFile {
+    #[serde(default)]
+    default: ConstantList,
+    #[serde(default)]
+    harness: Vec<HarnessConstants>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HarnessConstants {
+    select: String,
+    constants: ConstantList,
+}
+
+/// Constants written either as a list or as a string in the plain format.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ConstantList {
+    List(Vec<String>),
+    Text(String),
+}
+
+impl Default for ConstantList {
+    fn default() -> Self {
+        Self::List(vec![])
+    }
+}
+
+impl ConstantList {
+    pub(crate) fn into_vec(self) -> Result<Vec<String>, io::Error> {
+        match self {
+            Self::List(constants) => Ok(constants),
+            Self::Text(text) => parse_constants_file(text.as_bytes()),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConstantsError {
+    #[error(transparent)]
+    IOError(#[from] io::Error),
+    #[error("Failed to parse constants file {}: {err}", path.display())]
+    Parse {
+        path: PathBuf,
+        err: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error("Failed to parse selection '{0}': {1}")]
+    Selection(String, QueryParseError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Constants {
+        Constants::from_parsed(toml::from_str(content).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn constants_per_harness() {
+        let constants = parse(
+            r#"
+            default = "1, 2 3"
+
+            [[harness]]
+            select = "*/*/*/point"
+            constants = ["4", "5"]
+
+            [[harness]]
+            select = "*/*/*/biguint|scalar"
+            constants = "0,1"
+
+            [[harness]]
+            select = "*/*/*/scalar"
+            constants = ["6"]
+            "#,
+        );
+        assert_eq!(constants.for_harness("ecc/add/ecc/point"), ["4", "5"]);
+        assert_eq!(
+            constants.for_harness("arithmetic/add/biguint/biguint"),
+            ["0", "1"]
+        );
+        assert_eq!(
+            constants.for_harness("arithmetic/add/native/scalar"),
+            ["0", "1"]
+        );
+        assert_eq!(
+            constants.for_harness("arithmetic/add/native/native"),
+            ["1", "2", "3"]
+        );
+    }
+
+    #[test]
+    fn constants_without_default() {
+        let constants = parse("");
+        assert!(constants.is_empty());
+        assert!(constants.for_harness("arithmetic/add/native/native").is_empty());
+    }
+}
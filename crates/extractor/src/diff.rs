@@ -0,0 +1,338 @@
+//! Comparison of the outputs of two extractions.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use anyhow::{Context as _, Result};
+use picus::PicusFile;
+use regex::{Captures, Regex};
+
+mod picus;
+
+pub(crate) const PICUS_OUTPUT: &str = "output.picus";
+const IR_DUMP: &str = "dump.ir";
+
+/// A difference found in a harness.
+pub enum Change {
+    PicusAdded,
+    PicusRemoved,
+    PrimeChanged,
+    ModuleAdded(String),
+    ModuleRemoved(String),
+    SignatureChanged {
+        module: String,
+        old: (usize, usize),
+        new: (usize, usize),
+    },
+    ConstraintsChanged {
+        module: String,
+        old: usize,
+        new: usize,
+    },
+    BodyChanged(String),
+    IrChanged,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::PicusAdded => write!(f, "Picus output added"),
+            Change::PicusRemoved => write!(f, "Picus output removed"),
+            Change::PrimeChanged => write!(f, "prime changed"),
+            Change::ModuleAdded(module) => write!(f, "module {module} added"),
+            Change::ModuleRemoved(module) => write!(f, "module {module} removed"),
+            Change::SignatureChanged { module, old, new } => write!(
+                f,
+                "module {module} signature changed from {} inputs and {} outputs to {} inputs and {} outputs",
+                old.0, old.1, new.0, new.1
+            ),
+            Change::ConstraintsChanged { module, old, new } => {
+                write!(f, "module {module} constraints changed from {old} to {new}")
+            }
+            Change::BodyChanged(module) => write!(f, "module {module} statements changed"),
+            Change::IrChanged => write!(f, "IR dump changed"),
+        }
+    }
+}
+
+/// Differences between two extractions.
+#[derive(Default)]
+pub struct Diff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<(String, Vec<Change>)>,
+    unchanged: usize,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.added {
+            writeln!(f, "+ {name}")?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- {name}")?;
+        }
+        for (name, changes) in &self.changed {
+            writeln!(f, "~ {name}")?;
+            for change in changes {
+                writeln!(f, "    {change}")?;
+            }
+        }
+        writeln!(
+            f,
+            "{} added, {} removed, {} changed, {} unchanged",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.unchanged
+        )
+    }
+}
+
+/// Finds the directories with outputs of a harness under `dir` and returns
+/// them by harness name.
+pub(crate) fn find_harnesses(base: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    fn visit(base: &Path, dir: &Path, found: &mut BTreeMap<String, PathBuf>) -> Result<()> {
+        if dir.join(PICUS_OUTPUT).is_file() || dir.join(IR_DUMP).is_file() {
+            let name = dir
+                .strip_prefix(base)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            found.insert(name, dir.to_owned());
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                visit(base, &path, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut found = BTreeMap::new();
+    visit(base, base, &mut found)
+        .with_context(|| format!("Failed to read outputs in {}", base.display()))?;
+    Ok(found)
+}
+
+fn read_picus(dir: &Path) -> Result<Option<PicusFile>> {
+    let path = dir.join(PICUS_OUTPUT);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path)?;
+    let file =
+        PicusFile::parse(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(file))
+}
+
+/// Reads the IR dump ignoring indentation and blank lines.
+fn read_ir(dir: &Path) -> Result<Option<Vec<String>>> {
+    let path = dir.join(IR_DUMP);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    Ok(Some(normalize_ir(&std::fs::read_to_string(&path)?)))
+}
+
+/// Group, call, call result and temporary ids in the IR dump.
+static IR_IDS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\((group|call|call-result|temp) (\d+)").unwrap());
+
+/// Renumbers the groups, the call results and the temporaries of the IR dump
+/// in order of appearance, so that two dumps that only differ in how these
+/// are numbered are equal.
+fn normalize_ir(text: &str) -> Vec<String> {
+    let mut ids = HashMap::<&str, HashMap<String, usize>>::new();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            IR_IDS
+                .replace_all(line, |caps: &Captures| {
+                    // Calls refer to groups by the id of the called group.
+                    let namespace = match &caps[1] {
+                        "call" | "group" => "group",
+                        "call-result" => "call-result",
+                        _ => "temp",
+                    };
+                    let ids = ids.entry(namespace).or_default();
+                    let next = ids.len();
+                    let id = *ids.entry(caps[2].to_owned()).or_insert(next);
+                    format!("({} {id}", &caps[1])
+                })
+                .into_owned()
+        })
+        .collect()
+}
+
+fn diff_picus(old: &PicusFile, new: &PicusFile, changes: &mut Vec<Change>) {
+    if old.prime() != new.prime() {
+        changes.push(Change::PrimeChanged);
+    }
+    for module in old.modules() {
+        if new.module(module.key()).is_none() {
+            changes.push(Change::ModuleRemoved(module.name().to_owned()));
+        }
+    }
+    for new_module in new.modules() {
+        let name = new_module.name().to_owned();
+        let Some(old_module) = old.module(new_module.key()) else {
+            changes.push(Change::ModuleAdded(name));
+            continue;
+        };
+        let signature = |m: &picus::PicusModule| (m.inputs(), m.outputs());
+        if signature(old_module) != signature(new_module) {
+            changes.push(Change::SignatureChanged {
+                module: name.clone(),
+                old: signature(old_module),
+                new: signature(new_module),
+            });
+        }
+        if old_module.constraints() != new_module.constraints() {
+            changes.push(Change::ConstraintsChanged {
+                module: name,
+                old: old_module.constraints(),
+                new: new_module.constraints(),
+            });
+        } else if old_module.body() != new_module.body() {
+            changes.push(Change::BodyChanged(name));
+        }
+    }
+}
+
+fn diff_harness(old: &Path, new: &Path) -> Result<Vec<Change>> {
+    let mut changes = vec![];
+    match (read_picus(old)?, read_picus(new)?) {
+        (Some(old), Some(new)) => diff_picus(&old, &new, &mut changes),
+        (None, Some(_)) => changes.push(Change::PicusAdded),
+        (Some(_), None) => changes.push(Change::PicusRemoved),
+        (None, None) => {}
+    }
+    if let (Some(old), Some(new)) = (read_ir(old)?, read_ir(new)?) {
+        if old != new {
+            changes.push(Change::IrChanged);
+        }
+    }
+    Ok(changes)
+}
+
+/// Compares the outputs of the harnesses extracted in `old` with the ones in
+/// `new`.
+///
+/// Harnesses are compared by their Picus output, after removing comments and
+/// normalizing the names of temporaries and modules, and by their IR dump,
+/// after renumbering its groups and temporaries, if both extractions have one.
+pub fn diff(old: &Path, new: &Path) -> Result<Diff> {
+    let old_harnesses = find_harnesses(old)?;
+    let new_harnesses = find_harnesses(new)?;
+    let mut diff = Diff {
+        removed: old_harnesses
+            .keys()
+            .filter(|name| !new_harnesses.contains_key(*name))
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+    for (name, new_dir) in new_harnesses {
+        let Some(old_dir) = old_harnesses.get(&name) else {
+            diff.added.push(name);
+            continue;
+        };
+        let changes = diff_harness(old_dir, &new_dir)?;
+        if changes.is_empty() {
+            diff.unchanged += 1;
+        } else {
+            diff.changed.push((name, changes));
+        }
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_PICUS: &str = r#"(prime-number 17)
+(begin-module main)
+(input in_0)
+(output out_0)
+(call [cout_0_0] assign3 [in_0])
+(call [cout_1_0] assign4 [cout_0_0])
+(assert (= out_0 cout_1_0))
+(end-module)
+(begin-module assign3)
+(input in_0)
+(output out_0)
+(assert (= out_0 (* in_0 in_0)))
+(end-module)
+(begin-module assign4)
+(input in_0)
+(output out_0)
+(assert (= out_0 (+ in_0 1)))
+(end-module)
+"#;
+
+    const OLD_IR: &str = r#"(group 0 "main" (inputs 1) (outputs 1)
+  (call 3 "assign"
+        (inputs (input 0))
+        (outputs (temp 5)))
+  (assert (= (output 0) (call-result 2 0))))
+(group 3 "assign" (inputs 1) (outputs 1)
+  (assert (= (output 0) (temp 5))))
+"#;
+
+    fn write_outputs(dir: &Path, picus: &str, ir: &str) {
+        let dir = dir.join("a/b/c/d");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(PICUS_OUTPUT), picus).unwrap();
+        std::fs::write(dir.join(IR_DUMP), ir).unwrap();
+    }
+
+    fn diff_outputs(name: &str, new_picus: &str, new_ir: &str) -> Diff {
+        let dir = std::env::temp_dir().join(format!("{name}-test-{}", std::process::id()));
+        write_outputs(&dir.join("old"), OLD_PICUS, OLD_IR);
+        write_outputs(&dir.join("new"), new_picus, new_ir);
+        diff(&dir.join("old"), &dir.join("new")).unwrap()
+    }
+
+    #[test]
+    fn renumbered_outputs_are_unchanged() {
+        let picus = OLD_PICUS.replace("assign3", "assign7").replace("assign4", "assign8");
+        let ir = OLD_IR
+            .replace("group 3", "group 9")
+            .replace("call 3", "call 9")
+            .replace("temp 5", "temp 12")
+            .replace("call-result 2", "call-result 4");
+        let diff = diff_outputs("diff-renumbered", &picus, &ir);
+        assert!(diff.is_empty(), "{diff}");
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn changes_are_found_after_renumbering() {
+        let picus = OLD_PICUS
+            .replace("assign3", "assign7")
+            .replace("assign4", "assign8")
+            .replace("(+ in_0 1)", "(+ in_0 2)");
+        let ir = OLD_IR.replace("group 3", "group 9").replace("call 3", "call 1");
+        let diff = diff_outputs("diff-changed", &picus, &ir);
+        let changes = diff.changed[0].1.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            ["module assign8 statements changed", "IR dump changed"]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Reader for the Picus files written by the extractor.
+//!
+//! Only the structure needed for comparing two files is kept. Comments, which
+//! include the timestamp in the header, are dropped and the variables that are
+//! not inputs or outputs are renamed in order of appearance so the names given
+//! to temporaries don't affect the comparison. Modules are identified by their
+//! name without the number the backend appends to make it unique, so
+//! renumbering the groups of a circuit doesn't affect it either.
+
+use std::{collections::HashMap, fmt};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PicusParseError {
+    #[error("Unbalanced '{0}' at line {1}")]
+    Unbalanced(char, usize),
+    #[error("Unexpected {0} at line {1}")]
+    Unexpected(String, usize),
+}
+
+/// S-expression with the kind of brackets it was written with.
+#[derive(Debug, Clone, PartialEq)]
+enum Sexpr {
+    Atom(String),
+    List(char, Vec<Sexpr>),
+}
+
+impl Sexpr {
+    fn head(&self) -> Option<&str> {
+        match self {
+            Sexpr::List('(', items) => match items.first() {
+                Some(Sexpr::Atom(head)) => Some(head),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn arg(&self, n: usize) -> Option<&str> {
+        match self {
+            Sexpr::List(_, items) => match items.get(n + 1) {
+                Some(Sexpr::Atom(arg)) => Some(arg),
+                _ => None,
+            },
+            Sexpr::Atom(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Sexpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sexpr::Atom(atom) => write!(f, "{atom}"),
+            Sexpr::List(open, items) => {
+                write!(f, "{open}")?;
+                for (n, item) in items.iter().enumerate() {
+                    if n > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "{}", closing(*open))
+            }
+        }
+    }
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// Parses the text into a list of s-expressions paired with their line.
+fn parse_sexprs(text: &str) -> Result<Vec<(usize, Sexpr)>, PicusParseError> {
+    let mut stack: Vec<(usize, char, Vec<Sexpr>)> = vec![];
+    let mut top = vec![];
+    for (line_no, line) in text.lines().enumerate() {
+        let line_no = line_no + 1;
+        let line = line.split(';').next().unwrap_or_default();
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            let sexpr = match c {
+                '(' | '[' | '{' => {
+                    stack.push((line_no, c, vec![]));
+                    continue;
+                }
+                ')' | ']' | '}' => match stack.pop() {
+                    Some((_, open, items)) if closing(open) == c => Sexpr::List(open, items),
+                    _ => return Err(PicusParseError::Unbalanced(c, line_no)),
+                },
+                c if c.is_whitespace() => continue,
+                _ => {
+                    let mut end = start + c.len_utf8();
+                    while let Some(&(i, c)) = chars.peek() {
+                        if c.is_whitespace() || "()[]{}".contains(c) {
+                            break;
+                        }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    Sexpr::Atom(line[start..end].to_owned())
+                }
+            };
+            match stack.last_mut() {
+                Some((_, _, items)) => items.push(sexpr),
+                None => top.push((line_no, sexpr)),
+            }
+        }
+    }
+    match stack.pop() {
+        Some((line_no, open, _)) => Err(PicusParseError::Unbalanced(open, line_no)),
+        None => Ok(top),
+    }
+}
+
+/// A module of a Picus file.
+#[derive(Debug, Default)]
+pub struct PicusModule {
+    name: String,
+    key: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    constraints: usize,
+    body: Vec<String>,
+}
+
+impl PicusModule {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Name of the module without its numeric suffix, followed by its
+    /// position among the modules with the same such name if it is not the
+    /// first one.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Number of `assert` statements in the module.
+    pub fn constraints(&self) -> usize {
+        self.constraints
+    }
+
+    /// Statements of the module with the names normalized.
+    pub fn body(&self) -> &[String] {
+        &self.body
+    }
+}
+
+/// Renames the variables of a module that are not inputs or outputs, and the
+/// modules it calls to their keys.
+struct Normalizer<'m> {
+    module: &'m PicusModule,
+    keys: &'m HashMap<String, String>,
+    names: HashMap<String, String>,
+}
+
+impl Normalizer<'_> {
+    fn is_var(&self, atom: &str) -> bool {
+        !(atom.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+            || self.module.inputs.iter().any(|i| i == atom)
+            || self.module.outputs.iter().any(|o| o == atom))
+    }
+
+    fn normalize(&mut self, sexpr: &Sexpr) -> Sexpr {
+        match sexpr {
+            Sexpr::Atom(atom) if self.is_var(atom) => {
+                let next = self.names.len();
+                Sexpr::Atom(
+                    self.names.entry(atom.clone()).or_insert_with(|| format!("v{next}")).clone(),
+                )
+            }
+            Sexpr::Atom(_) => sexpr.clone(),
+            Sexpr::List(open, items) => {
+                // The head of a list is an operator and the third element of a
+                // call is the called module.
+                let is_call = sexpr.head() == Some("call");
+                Sexpr::List(
+                    *open,
+                    items
+                        .iter()
+                        .enumerate()
+                        .map(|(n, item)| match item {
+                            Sexpr::Atom(callee) if *open == '(' && is_call && n == 2 => {
+                                Sexpr::Atom(self.keys.get(callee).unwrap_or(callee).clone())
+                            }
+                            Sexpr::Atom(_) if *open == '(' && n == 0 => item.clone(),
+                            _ => self.normalize(item),
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// Contents of a Picus file.
+#[derive(Debug, Default)]
+pub struct PicusFile {
+    prime: Option<String>,
+    modules: Vec<PicusModule>,
+}
+
+impl PicusFile {
+    pub fn parse(text: &str) -> Result<Self, PicusParseError> {
+        let mut file = Self::default();
+        let mut current: Option<(PicusModule, Vec<Sexpr>)> = None;
+        let mut bodies = vec![];
+        for (line_no, sexpr) in parse_sexprs(text)? {
+            let unexpected = || PicusParseError::Unexpected(sexpr.to_string(), line_no);
+            match (sexpr.head(), &mut current) {
+                (Some("prime-number"), None) => file.prime = sexpr.arg(0).map(ToOwned::to_owned),
+                (Some("begin-module"), None) => {
+                    let name = sexpr.arg(0).ok_or_else(unexpected)?.to_owned();
+                    current = Some((
+                        PicusModule {
+                            name,
+                            ..Default::default()
+                        },
+                        vec![],
+                    ));
+                }
+                (Some("input"), Some((module, _))) => {
+                    module.inputs.push(sexpr.arg(0).ok_or_else(unexpected)?.to_owned())
+                }
+                (Some("output"), Some((module, _))) => {
+                    module.outputs.push(sexpr.arg(0).ok_or_else(unexpected)?.to_owned())
+                }
+                (Some("end-module"), Some(_)) => {
+                    let (mut module, stmts) = current.take().unwrap();
+                    module.constraints =
+                        stmts.iter().filter(|stmt| stmt.head() == Some("assert")).count();
+                    file.modules.push(module);
+                    bodies.push(stmts);
+                }
+                (_, Some((_, stmts))) => stmts.push(sexpr),
+                (_, None) => return Err(unexpected()),
+            }
+        }
+        if let Some((module, _)) = current {
+            return Err(PicusParseError::Unexpected(
+                format!("end of file inside module {}", module.name),
+                text.lines().count(),
+            ));
+        }
+
+        // Calls may refer to modules defined later, so the bodies are
+        // normalized once every module has a key.
+        let mut seen = HashMap::<&str, usize>::new();
+        let keys = file
+            .modules
+            .iter()
+            .map(|module| {
+                let base = module.name.trim_end_matches(|c: char| c.is_ascii_digit());
+                let n = seen.entry(base).or_default();
+                let key = if *n == 0 {
+                    base.to_owned()
+                } else {
+                    format!("{base}#{n}")
+                };
+                *n += 1;
+                (module.name.clone(), key)
+            })
+            .collect::<HashMap<_, _>>();
+        for (module, stmts) in file.modules.iter_mut().zip(bodies) {
+            let mut normalizer = Normalizer {
+                module,
+                keys: &keys,
+                names: HashMap::new(),
+            };
+            let body = stmts.iter().map(|stmt| normalizer.normalize(stmt).to_string()).collect();
+            module.body = body;
+            module.key = keys[&module.name].clone();
+        }
+        Ok(file)
+    }
+
+    pub fn prime(&self) -> Option<&str> {
+        self.prime.as_deref()
+    }
+
+    pub fn modules(&self) -> &[PicusModule] {
+        &self.modules
+    }
+
+    /// Returns the module with the given key.
+    pub fn module(&self, key: &str) -> Option<&PicusModule> {
+        self.modules.iter().find(|m| m.key == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_temporaries() {
+        let old = PicusFile::parse(
+            r#"; Timestamp 2024-01-01
+(prime-number 17)
+(begin-module foo)
+; Number of temporaries: 1
+(input in_0)
+(output out_0)
+(assert (= t3 (* in_0 in_0)))
+(call [cout_1_0] bar [t3])
+(assert (= out_0 (+ cout_1_0 1)))
+(end-module)
+"#,
+        )
+        .unwrap();
+        let new = PicusFile::parse(
+            r#"; Timestamp 2025-01-01
+(prime-number 17)
+(begin-module foo)
+(input in_0)
+(output out_0)
+(assert (= t7
+  (* in_0 in_0)))
+(call [cout_2_0] bar [t7])
+(assert (= out_0 (+ cout_2_0 1)))
+(end-module)
+"#,
+        )
+        .unwrap();
+        let (old, new) = (old.module("foo").unwrap(), new.module("foo").unwrap());
+        assert_eq!(old.inputs(), 1);
+        assert_eq!(old.outputs(), 1);
+        assert_eq!(old.constraints(), 2);
+        assert_eq!(old.body(), new.body());
+        assert_eq!(old.body()[1], "(call [v1] bar [v0])");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(PicusFile::parse("(begin-module foo)\n(input x)").is_err());
+        assert!(PicusFile::parse("(assert (= x 1))").is_err());
+        assert!(PicusFile::parse("(begin-module foo)\n(assert (= x 1)\n(end-module)").is_err());
+    }
+}
@@ -1,9 +1,12 @@
 pub mod app;
-mod chips;
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod config;
-mod instructions;
+pub mod constants;
+pub mod diff;
 mod picus;
+pub mod project;
 mod query;
+mod smtlib;
 mod utils;
+pub mod verify;
@@ -1,6 +1,45 @@
+use std::time::Duration;
+
 use clap::Parser as _;
-use mdnt_extractor::{app::App, cli::Cli};
+use mdnt_extractor::{
+    app::App,
+    cli::{Cli, Command},
+    diff::diff,
+    project::ProjectConfig,
+    verify::Verifier,
+};
 
 fn main() -> anyhow::Result<()> {
-    App::new(Cli::parse())?.run()
+    let cli = Cli::parse();
+    if let Some(Command::Diff { old, new }) = &cli.command {
+        let diff = diff(old, new)?;
+        print!("{diff}");
+        // Same convention as diff(1).
+        std::process::exit(if diff.is_empty() { 0 } else { 1 });
+    }
+    if let Some(Command::Verify {
+        dir,
+        select,
+        solver,
+        time_limit,
+        jobs,
+        results,
+    }) = &cli.command
+    {
+        env_logger::init();
+        let verifier = Verifier::new(solver.parse()?, Duration::from_secs(*time_limit), *jobs);
+        let report = verifier.verify(dir, select.as_deref())?;
+        print!("{report}");
+        report.write(&results.clone().unwrap_or_else(|| dir.join("verify.json")))?;
+        std::process::exit(if report.has_failures() { 1 } else { 0 });
+    }
+    if let Some(path) = &cli.config {
+        let mut project = ProjectConfig::from_file(path)?;
+        project.set_list(cli.list);
+        project.set_verbose(cli.verbose);
+        project.set_force(cli.force);
+        project.override_invocation(cli.jobs, cli.log.clone(), cli.log_level);
+        return project.run(&cli.run);
+    }
+    App::new(cli)?.run()
 }
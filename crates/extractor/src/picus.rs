@@ -1,50 +1,100 @@
 use std::{
+    collections::BTreeSet,
     fs::{self, File},
     io::Write as _,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use haloumi::{driver::Driver, ir_gen::circuit::resolved::ResolvedIRCircuit};
 use haloumi_picus::PicusParamsBuilder;
 use header::Header;
-use prelude::Preludes;
+use mdnt_extractor_core::{cells::layout::IoLayout, lookups::TableModule, shuffles::ShuffleModule};
+use prelude::{required_preludes, Preludes};
+use serde::Serialize;
+use shuffle::PicusShuffleModule;
+use sidecar::PicusSidecar;
+use table::PicusTableModule;
 
 pub mod header;
 pub mod prelude;
+pub mod shuffle;
+pub mod sidecar;
+pub mod table;
 
+#[derive(Serialize)]
 pub struct PicusConfig {
     opt: bool,
-    prelude: Option<Preludes>,
+    preludes: Vec<Preludes>,
 }
 
 impl PicusConfig {
-    pub fn new(opt: bool, prelude: Option<Preludes>) -> Self {
-        Self { opt, prelude }
+    /// Creates the configuration. The given preludes are written to every
+    /// file in addition to the ones the file needs.
+    pub fn new(opt: bool, preludes: Vec<Preludes>) -> Self {
+        Self { opt, preludes }
     }
 }
 
+/// Files written by [`write_picus_output`].
+pub struct PicusFiles {
+    pub output: PathBuf,
+    pub sidecar: PathBuf,
+    /// Preludes written at the top of the output.
+    pub preludes: BTreeSet<Preludes>,
+}
+
+/// Writes the Picus file of the harness and the `picus.json` that describes
+/// how to run Picus on it.
+///
+/// The table modules called by the lookups and the modules called by the
+/// shuffles are defined after the preludes.
+#[allow(clippy::too_many_arguments)]
 pub fn write_picus_output(
     config: &PicusConfig,
+    header: &Header,
     name: &'static str,
     output_base: impl AsRef<Path>,
     ir: &ResolvedIRCircuit,
+    tables: &[TableModule],
+    shuffles: &[ShuffleModule],
+    layout: Option<IoLayout>,
     mut params: PicusParamsBuilder,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<PicusFiles> {
+    let shuffle_names = shuffles.iter().map(ShuffleModule::name).collect::<Vec<_>>();
+    let mut defined = tables.iter().map(TableModule::name).collect::<Vec<_>>();
+    defined.extend(shuffle_names.iter().map(String::as_str));
+    let mut preludes = required_preludes(ir, &defined)?;
+    preludes.extend(config.preludes.iter().copied());
     let output_dir = output_base.as_ref().join(name);
     fs::create_dir_all(&output_dir)?;
     params.short_names().no_expr_cutoff().entrypoint(name);
     if !config.opt {
         params.no_optimize();
     }
-    let output = Driver::default().picus(ir, params.build())?;
+    let params = params.build();
+    let entrypoint = params.entrypoint().to_owned();
+    let output = Driver::default().picus(ir, params)?;
 
     let output_path = output_dir.join("output.picus");
     let mut output_file = File::create(&output_path)?;
-    writeln!(output_file, "{}", Header)?;
-    if let Some(prelude) = config.prelude {
+    writeln!(output_file, "{header}")?;
+    for prelude in &preludes {
         writeln!(output_file, "{prelude}")?;
     }
+    for table in tables {
+        writeln!(output_file, "{}", PicusTableModule(table))?;
+    }
+    for shuffle in shuffles {
+        writeln!(output_file, "{}", PicusShuffleModule(shuffle))?;
+    }
     writeln!(output_file, "{}", output.display())?;
     log::info!("Saved picus output in {}", output_path.display());
-    Ok(())
+
+    let sidecar = PicusSidecar::new("output.picus".into(), &entrypoint, &preludes, layout)
+        .write(&output_dir)?;
+    Ok(PicusFiles {
+        output: output_path,
+        sidecar,
+        preludes,
+    })
 }
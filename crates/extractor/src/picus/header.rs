@@ -1,4 +1,5 @@
 use chrono::Utc;
+use mdnt_extractor_core::circuit::planner::FloorPlanning;
 use std::fmt;
 
 fn dep_header(
@@ -14,7 +15,17 @@ fn dep_header(
     writeln!(f)
 }
 
-pub struct Header;
+/// Comments at the top of the outputs with the versions and settings they were
+/// extracted with.
+pub struct Header {
+    floor_planner: FloorPlanning,
+}
+
+impl Header {
+    pub fn new(floor_planner: FloorPlanning) -> Self {
+        Self { floor_planner }
+    }
+}
 
 impl fmt::Display for Header {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -34,6 +45,7 @@ impl fmt::Display for Header {
             env!("MIDNIGHT_CIRCUITS_VERSION"),
             option_env!("MIDNIGHT_CIRCUITS_CHECKSUM"),
         )?;
+        writeln!(f, "; Floor planner {}", self.floor_planner)?;
         writeln!(f, "; Timestamp {}", Utc::now())
     }
 }
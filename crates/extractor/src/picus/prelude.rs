@@ -1,5 +1,25 @@
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+//! Preludes that declare the modules the lookup callbacks call.
+//!
+//! These modules have no body in the circuit, so a Picus file that calls them
+//! needs the prelude that declares them. The preludes a file needs are found by
+//! collecting the callees of the calls in the IR.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+};
+
+use haloumi::{
+    core::{cmp::CmpOp, felt::Felt, slot::Slot},
+    ir::{expr::IRAexpr, groups::IRGroup},
+    ir_gen::circuit::resolved::ResolvedIRCircuit,
+};
+use haloumi_lowering::{lowerable::LowerableStmt as _, ExprLowering, Lowering};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
 pub enum Preludes {
     Spread,
     Automaton,
@@ -8,6 +28,31 @@ pub enum Preludes {
 const SPREAD_PRELUDE: &str = include_str!("spread.picus.inc");
 const AUTOMATON_PRELUDE: &str = include_str!("automaton.picus.inc");
 
+impl Preludes {
+    const ALL: [Preludes; 2] = [Preludes::Spread, Preludes::Automaton];
+
+    /// Names of the modules declared by the prelude.
+    pub fn modules(&self) -> &'static [&'static str] {
+        match self {
+            Preludes::Spread => &["Spread", "Unspread"],
+            Preludes::Automaton => &["Automaton"],
+        }
+    }
+
+    /// Name of the prelude, as accepted by `--prelude`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preludes::Spread => "spread",
+            Preludes::Automaton => "automaton",
+        }
+    }
+
+    /// Returns the prelude that declares the module.
+    pub fn declaring(module: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|prelude| prelude.modules().contains(&module))
+    }
+}
+
 impl std::fmt::Display for Preludes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -20,3 +65,281 @@ impl std::fmt::Display for Preludes {
         }
     }
 }
+
+/// Preludes written either as a list or as a single name.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum PreludeList {
+    List(Vec<Preludes>),
+    Single(Preludes),
+}
+
+impl Default for PreludeList {
+    fn default() -> Self {
+        Self::List(vec![])
+    }
+}
+
+impl PreludeList {
+    pub(crate) fn as_slice(&self) -> &[Preludes] {
+        match self {
+            Self::List(preludes) => preludes,
+            Self::Single(prelude) => std::slice::from_ref(prelude),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreludeError {
+    #[error("No prelude declares the called module(s) {}", .0.join(", "))]
+    UnknownModules(Vec<String>),
+    #[error("Failed to collect the called modules: {0}")]
+    Lowering(#[from] haloumi_lowering::error::Error),
+}
+
+/// Returns the preludes that declare the modules called by the circuit.
+///
+/// Calls to the groups of the circuit and to the `defined` modules are ignored
+/// since those modules are part of the output. Fails if a called module is not
+/// declared by any prelude.
+pub fn required_preludes(
+    ir: &ResolvedIRCircuit,
+    defined: &[&str],
+) -> Result<BTreeSet<Preludes>, PreludeError> {
+    preludes_called_by(ir.groups(), defined)
+}
+
+fn preludes_called_by(
+    groups: &[IRGroup<IRAexpr>],
+    defined: &[&str],
+) -> Result<BTreeSet<Preludes>, PreludeError> {
+    let collector = CalleeCollector::default();
+    for group in groups {
+        for stmt in group.statements() {
+            stmt.clone().lower(&collector)?;
+        }
+    }
+    let callees = collector.callees.into_inner();
+    let mut preludes = BTreeSet::new();
+    let mut unknown = vec![];
+    for callee in callees {
+        if groups.iter().any(|group| group.name() == callee) || defined.contains(&callee.as_str()) {
+            continue;
+        }
+        match Preludes::declaring(&callee) {
+            Some(prelude) => {
+                preludes.insert(prelude);
+            }
+            None => unknown.push(callee),
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(PreludeError::UnknownModules(unknown));
+    }
+    Ok(preludes)
+}
+
+/// Lowering that only records the names of the called modules.
+#[derive(Default)]
+struct CalleeCollector {
+    callees: RefCell<BTreeSet<String>>,
+    constraints: Cell<usize>,
+}
+
+type LResult<T> = haloumi_lowering::Result<T>;
+
+impl Lowering for CalleeCollector {
+    fn generate_constraint(&self, _: CmpOp, _: &(), _: &()) -> LResult<()> {
+        self.constraints.set(self.constraints.get() + 1);
+        Ok(())
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.constraints.get()
+    }
+
+    fn generate_comment(&self, _: String) -> LResult<()> {
+        Ok(())
+    }
+
+    fn generate_assume_deterministic(&self, _: Slot) -> LResult<()> {
+        Ok(())
+    }
+
+    fn generate_call(&self, name: &str, _: &[()], _: &[Slot]) -> LResult<()> {
+        self.callees.borrow_mut().insert(name.to_owned());
+        Ok(())
+    }
+
+    fn generate_assert(&self, _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn generate_post_condition(&self, _: &()) -> LResult<()> {
+        Ok(())
+    }
+}
+
+impl ExprLowering for CalleeCollector {
+    type CellOutput = ();
+
+    fn lower_sum(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_product(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_neg(&self, _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_constant(&self, _: Felt) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_eq(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_lt(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_le(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_gt(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_ge(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_ne(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_and(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_or(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_not(&self, _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_true(&self) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_false(&self) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_det(&self, _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_implies(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_iff(&self, _: &(), _: &()) -> LResult<()> {
+        Ok(())
+    }
+
+    fn lower_function_input(&self, i: usize) -> Slot {
+        Slot::Arg(i.into())
+    }
+
+    fn lower_function_output(&self, o: usize) -> Slot {
+        Slot::Output(o.into())
+    }
+
+    fn lower_funcio<IO>(&self, _: IO) -> LResult<()>
+    where
+        IO: Into<Slot>,
+    {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use haloumi::ir::stmt::IRStmt;
+
+    use super::*;
+
+    fn group(name: &str, id: usize, callees: &[&str]) -> IRGroup<IRAexpr> {
+        let arg = IRAexpr::slot(Slot::Arg(0.into()));
+        IRGroup::new(name.to_owned(), id).with_input_count(1).with_lookups(
+            callees
+                .iter()
+                .enumerate()
+                .map(|(n, callee)| IRStmt::call(callee, [arg.clone()], [Slot::Temp(n)])),
+        )
+    }
+
+    fn preludes(groups: &[IRGroup<IRAexpr>]) -> Vec<Preludes> {
+        preludes_called_by(groups, &[]).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn declaring_prelude() {
+        assert_eq!(Preludes::declaring("Spread"), Some(Preludes::Spread));
+        assert_eq!(Preludes::declaring("Unspread"), Some(Preludes::Spread));
+        assert_eq!(Preludes::declaring("Automaton"), Some(Preludes::Automaton));
+        assert_eq!(Preludes::declaring("Foo"), None);
+        for prelude in Preludes::ALL {
+            let text = prelude.to_string();
+            for module in prelude.modules() {
+                assert!(text.contains(&format!("(begin-module {module})")));
+            }
+        }
+    }
+
+    #[test]
+    fn calls_to_groups_need_no_prelude() {
+        let groups = [
+            group("main", 0, &["range_check", "Spread"]),
+            group("range_check", 1, &[]),
+        ];
+        assert_eq!(preludes(&groups), [Preludes::Spread]);
+        assert_eq!(preludes(&groups[1..]), []);
+    }
+
+    #[test]
+    fn collects_every_required_prelude() {
+        let groups = [
+            group("main", 0, &["Unspread", "Automaton"]),
+            group("parse", 1, &["Automaton", "Spread"]),
+        ];
+        assert_eq!(preludes(&groups), [Preludes::Spread, Preludes::Automaton]);
+    }
+
+    #[test]
+    fn undeclared_modules_are_reported() {
+        let groups = [
+            group("main", 0, &["Spread", "Table", "sub"]),
+            group("sub", 1, &["Xor"]),
+        ];
+        let Err(PreludeError::UnknownModules(modules)) = preludes_called_by(&groups, &[]) else {
+            panic!("Expected the modules to be unknown");
+        };
+        assert_eq!(modules, ["Table", "Xor"]);
+    }
+
+    #[test]
+    fn calls_to_table_modules_need_no_prelude() {
+        let groups = [group("main", 0, &["Spread", "Table"])];
+        let preludes = preludes_called_by(&groups, &["Table"]).unwrap();
+        assert_eq!(preludes.into_iter().collect::<Vec<_>>(), [Preludes::Spread]);
+    }
+}
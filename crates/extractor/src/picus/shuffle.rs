@@ -0,0 +1,87 @@
+//! Modules called by the shuffles.
+//!
+//! A shuffle module asserts that the power sums of the rows of both halves of
+//! its inputs are equal, `x₁ᵏ + … + xₙᵏ = y₁ᵏ + … + yₙᵏ` for every `k` up to
+//! the number of rows `n`. Over a prime field larger than `n` that holds
+//! exactly when the rows are the same multiset. Rows of several values are
+//! combined into `v₀ + 2³²·v₁ + 2⁶⁴·v₂ + …` first, which keeps rows of 32-bit
+//! values apart; otherwise the module is weaker than the shuffle.
+
+use std::fmt;
+
+use mdnt_extractor_core::shuffles::ShuffleModule;
+use num_bigint::BigUint;
+
+use super::table::nest;
+
+/// Writes the definition of a shuffle module in Picus.
+pub struct PicusShuffleModule<'m>(pub &'m ShuffleModule);
+
+impl fmt::Display for PicusShuffleModule<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let module = self.0;
+        let (name, width, rows) = (module.name(), module.width(), module.rows());
+        writeln!(f, "; Shuffle module {name}")?;
+        writeln!(f, "(begin-module {name})")?;
+        for n in 0..module.inputs() {
+            writeln!(f, "(input i{n})")?;
+        }
+        let row = |row: usize| {
+            let values = (0..width).map(|n| match n {
+                0 => format!("i{}", row * width),
+                n => format!(
+                    "(* {} i{})",
+                    BigUint::from(1u8) << (32 * n),
+                    row * width + n
+                ),
+            });
+            nest("+", "0", values.collect())
+        };
+        let (lhs, rhs): (Vec<_>, Vec<_>) = (
+            (0..rows).map(row).collect(),
+            (rows..2 * rows).map(row).collect(),
+        );
+        let power_sum = |rows: &[String], k: usize| {
+            let powers = rows.iter().map(|row| nest("*", "1", vec![row.clone(); k]));
+            nest("+", "0", powers.collect())
+        };
+        for k in 1..=rows {
+            writeln!(
+                f,
+                "(assert (= {} {}))",
+                power_sum(&lhs, k),
+                power_sum(&rhs, k)
+            )?;
+        }
+        writeln!(f, "(end-module)")?;
+        writeln!(f, "; End of shuffle module {name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_asserts_the_power_sums_are_equal() {
+        assert_eq!(
+            PicusShuffleModule(&ShuffleModule::new(1, 2)).to_string(),
+            "; Shuffle module MultisetEq_1x2\n\
+             (begin-module MultisetEq_1x2)\n\
+             (input i0)\n\
+             (input i1)\n\
+             (input i2)\n\
+             (input i3)\n\
+             (assert (= (+ i0 i1) (+ i2 i3)))\n\
+             (assert (= (+ (* i0 i0) (* i1 i1)) (+ (* i2 i2) (* i3 i3))))\n\
+             (end-module)\n\
+             ; End of shuffle module MultisetEq_1x2\n"
+        );
+    }
+
+    #[test]
+    fn rows_of_several_values_are_combined() {
+        let module = PicusShuffleModule(&ShuffleModule::new(2, 1)).to_string();
+        assert!(module.contains("(assert (= (+ i0 (* 4294967296 i1)) (+ i2 (* 4294967296 i3))))\n"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Description of how to run Picus on an output, written next to it as
+//! `picus.json`.
+//!
+//! Picus needs to know which modules declared by the preludes have to be
+//! assumed deterministic. Job launchers read this file instead of inspecting
+//! the preludes at the top of the Picus file.
+
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use mdnt_extractor_core::cells::layout::IoLayout;
+use serde::{Deserialize, Serialize};
+
+use super::prelude::Preludes;
+
+const SIDECAR_FILE: &str = "picus.json";
+
+/// Contents of `picus.json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PicusSidecar {
+    source: PathBuf,
+    entrypoint: String,
+    assume_deterministic: Vec<String>,
+    args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layout: Option<IoLayout>,
+}
+
+impl PicusSidecar {
+    /// Creates the description of the Picus file `source` whose top-level
+    /// module is `entrypoint` and that includes the given preludes. The layout
+    /// of the IO of the harness is kept for decoding counterexamples.
+    pub fn new(
+        source: PathBuf,
+        entrypoint: &str,
+        preludes: &BTreeSet<Preludes>,
+        layout: Option<IoLayout>,
+    ) -> Self {
+        let assume_deterministic: Vec<_> = preludes
+            .iter()
+            .flat_map(|prelude| prelude.modules())
+            .map(|module| module.to_string())
+            .collect();
+        let args = if assume_deterministic.is_empty() {
+            vec!["--empty-assume-deterministic".to_owned()]
+        } else {
+            vec![
+                "--assume-deterministic".to_owned(),
+                assume_deterministic.join(","),
+            ]
+        };
+        Self {
+            source,
+            entrypoint: entrypoint.to_owned(),
+            assume_deterministic,
+            args,
+            layout,
+        }
+    }
+
+    /// Name of the top-level module.
+    pub fn entrypoint(&self) -> &str {
+        &self.entrypoint
+    }
+
+    /// Arguments to pass to Picus.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Layout of the inputs and outputs of the harness, if it was recorded.
+    pub fn layout(&self) -> Option<&IoLayout> {
+        self.layout.as_ref()
+    }
+
+    /// Reads the sidecar of the output in `output_dir`, if there is one.
+    pub fn read(output_dir: &Path) -> anyhow::Result<Option<Self>> {
+        let path = output_dir.join(SIDECAR_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_reader(BufReader::new(File::open(
+            path,
+        )?))?))
+    }
+
+    /// Writes the sidecar into `output_dir` and returns its path.
+    pub fn write(&self, output_dir: &Path) -> anyhow::Result<PathBuf> {
+        let output_path = output_dir.join(SIDECAR_FILE);
+        let output_file = BufWriter::new(File::create(&output_path)?);
+        serde_json::to_writer_pretty(output_file, self)?;
+        Ok(output_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picus_args() {
+        let sidecar = PicusSidecar::new("output.picus".into(), "a/b/c/d", &BTreeSet::new(), None);
+        assert!(sidecar.assume_deterministic.is_empty());
+        assert_eq!(sidecar.args, ["--empty-assume-deterministic"]);
+
+        let preludes = BTreeSet::from([Preludes::Automaton, Preludes::Spread]);
+        let sidecar = PicusSidecar::new("output.picus".into(), "a/b/c/d", &preludes, None);
+        assert_eq!(
+            sidecar.assume_deterministic,
+            ["Spread", "Unspread", "Automaton"]
+        );
+        assert_eq!(
+            sidecar.args,
+            ["--assume-deterministic", "Spread,Unspread,Automaton"]
+        );
+    }
+}
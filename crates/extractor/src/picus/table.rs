@@ -0,0 +1,74 @@
+//! Modules generated from the tables of the lookups.
+//!
+//! Unlike the modules declared by the preludes, these modules have a body: the
+//! disjunction of the rows of the table, each one the conjunction of the
+//! equalities between the inputs and the values of the row.
+
+use std::fmt;
+
+use mdnt_extractor_core::lookups::TableModule;
+use num_bigint::BigUint;
+
+/// Writes the definition of a table module in Picus.
+pub struct PicusTableModule<'m>(pub &'m TableModule);
+
+/// Joins the expressions with a binary operator, nesting to the right. An
+/// empty list is replaced by `empty`.
+pub(super) fn nest(op: &str, empty: &str, exprs: Vec<String>) -> String {
+    exprs
+        .into_iter()
+        .rev()
+        .reduce(|rhs, lhs| format!("({op} {lhs} {rhs})"))
+        .unwrap_or_else(|| empty.to_owned())
+}
+
+fn row(row: &[BigUint]) -> String {
+    let eqs = row.iter().enumerate().map(|(n, value)| format!("(= i{n} {value})"));
+    nest("&&", "(= 0 0)", eqs.collect())
+}
+
+impl fmt::Display for PicusTableModule<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let module = self.0;
+        writeln!(f, "; Table module {}", module.name())?;
+        writeln!(f, "(begin-module {})", module.name())?;
+        for n in 0..module.inputs() {
+            writeln!(f, "(input i{n})")?;
+        }
+        let rows = module.rows().iter().map(Vec::as_slice).map(row).collect();
+        writeln!(f, "(assert {})", nest("||", "(= 0 1)", rows))?;
+        writeln!(f, "(end-module)")?;
+        writeln!(f, "; End of table module {}", module.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(inputs: usize, rows: &[&[u64]]) -> String {
+        let rows = rows.iter().map(|row| row.iter().copied().map(BigUint::from).collect());
+        PicusTableModule(&TableModule::new("Table", inputs, rows.collect())).to_string()
+    }
+
+    #[test]
+    fn module_asserts_the_disjunction_of_the_rows() {
+        assert_eq!(
+            module(2, &[&[0, 1], &[2, 3], &[4, 5]]),
+            "; Table module Table\n\
+             (begin-module Table)\n\
+             (input i0)\n\
+             (input i1)\n\
+             (assert (|| (&& (= i0 0) (= i1 1)) (|| (&& (= i0 2) (= i1 3)) (&& (= i0 4) (= i1 5)))))\n\
+             (end-module)\n\
+             ; End of table module Table\n"
+        );
+    }
+
+    #[test]
+    fn single_rows_and_columns_are_not_nested() {
+        assert!(module(1, &[&[7]]).contains("(assert (= i0 7))\n"));
+        assert!(module(1, &[&[7], &[8]]).contains("(assert (|| (= i0 7) (= i0 8)))\n"));
+        assert!(module(2, &[]).contains("(assert (= 0 1))\n"));
+    }
+}
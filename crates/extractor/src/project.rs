@@ -0,0 +1,551 @@
+//! Project files describing several extraction runs.
+//!
+//! A project file is a TOML file with settings shared by all the runs and a
+//! list of named runs:
+//!
+//! ```toml
+//! output = "picus_files"
+//! jobs = 4
+//!
+//! [[run]]
+//! name = "native"
+//! select = "*/*/*/native"
+//! constants = "1,2,3"
+//!
+//! [[run]]
+//! name = "base64"
+//! select = "base64/*/*/byte"
+//! constants-file = "constants.toml"
+//! no-opt = true
+//! ```
+//!
+//! Each run is an [`AppConfig`] and is extracted by its own [`App`]. Relative
+//! paths are resolved from the directory of the project file.
+
+use std::{
+    collections::HashSet,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+use mdnt_extractor_core::{
+    circuit::planner::FloorPlanning,
+    taxonomy::{Chip, Instructions, Type},
+};
+use serde::Deserialize;
+
+use crate::{
+    app::App,
+    config::{Action, AppConfig, FailMode, HarnessConfig, LoggingConfig, OutputFormat},
+    constants::{ConstantList, Constants, ConstantsError},
+    picus::{
+        prelude::{PreludeList, Preludes},
+        PicusConfig,
+    },
+};
+
+fn default_output() -> PathBuf {
+    PathBuf::from("picus_files")
+}
+
+fn default_log_level() -> log::Level {
+    log::Level::Info
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(default = "default_output")]
+    output: PathBuf,
+    jobs: Option<NonZeroUsize>,
+    #[serde(default)]
+    fail_fast: bool,
+    log: Option<PathBuf>,
+    #[serde(default = "default_log_level")]
+    log_level: log::Level,
+    #[serde(rename = "run")]
+    runs: Vec<RunSettings>,
+    #[serde(skip)]
+    list: bool,
+    #[serde(skip)]
+    verbose: bool,
+    #[serde(skip)]
+    force: bool,
+}
+
+/// Settings of a single run as written in the project file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct RunSettings {
+    name: String,
+    select: Option<String>,
+    constants: Option<ConstantList>,
+    constants_file: Option<PathBuf>,
+    #[serde(default)]
+    prelude: PreludeList,
+    #[serde(default)]
+    formats: Vec<OutputFormat>,
+    output: Option<PathBuf>,
+    #[serde(default)]
+    no_opt: bool,
+    #[serde(default)]
+    picus_no_opt: bool,
+    #[serde(default)]
+    disable_decomposition_rewrite: bool,
+    #[serde(default)]
+    debug_comments: bool,
+    #[serde(default)]
+    allow_injected_ir_for_outputs: bool,
+    #[serde(default)]
+    check_witnesses: usize,
+    #[serde(default)]
+    floor_planner: FloorPlanning,
+    #[serde(default)]
+    strict_lookups: bool,
+    #[serde(default)]
+    dump_ir: bool,
+}
+
+impl ProjectConfig {
+    /// Loads the project from a TOML file.
+    pub fn from_file(path: &Path) -> Result<Self, ProjectError> {
+        let mut project: Self = toml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|err| ProjectError::Parse(path.to_owned(), err))?;
+
+        let mut names = HashSet::new();
+        if let Some(run) = project.runs.iter().find(|run| !names.insert(&run.name)) {
+            return Err(ProjectError::DuplicatedRun(run.name.clone()));
+        }
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        project.output = base.join(&project.output);
+        project.log = project.log.map(|log| base.join(log));
+        for run in &mut project.runs {
+            run.output = run.output.as_ref().map(|output| base.join(output));
+            run.constants_file = run.constants_file.as_ref().map(|file| base.join(file));
+        }
+        Ok(project)
+    }
+
+    /// Makes the runs print the selected harnesses instead of extracting them.
+    pub fn set_list(&mut self, list: bool) {
+        self.list = list;
+    }
+
+    /// Makes the list of harnesses include their metadata.
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// Makes the runs extract every harness even if its outputs are up to date.
+    pub fn set_force(&mut self, force: bool) {
+        self.force = force;
+    }
+
+    /// Replaces the settings of the project that were also given on the
+    /// command line. Unlike the ones in the file, the log path is not resolved
+    /// from the directory of the project.
+    pub fn override_invocation(
+        &mut self,
+        jobs: Option<NonZeroUsize>,
+        log: Option<PathBuf>,
+        log_level: Option<log::Level>,
+    ) {
+        self.jobs = jobs.or(self.jobs);
+        self.log = log.or(self.log.take());
+        self.log_level = log_level.unwrap_or(self.log_level);
+    }
+
+    /// Returns the configurations of the runs with the given names, or of all
+    /// the runs if no name is given, in the order of the project file.
+    pub fn into_runs(self, names: &[String]) -> Result<Vec<RunConfig>, ProjectError> {
+        if let Some(name) =
+            names.iter().find(|name| !self.runs.iter().any(|run| &run.name == *name))
+        {
+            return Err(ProjectError::UnknownRun(name.clone()));
+        }
+        Ok(self
+            .runs
+            .into_iter()
+            .filter(|run| names.is_empty() || names.contains(&run.name))
+            .map(|settings| RunConfig {
+                output: settings.output.clone().unwrap_or_else(|| self.output.join(&settings.name)),
+                logging: self.log.as_deref().map(|path| LoggingConfig::new(path, self.log_level)),
+                jobs: self.jobs.unwrap_or(NonZeroUsize::MIN),
+                fail_fast: self.fail_fast,
+                list: self.list,
+                verbose: self.verbose,
+                force: self.force,
+                constants: Constants::default(),
+                settings,
+            })
+            .collect())
+    }
+
+    /// Extracts the runs with the given names, or all the runs if no name is
+    /// given.
+    ///
+    /// A failed run doesn't prevent the next ones from running unless
+    /// `fail-fast` is set.
+    pub fn run(self, names: &[String]) -> anyhow::Result<()> {
+        let fail_fast = self.fail_fast;
+        let mut failed = vec![];
+        for run in self.into_runs(names)? {
+            let name = run.name().to_owned();
+            if let Err(err) = App::new(run).and_then(|mut app| app.run()) {
+                if fail_fast {
+                    return Err(err.context(format!("Run {name} failed")));
+                }
+                log::error!("Run {name} failed: {err:#}");
+                failed.push(name);
+            }
+        }
+        if !failed.is_empty() {
+            bail!("{} runs failed: {}", failed.len(), failed.join(", "));
+        }
+        Ok(())
+    }
+}
+
+/// Configuration of a single run of a project.
+#[derive(Debug)]
+pub struct RunConfig {
+    settings: RunSettings,
+    output: PathBuf,
+    logging: Option<LoggingConfig>,
+    jobs: NonZeroUsize,
+    fail_fast: bool,
+    list: bool,
+    verbose: bool,
+    force: bool,
+    constants: Constants,
+}
+
+impl RunConfig {
+    pub fn name(&self) -> &str {
+        &self.settings.name
+    }
+}
+
+impl AppConfig for RunConfig {
+    type Error = ProjectError;
+
+    fn logging(&self) -> Option<LoggingConfig> {
+        self.logging.clone()
+    }
+
+    fn setup(&mut self) -> Result<(), Self::Error> {
+        log::info!("Starting run {}", self.name());
+        self.constants = match (
+            self.settings.constants.take(),
+            &self.settings.constants_file,
+        ) {
+            (Some(_), Some(_)) => {
+                return Err(ProjectError::ConstantsConfigErr(self.settings.name.clone()))
+            }
+            (Some(list), None) => Constants::new(list.into_vec().map_err(ConstantsError::from)?),
+            (None, Some(path)) => Constants::from_file(path)?,
+            (None, None) => Constants::default(),
+        };
+        if self.constants.is_empty() {
+            log::warn!("No constants provided! Some circuits may fail to extract due to this.");
+        }
+        Ok(())
+    }
+
+    fn instructions(&self) -> &[Instructions] {
+        &[]
+    }
+
+    fn chip(&self) -> Option<Chip> {
+        None
+    }
+
+    fn ignore_chips(&self) -> &[Chip] {
+        &[]
+    }
+
+    fn r#type(&self) -> Option<Type> {
+        None
+    }
+
+    fn method_whitelist(&self) -> &[String] {
+        &[]
+    }
+
+    fn method_blacklist(&self) -> &[String] {
+        &[]
+    }
+
+    fn select(&self) -> Option<&str> {
+        self.settings.select.as_deref()
+    }
+
+    fn constants(&self) -> &Constants {
+        &self.constants
+    }
+
+    fn output(&self) -> Option<&Path> {
+        Some(&self.output)
+    }
+
+    fn preludes(&self) -> &[Preludes] {
+        self.settings.prelude.as_slice()
+    }
+
+    fn picus_config(&self) -> PicusConfig {
+        PicusConfig::new(
+            !(self.settings.picus_no_opt || self.settings.no_opt),
+            self.settings.prelude.as_slice().to_vec(),
+        )
+    }
+
+    fn harness_config(&self) -> HarnessConfig {
+        HarnessConfig::new(
+            &self.constants,
+            self.settings.debug_comments,
+            !self.settings.disable_decomposition_rewrite,
+            self.settings.allow_injected_ir_for_outputs,
+            self.settings.check_witnesses,
+            self.settings.floor_planner,
+            self.settings.strict_lookups,
+        )
+    }
+
+    fn dump_ir(&self) -> bool {
+        self.settings.dump_ir
+    }
+
+    fn fail_mode(&self) -> FailMode {
+        if self.fail_fast {
+            FailMode::Fast
+        } else {
+            FailMode::Continue
+        }
+    }
+
+    fn jobs(&self) -> NonZeroUsize {
+        self.jobs
+    }
+
+    fn force(&self) -> bool {
+        self.force
+    }
+
+    fn action(&self) -> Action {
+        if self.list {
+            Action::List {
+                verbose: self.verbose,
+            }
+        } else {
+            Action::Extract
+        }
+    }
+
+    fn formats(&self) -> &[OutputFormat] {
+        if self.settings.formats.is_empty() {
+            return &[OutputFormat::Picus];
+        }
+        &self.settings.formats
+    }
+
+    fn optimize_ir(&self) -> bool {
+        !self.settings.no_opt
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectError {
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+    #[error("Failed to parse project file {}: {}", .0.display(), .1)]
+    Parse(PathBuf, toml::de::Error),
+    #[error("Run {0} is defined more than once")]
+    DuplicatedRun(String),
+    #[error("No run named {0} in the project file")]
+    UnknownRun(String),
+    #[error("Cannot set constants and constants-file at the same time in run {0}")]
+    ConstantsConfigErr(String),
+    #[error(transparent)]
+    ConstantsError(#[from] ConstantsError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the project file in a fresh directory and returns its path.
+    fn write_project(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{name}-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("project.toml");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn remove_project(path: &Path) {
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn parses_project() {
+        let path = write_project(
+            "parses-project",
+            r#"
+            jobs = 4
+            fail-fast = true
+
+            [[run]]
+            name = "native"
+            select = "*/*/*/native"
+            constants = "1,2,3"
+            check-witnesses = 8
+
+            [[run]]
+            name = "base64"
+            formats = ["picus", "smtlib"]
+            no-opt = true
+            "#,
+        );
+        let runs = ProjectConfig::from_file(&path).unwrap().into_runs(&[]).unwrap();
+        assert_eq!(
+            runs.iter().map(RunConfig::name).collect::<Vec<_>>(),
+            ["native", "base64"]
+        );
+
+        let [native, base64] = &runs[..] else {
+            unreachable!()
+        };
+        assert_eq!(native.select(), Some("*/*/*/native"));
+        assert_eq!(native.jobs().get(), 4);
+        assert!(matches!(native.fail_mode(), FailMode::Fast));
+        assert_eq!(native.formats(), [OutputFormat::Picus]);
+        assert!(native.optimize_ir());
+        assert_eq!(base64.select(), None);
+        assert_eq!(
+            base64.formats(),
+            [OutputFormat::Picus, OutputFormat::Smtlib]
+        );
+        assert!(!base64.optimize_ir());
+        remove_project(&path);
+    }
+
+    #[test]
+    fn resolves_paths_from_project_directory() {
+        let path = write_project(
+            "resolves-paths",
+            r#"
+            output = "out"
+            log = "logs/extractor.log"
+
+            [[run]]
+            name = "native"
+            constants-file = "constants.toml"
+
+            [[run]]
+            name = "base64"
+            output = "base64-out"
+            "#,
+        );
+        let dir = path.parent().unwrap();
+        let runs = ProjectConfig::from_file(&path).unwrap().into_runs(&[]).unwrap();
+        assert_eq!(runs[0].output(), Some(dir.join("out/native").as_path()));
+        assert_eq!(
+            runs[0].settings.constants_file,
+            Some(dir.join("constants.toml"))
+        );
+        assert_eq!(runs[1].output(), Some(dir.join("base64-out").as_path()));
+        assert!(runs.iter().all(|run| run.logging().is_some()));
+        remove_project(&path);
+    }
+
+    #[test]
+    fn command_line_overrides_invocation_settings() {
+        let path = write_project(
+            "overrides",
+            r#"
+            jobs = 4
+            log = "project.log"
+            log-level = "warn"
+
+            [[run]]
+            name = "native"
+            "#,
+        );
+        let mut project = ProjectConfig::from_file(&path).unwrap();
+        project.override_invocation(NonZeroUsize::new(8), None, Some(log::Level::Debug));
+        let runs = project.into_runs(&[]).unwrap();
+        assert_eq!(runs[0].jobs().get(), 8);
+        let logging = runs[0].logging().unwrap();
+        assert_eq!(logging.path(), path.parent().unwrap().join("project.log"));
+        assert_eq!(logging.level(), log::Level::Debug);
+
+        let mut project = ProjectConfig::from_file(&path).unwrap();
+        project.override_invocation(None, Some(PathBuf::from("cli.log")), None);
+        let runs = project.into_runs(&[]).unwrap();
+        assert_eq!(runs[0].jobs().get(), 4);
+        let logging = runs[0].logging().unwrap();
+        assert_eq!(logging.path(), Path::new("cli.log"));
+        assert_eq!(logging.level(), log::Level::Warn);
+        remove_project(&path);
+    }
+
+    #[test]
+    fn rejects_duplicated_runs() {
+        let path = write_project(
+            "duplicated-runs",
+            r#"
+            [[run]]
+            name = "native"
+
+            [[run]]
+            name = "native"
+            no-opt = true
+            "#,
+        );
+        let err = ProjectConfig::from_file(&path).unwrap_err();
+        assert!(matches!(err, ProjectError::DuplicatedRun(name) if name == "native"));
+        remove_project(&path);
+    }
+
+    #[test]
+    fn selects_runs_by_name() {
+        let path = write_project(
+            "selects-runs",
+            r#"
+            [[run]]
+            name = "native"
+
+            [[run]]
+            name = "base64"
+            "#,
+        );
+        let project = ProjectConfig::from_file(&path).unwrap();
+        let runs = project.into_runs(&["base64".to_owned()]).unwrap();
+        assert_eq!(
+            runs.iter().map(RunConfig::name).collect::<Vec<_>>(),
+            ["base64"]
+        );
+
+        let project = ProjectConfig::from_file(&path).unwrap();
+        let err = project.into_runs(&["sha256".to_owned()]).unwrap_err();
+        assert!(matches!(err, ProjectError::UnknownRun(name) if name == "sha256"));
+        remove_project(&path);
+    }
+
+    #[test]
+    fn rejects_unknown_settings() {
+        let path = write_project(
+            "unknown-settings",
+            r#"
+            [[run]]
+            name = "native"
+            no-optimize = true
+            "#,
+        );
+        let err = ProjectConfig::from_file(&path).unwrap_err();
+        assert!(matches!(err, ProjectError::Parse(..)), "{err}");
+        remove_project(&path);
+    }
+}
@@ -2,20 +2,49 @@
 
 use std::fmt;
 
-use crate::{
-    chips::{Chip, Type},
-    instructions::Instructions,
+use mdnt_extractor_core::{
+    fields::DEFAULT_FIELD,
+    taxonomy::{Chip, Instructions, Type},
 };
+use regex::Regex;
 
-/// The parts that make up a harness name.
+mod parse;
+
+pub use parse::QueryParseError;
+
+/// The parts that make up a harness name. Harnesses over a field other than
+/// the default one have it at the end of the name, as in `a/b/c/d@field`.
+///
+/// Names of registered harnesses are checked against the
+/// [taxonomy](mdnt_extractor_core::taxonomy) when they are compiled. This
+/// type also splits names read at runtime, like the directories of
+/// extraction outputs, so the components are kept as strings.
 pub struct HarnessName<'a> {
     instruction: &'a str,
     method: &'a str,
     chip: &'a str,
     r#type: &'a str,
+    field: &'a str,
 }
 
 impl<'a> HarnessName<'a> {
+    /// Splits the name into its parts. Returns `None` if the name doesn't
+    /// have [`NAME_COMPONENTS`](mdnt_extractor_core::taxonomy::NAME_COMPONENTS) components.
+    pub fn parse(value: &'a str) -> Option<Self> {
+        let (value, field) = value.split_once('@').unwrap_or((value, DEFAULT_FIELD));
+        let parts: Vec<_> = value.split('/').collect();
+        let [instruction, method, chip, r#type] = parts[..] else {
+            return None;
+        };
+        Some(Self {
+            instruction,
+            method,
+            chip,
+            r#type,
+            field,
+        })
+    }
+
     pub fn instruction(&self) -> &'a str {
         self.instruction
     }
@@ -31,18 +60,9 @@ impl<'a> HarnessName<'a> {
     pub fn r#type(&self) -> &'a str {
         self.r#type
     }
-}
 
-impl<'a> From<&'a str> for HarnessName<'a> {
-    fn from(value: &'a str) -> Self {
-        let parts: Vec<_> = value.split('/').collect();
-        assert_eq!(parts.len(), 4);
-        Self {
-            instruction: parts[0],
-            method: parts[1],
-            chip: parts[2],
-            r#type: parts[3],
-        }
+    pub fn field(&self) -> &'a str {
+        self.field
     }
 }
 
@@ -51,6 +71,9 @@ enum QueryPart {
     Exact(String),
     Negated(String),
     Wildcard,
+    Glob(String, Regex),
+    Regex(String, Regex),
+    Not(Box<QueryPart>),
     Or(Vec<QueryPart>),
     And(Vec<QueryPart>),
 }
@@ -61,6 +84,8 @@ impl QueryPart {
             Self::Exact(s) => s == part,
             Self::Negated(s) => s != part,
             Self::Wildcard => true,
+            Self::Glob(_, re) | Self::Regex(_, re) => re.is_match(part),
+            Self::Not(op) => !op.matches(part),
             Self::Or(ops) => ops.iter().any(|op| op.matches(part)),
             Self::And(ops) => ops.iter().all(|op| op.matches(part)),
         }
@@ -70,6 +95,25 @@ impl QueryPart {
         matches!(self, Self::Or(_) | Self::And(_))
     }
 
+    fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Wildcard, part) | (part, Self::Wildcard) => part,
+            (Self::And(mut lhs), Self::And(rhs)) => {
+                lhs.extend(rhs);
+                Self::And(lhs)
+            }
+            (Self::And(mut lhs), rhs) => {
+                lhs.push(rhs);
+                Self::And(lhs)
+            }
+            (lhs, Self::And(mut rhs)) => {
+                rhs.insert(0, lhs);
+                Self::And(rhs)
+            }
+            (lhs, rhs) => Self::And(vec![lhs, rhs]),
+        }
+    }
+
     fn new_instructions_part(instructions: &[Instructions]) -> Self {
         if instructions.is_empty() {
             QueryPart::Wildcard
@@ -138,20 +182,39 @@ fn interleave(parts: &[QueryPart], sep: &str, f: &mut fmt::Formatter<'_>) -> fmt
     Ok(())
 }
 
+fn quoted(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "\"")?;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        // Backslashes are only escapes before a quote or another backslash so
+        // regular expressions can be written without doubling them.
+        let escape = match c {
+            '"' => true,
+            '\\' => matches!(chars.peek(), None | Some('"' | '\\')),
+            _ => false,
+        };
+        if escape {
+            write!(f, "\\")?;
+        }
+        write!(f, "{c}")?;
+    }
+    write!(f, "\"")
+}
+
 fn exact(s: &str, neg: bool, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     if neg {
         write!(f, "!")?;
     }
-    let has_ws = s.contains(char::is_whitespace);
+    let needs_quotes = s.is_empty()
+        || s.contains(|c: char| {
+            c.is_whitespace() || c == '*' || c == '?' || parse::SPECIAL_CHARS.contains(&c)
+        });
 
-    if has_ws {
-        write!(f, "\"")?;
-    }
-    write!(f, "{s}")?;
-    if has_ws {
-        write!(f, "\"")?;
+    if needs_quotes {
+        quoted(s, f)
+    } else {
+        write!(f, "{s}")
     }
-    Ok(())
 }
 
 impl fmt::Debug for QueryPart {
@@ -160,27 +223,39 @@ impl fmt::Debug for QueryPart {
             Self::Exact(s) => exact(s, false, f),
             Self::Negated(s) => exact(s, true, f),
             Self::Wildcard => write!(f, "*"),
+            Self::Glob(s, _) => write!(f, "{s}"),
+            Self::Regex(s, _) => {
+                write!(f, "~")?;
+                quoted(s, f)
+            }
+            Self::Not(part) if part.needs_parens() => write!(f, "!({part:?})"),
+            Self::Not(part) => write!(f, "!{part:?}"),
             Self::Or(parts) => interleave(parts, "|", f),
             Self::And(parts) => interleave(parts, "&", f),
         }
     }
 }
 
-/// The complete query with the 4 parts
+/// The complete query with the 4 parts and the field.
 pub struct Query {
     instruction: QueryPart,
     method: QueryPart,
     chip: QueryPart,
     r#type: QueryPart,
+    field: QueryPart,
 }
 
 impl Query {
-    pub fn matches<'s: 'n, 'n>(&'s self, name: impl Into<HarnessName<'n>>) -> bool {
-        let name = name.into();
-        self.instruction.matches(name.instruction())
-            && self.method.matches(name.method())
-            && self.chip.matches(name.chip())
-            && self.r#type.matches(name.r#type())
+    /// Returns true if the query matches the harness name. Strings that are
+    /// not harness names never match.
+    pub fn matches(&self, name: &str) -> bool {
+        HarnessName::parse(name).is_some_and(|name| {
+            self.instruction.matches(name.instruction())
+                && self.method.matches(name.method())
+                && self.chip.matches(name.chip())
+                && self.r#type.matches(name.r#type())
+                && self.field.matches(name.field())
+        })
     }
 
     /// Constructs a query from the CLI arguments
@@ -197,6 +272,18 @@ impl Query {
             method: QueryPart::new_method_part(whitelist, blacklist),
             chip: QueryPart::new_chips(chip, ignored_chips),
             r#type: QueryPart::new_part(r#type),
+            field: QueryPart::Wildcard,
+        }
+    }
+
+    /// Returns a query that only matches the names matched by both queries.
+    pub fn and(self, other: Self) -> Self {
+        Self {
+            instruction: self.instruction.and(other.instruction),
+            method: self.method.and(other.method),
+            chip: self.chip.and(other.chip),
+            r#type: self.r#type.and(other.r#type),
+            field: self.field.and(other.field),
         }
     }
 }
@@ -207,6 +294,34 @@ impl fmt::Debug for Query {
             f,
             "{:?}/{:?}/{:?}/{:?}",
             self.instruction, self.method, self.chip, self.r#type
-        )
+        )?;
+        match &self.field {
+            QueryPart::Wildcard => Ok(()),
+            field => write!(f, "@{field:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(query: &str, name: &str) -> bool {
+        query.parse::<Query>().unwrap().matches(name)
+    }
+
+    #[test]
+    fn selects_harnesses_by_field() {
+        let blstrs = "arithmetic/square/native/native";
+        let bn254 = "arithmetic/square/native/native@bn254";
+        assert!(matches("arithmetic/square/native", blstrs));
+        assert!(matches("arithmetic/square/native", bn254));
+        assert!(matches("arithmetic/square/native@blstrs", blstrs));
+        assert!(!matches("arithmetic/square/native@blstrs", bn254));
+        assert!(matches("arithmetic/square/native@bn254", bn254));
+        assert!(!matches("arithmetic/square/native@bn254", blstrs));
+        assert!(matches("*@bn254", bn254));
+        assert!(!matches("*@!bn254", bn254));
+        assert!(matches("*@!bn254", blstrs));
     }
 }
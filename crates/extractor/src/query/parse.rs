@@ -0,0 +1,395 @@
+//! Parser for selection expressions.
+//!
+//! A selection has up to 4 components separated by `/` that are matched
+//! against the instruction, method, chip and type of the harness name.
+//! Missing components match anything. Each component is a boolean expression
+//! with the following syntax, from lowest to highest precedence.
+//!
+//! - `a|b`: matches if either side matches.
+//! - `a&b`: matches if both sides match.
+//! - `!a`: matches if `a` doesn't match.
+//! - `(a)`: groups an expression.
+//! - `*`: matches anything.
+//! - `add_*`: glob pattern, where `*` matches any sequence of characters and
+//!   `?` matches a single character.
+//! - `~"add_\d+"`: regular expression that has to match the whole component.
+//! - `native` or `"native"`: matches the text exactly.
+//!
+//! The components can be followed by `@` and an expression that is matched
+//! against the field of the harness, for example `hash/*@blstrs`. Without it,
+//! harnesses over any field are selected.
+
+use std::str::FromStr;
+
+use regex::Regex;
+
+use super::{Query, QueryPart};
+
+#[derive(Debug, thiserror::Error)]
+pub enum QueryParseError {
+    #[error("Unexpected {found} at position {pos}, expected {expected}")]
+    Unexpected {
+        found: String,
+        pos: usize,
+        expected: &'static str,
+    },
+    #[error("Unterminated string starting at position {0}")]
+    UnterminatedString(usize),
+    #[error("Expected at most 4 components in the selection but got {0}")]
+    TooManyComponents(usize),
+    #[error("Invalid regular expression at position {0}: {1}")]
+    Regex(usize, regex::Error),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Slash,
+    At,
+    Or,
+    And,
+    Not,
+    LParen,
+    RParen,
+    Word(String),
+    Quoted(String),
+    Regex(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Slash => "'/'".to_owned(),
+            Token::At => "'@'".to_owned(),
+            Token::Or => "'|'".to_owned(),
+            Token::And => "'&'".to_owned(),
+            Token::Not => "'!'".to_owned(),
+            Token::LParen => "'('".to_owned(),
+            Token::RParen => "')'".to_owned(),
+            Token::Word(w) => format!("'{w}'"),
+            Token::Quoted(s) => format!("\"{s}\""),
+            Token::Regex(r) => format!("~\"{r}\""),
+        }
+    }
+}
+
+/// Characters that have a meaning in the syntax and can't be part of a word.
+pub(super) const SPECIAL_CHARS: &[char] = &['/', '@', '|', '&', '!', '(', ')', '"', '~'];
+
+fn quoted(chars: &[(usize, char)], start: usize) -> Result<(String, usize), QueryParseError> {
+    let mut s = String::new();
+    let mut i = start + 1;
+    loop {
+        match chars.get(i).map(|(_, c)| *c) {
+            None => return Err(QueryParseError::UnterminatedString(chars[start].0)),
+            Some('"') => return Ok((s, i + 1)),
+            Some('\\') if matches!(chars.get(i + 1), Some((_, '"' | '\\'))) => {
+                s.push(chars[i + 1].1);
+                i += 2;
+            }
+            Some(c) => {
+                s.push(c);
+                i += 1;
+            }
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, QueryParseError> {
+    let chars: Vec<_> = input.char_indices().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while let Some(&(pos, c)) = chars.get(i) {
+        let simple = match c {
+            '/' => Some(Token::Slash),
+            '@' => Some(Token::At),
+            '|' => Some(Token::Or),
+            '&' => Some(Token::And),
+            '!' => Some(Token::Not),
+            '(' => Some(Token::LParen),
+            ')' => Some(Token::RParen),
+            _ => None,
+        };
+        if let Some(token) = simple {
+            tokens.push((pos, token));
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' {
+            let (s, next) = quoted(&chars, i)?;
+            tokens.push((pos, Token::Quoted(s)));
+            i = next;
+        } else if c == '~' {
+            if !matches!(chars.get(i + 1), Some((_, '"'))) {
+                return Err(QueryParseError::Unexpected {
+                    found: "'~'".to_owned(),
+                    pos,
+                    expected: "a quoted regular expression after '~'",
+                });
+            }
+            let (s, next) = quoted(&chars, i + 1)?;
+            tokens.push((pos, Token::Regex(s)));
+            i = next;
+        } else {
+            let len = chars[i..]
+                .iter()
+                .take_while(|(_, c)| !c.is_whitespace() && !SPECIAL_CHARS.contains(c))
+                .count();
+            let word = chars[i..i + len].iter().map(|(_, c)| c).collect();
+            tokens.push((pos, Token::Word(word)));
+            i += len;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Converts a glob pattern into a regular expression that matches the whole
+/// input.
+fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn unexpected(&self, expected: &'static str) -> QueryParseError {
+        match self.tokens.get(self.pos) {
+            Some((pos, token)) => QueryParseError::Unexpected {
+                found: token.describe(),
+                pos: *pos,
+                expected,
+            },
+            None => QueryParseError::Unexpected {
+                found: "end of input".to_owned(),
+                pos: self.end,
+                expected,
+            },
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, QueryParseError> {
+        let mut parts = vec![self.or()?];
+        while self.eat(&Token::Slash) {
+            parts.push(self.or()?);
+        }
+        let field = if self.eat(&Token::At) {
+            self.or()?
+        } else {
+            QueryPart::Wildcard
+        };
+        if self.peek().is_some() {
+            return Err(self.unexpected("'/', '@', '|' or '&'"));
+        }
+        if parts.len() > 4 {
+            return Err(QueryParseError::TooManyComponents(parts.len()));
+        }
+        let mut parts = parts.into_iter().chain(std::iter::repeat_with(|| QueryPart::Wildcard));
+        Ok(Query {
+            instruction: parts.next().unwrap(),
+            method: parts.next().unwrap(),
+            chip: parts.next().unwrap(),
+            r#type: parts.next().unwrap(),
+            field,
+        })
+    }
+
+    fn or(&mut self) -> Result<QueryPart, QueryParseError> {
+        let mut ops = vec![self.and()?];
+        while self.eat(&Token::Or) {
+            ops.push(self.and()?);
+        }
+        Ok(if ops.len() == 1 {
+            ops.pop().unwrap()
+        } else {
+            QueryPart::Or(ops)
+        })
+    }
+
+    fn and(&mut self) -> Result<QueryPart, QueryParseError> {
+        let mut ops = vec![self.unary()?];
+        while self.eat(&Token::And) {
+            ops.push(self.unary()?);
+        }
+        Ok(if ops.len() == 1 {
+            ops.pop().unwrap()
+        } else {
+            QueryPart::And(ops)
+        })
+    }
+
+    fn unary(&mut self) -> Result<QueryPart, QueryParseError> {
+        if self.eat(&Token::Not) {
+            return Ok(match self.unary()? {
+                QueryPart::Exact(s) => QueryPart::Negated(s),
+                QueryPart::Negated(s) => QueryPart::Exact(s),
+                part => QueryPart::Not(Box::new(part)),
+            });
+        }
+        if self.eat(&Token::LParen) {
+            let part = self.or()?;
+            if !self.eat(&Token::RParen) {
+                return Err(self.unexpected("')'"));
+            }
+            return Ok(part);
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<QueryPart, QueryParseError> {
+        let Some((pos, token)) = self.tokens.get(self.pos).cloned() else {
+            return Err(self.unexpected("a pattern"));
+        };
+        let part = match token {
+            Token::Word(w) if w == "*" => QueryPart::Wildcard,
+            Token::Word(w) if w.contains(['*', '?']) => {
+                let re =
+                    Regex::new(&glob_to_regex(&w)).map_err(|e| QueryParseError::Regex(pos, e))?;
+                QueryPart::Glob(w, re)
+            }
+            Token::Word(w) | Token::Quoted(w) => QueryPart::Exact(w),
+            Token::Regex(r) => {
+                let re = Regex::new(&format!("^(?:{r})$"))
+                    .map_err(|e| QueryParseError::Regex(pos, e))?;
+                QueryPart::Regex(r, re)
+            }
+            _ => return Err(self.unexpected("a pattern")),
+        };
+        self.pos += 1;
+        Ok(part)
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            end: s.len(),
+        }
+        .query()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(query: &str, name: &str) -> bool {
+        query.parse::<Query>().unwrap().matches(name)
+    }
+
+    #[test]
+    fn parse_components() {
+        assert!(matches(
+            "hash/*/sha256|varhash/*",
+            "hash/hash_1/sha256/byte"
+        ));
+        assert!(matches(
+            "hash/*/sha256|varhash/*",
+            "hash/hash_1/varhash/byte"
+        ));
+        assert!(!matches(
+            "hash/*/sha256|varhash/*",
+            "hash/hash_1/sha512/byte"
+        ));
+        assert!(matches("hash", "hash/hash_1/sha512/byte"));
+        assert!(!matches("hash", "arithmetic/add/native/native"));
+    }
+
+    #[test]
+    fn parse_globs_and_negation() {
+        let query = "arithmetic/add_constants_*/native*/!field";
+        assert!(matches(query, "arithmetic/add_constants_3/native/native"));
+        assert!(matches(
+            query,
+            "arithmetic/add_constants_3/native_gadget/bit"
+        ));
+        assert!(!matches(query, "arithmetic/add_constants_3/native/field"));
+        assert!(!matches(query, "arithmetic/add/native/native"));
+        assert!(matches("*/!(add_*|mul)", "arithmetic/sub/native/native"));
+        assert!(!matches("*/!(add_*|mul)", "arithmetic/mul/native/native"));
+    }
+
+    #[test]
+    fn parse_regex() {
+        let query = r#"*/~"hash_\d+"&!hash_1"#;
+        assert!(matches(query, "hash/hash_10/sha256/byte"));
+        assert!(!matches(query, "hash/hash_1/sha256/byte"));
+        assert!(!matches(query, "hash/hash_10x/sha256/byte"));
+    }
+
+    #[test]
+    fn parse_field() {
+        assert!(matches("hash@blstrs", "hash/hash_1/sha256/byte"));
+        assert!(!matches("hash@pasta", "hash/hash_1/sha256/byte"));
+        assert!(matches("hash", "hash/hash_1/sha256/byte@pasta"));
+        assert!(matches(
+            "*/*/*/byte@!blstrs",
+            "hash/hash_1/sha256/byte@pasta"
+        ));
+        assert!(!matches("*@pasta|bn254", "hash/hash_1/sha256/byte"));
+    }
+
+    #[test]
+    fn malformed_names_dont_match() {
+        assert!(!matches("*", "hash/hash_1/sha256"));
+        assert!(!matches("*", "run/hash/hash_1/sha256/byte"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        for query in [
+            "a/b/c/d/e",
+            "a/(b",
+            "a/|b",
+            "a/~b",
+            "a/\"b",
+            "a/~\"(\"",
+            "a@b@c",
+            "a@",
+        ] {
+            assert!(query.parse::<Query>().is_err(), "{query}");
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        for query in [
+            "hash/*/sha256|varhash/*",
+            "arithmetic/add_constants_*/native*/!field",
+            r#"*/~"hash_\d+"&!(a|b)/"with space"/*"#,
+            "hash/*/*/*@pasta|bn254",
+        ] {
+            let parsed = query.parse::<Query>().unwrap();
+            let printed = format!("{parsed:?}");
+            assert_eq!(printed, query);
+            assert_eq!(format!("{:?}", printed.parse::<Query>().unwrap()), printed);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    fs::{self, File},
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use haloumi::ir_gen::circuit::resolved::ResolvedIRCircuit;
+use mdnt_extractor_core::{lookups::TableModule, shuffles::ShuffleModule};
+
+use crate::picus::header::Header;
+use codegen::{SmtlibBackend, SmtlibParams};
+use query::DeterminismQuery;
+
+mod codegen;
+mod module;
+mod order;
+mod query;
+
+/// Lowers the circuit into a finite-field SMT-LIB2 query and writes it into
+/// `<output_base>/<name>/output.smt2`.
+///
+/// The query asks whether two witnesses that agree on the inputs of the circuit
+/// can disagree on any of its outputs. An `unsat` answer means the circuit is
+/// deterministic. Calls to the table modules are encoded as the disjunction of
+/// the rows of their table, and calls to the shuffle modules as the equality
+/// of the power sums of their halves.
+pub fn write_smtlib_output(
+    header: &Header,
+    name: &'static str,
+    output_base: impl AsRef<Path>,
+    ir: &ResolvedIRCircuit,
+    tables: &[TableModule],
+    shuffles: &[ShuffleModule],
+) -> anyhow::Result<PathBuf> {
+    let output_dir = output_base.as_ref().join(name);
+    fs::create_dir_all(&output_dir)?;
+    let output = SmtlibBackend::initialize(SmtlibParams::new(name)).codegen(ir, ir.ctx())?;
+    let query = DeterminismQuery::new(&output, tables, shuffles)?;
+
+    let output_path = output_dir.join("output.smt2");
+    let mut output_file = File::create(&output_path)?;
+    writeln!(output_file, "{header}")?;
+    write!(output_file, "{query}")?;
+    log::info!("Saved SMT-LIB output in {}", output_path.display());
+    Ok(output_path)
+}
@@ -0,0 +1,320 @@
+use std::{cell::RefCell, rc::Rc};
+
+use haloumi::{
+    core::{
+        cmp::CmpOp,
+        felt::{Felt, Prime},
+        slot::{arg::ArgNo, output::OutputId, Slot},
+    },
+    synthesis::io::{AdviceIO, InstanceIO},
+};
+use haloumi_backend::{
+    codegen::{Codegen, CodegenParams},
+    Backend,
+};
+use haloumi_lowering::{backend_err, ExprLowering, Lowering};
+
+use super::module::{Module, Stmt, Term};
+
+/// Instance of a [`Backend`] prepared for lowering to SMT-LIB.
+pub type SmtlibBackend = Backend<SmtlibCodegen, SmtlibState>;
+pub type SmtlibState = Rc<RefCell<SmtlibCodegenInner>>;
+type ModuleRef = Rc<RefCell<Module>>;
+
+#[derive(Clone, Debug)]
+pub struct SmtlibParams {
+    entrypoint: String,
+}
+
+impl SmtlibParams {
+    pub fn new(entrypoint: impl Into<String>) -> Self {
+        Self {
+            entrypoint: entrypoint.into(),
+        }
+    }
+}
+
+impl From<SmtlibParams> for SmtlibState {
+    fn from(params: SmtlibParams) -> Self {
+        Rc::new(RefCell::new(SmtlibCodegenInner {
+            params,
+            prime: None,
+            modules: vec![],
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct SmtlibCodegenInner {
+    params: SmtlibParams,
+    prime: Option<Prime>,
+    modules: Vec<ModuleRef>,
+}
+
+impl SmtlibCodegenInner {
+    fn add_module(&mut self, name: String, inputs: usize, outputs: usize) -> SmtlibModuleLowering {
+        log::debug!("Adding SMT-LIB module {name}");
+        let module = Rc::new(RefCell::new(Module::new(name, inputs, outputs)));
+        self.modules.push(module.clone());
+        SmtlibModuleLowering { module }
+    }
+}
+
+impl CodegenParams for SmtlibCodegenInner {
+    fn inlining_enabled(&self) -> bool {
+        // Each group is lowered into its own module and the calls are resolved
+        // when building the final query.
+        false
+    }
+}
+
+/// Output produced by the SMT-LIB backend.
+#[derive(Debug)]
+pub struct SmtlibOutput {
+    prime: Prime,
+    entrypoint: String,
+    modules: Vec<Module>,
+}
+
+impl SmtlibOutput {
+    pub fn prime(&self) -> Prime {
+        self.prime
+    }
+
+    pub fn entrypoint_name(&self) -> &str {
+        &self.entrypoint
+    }
+
+    pub fn entrypoint(&self) -> Option<&Module> {
+        self.module(&self.entrypoint)
+    }
+
+    pub fn module(&self, name: &str) -> Option<&Module> {
+        self.modules.iter().find(|m| m.name() == name)
+    }
+}
+
+/// Code generator for SMT-LIB.
+#[derive(Debug, Clone)]
+pub struct SmtlibCodegen {
+    inner: SmtlibState,
+}
+
+impl<'c: 's, 's> Codegen<'c, 's> for SmtlibCodegen {
+    type FuncOutput = SmtlibModuleLowering;
+    type Output = SmtlibOutput;
+    type State = SmtlibState;
+    type Error = SmtlibError;
+
+    fn initialize(state: &'s Self::State) -> Self {
+        Self {
+            inner: state.clone(),
+        }
+    }
+
+    fn set_prime_field(&self, prime: Prime) -> Result<(), SmtlibError> {
+        self.inner.borrow_mut().prime = Some(prime);
+        Ok(())
+    }
+
+    fn define_function(
+        &self,
+        name: &str,
+        inputs: usize,
+        outputs: usize,
+    ) -> Result<Self::FuncOutput, SmtlibError> {
+        Ok(self.inner.borrow_mut().add_module(name.to_owned(), inputs, outputs))
+    }
+
+    fn define_main_function(
+        &self,
+        advice_io: &AdviceIO,
+        instance_io: &InstanceIO,
+    ) -> Result<Self::FuncOutput, SmtlibError> {
+        let mut inner = self.inner.borrow_mut();
+        let name = inner.params.entrypoint.clone();
+        Ok(inner.add_module(
+            name,
+            instance_io.inputs().len() + advice_io.inputs().len(),
+            instance_io.outputs().len() + advice_io.outputs().len(),
+        ))
+    }
+
+    fn generate_output(self) -> Result<Self::Output, SmtlibError> {
+        let inner = self.inner.borrow();
+        Ok(SmtlibOutput {
+            prime: inner.prime.ok_or(SmtlibError::PrimeNotSet)?,
+            entrypoint: inner.params.entrypoint.clone(),
+            modules: inner.modules.iter().map(|m| m.borrow().clone()).collect(),
+        })
+    }
+}
+
+/// Lowers the statements of a group into a [`Module`].
+#[derive(Debug, Clone)]
+pub struct SmtlibModuleLowering {
+    module: ModuleRef,
+}
+
+impl SmtlibModuleLowering {
+    fn push(&self, stmt: Stmt) -> haloumi_lowering::Result<()> {
+        self.module.borrow_mut().push(stmt);
+        Ok(())
+    }
+}
+
+impl Lowering for SmtlibModuleLowering {
+    fn generate_constraint(
+        &self,
+        op: CmpOp,
+        lhs: &Self::CellOutput,
+        rhs: &Self::CellOutput,
+    ) -> haloumi_lowering::Result<()> {
+        self.push(Stmt::Assert(Term::cmp(op, lhs, rhs)))
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.module.borrow().constraints_len()
+    }
+
+    fn generate_comment(&self, s: String) -> haloumi_lowering::Result<()> {
+        self.push(Stmt::Comment(s))
+    }
+
+    fn generate_assume_deterministic(&self, slot: Slot) -> haloumi_lowering::Result<()> {
+        self.push(Stmt::AssumeDeterministic(slot))
+    }
+
+    fn generate_call(
+        &self,
+        name: &str,
+        inputs: &[Self::CellOutput],
+        outputs: &[Slot],
+    ) -> haloumi_lowering::Result<()> {
+        self.push(Stmt::Call {
+            callee: name.to_owned(),
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+        })
+    }
+
+    fn generate_assert(&self, expr: &Self::CellOutput) -> haloumi_lowering::Result<()> {
+        self.push(Stmt::Assert(expr.clone()))
+    }
+
+    fn generate_post_condition(&self, _: &Self::CellOutput) -> haloumi_lowering::Result<()> {
+        // Post-conditions are properties of the circuit, not constraints, and
+        // don't take part in the determinism query.
+        self.push(Stmt::Comment("post-condition omitted".to_owned()))
+    }
+}
+
+impl ExprLowering for SmtlibModuleLowering {
+    type CellOutput = Term;
+
+    fn lower_sum(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::add(lhs, rhs))
+    }
+
+    fn lower_product(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::mul(lhs, rhs))
+    }
+
+    fn lower_neg(&self, expr: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::neg(expr))
+    }
+
+    fn lower_constant(&self, f: Felt) -> haloumi_lowering::Result<Term> {
+        Ok(Term::constant(f))
+    }
+
+    fn lower_eq(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::cmp(CmpOp::Eq, lhs, rhs))
+    }
+
+    fn lower_lt(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::cmp(CmpOp::Lt, lhs, rhs))
+    }
+
+    fn lower_le(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::cmp(CmpOp::Le, lhs, rhs))
+    }
+
+    fn lower_gt(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::cmp(CmpOp::Gt, lhs, rhs))
+    }
+
+    fn lower_ge(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::cmp(CmpOp::Ge, lhs, rhs))
+    }
+
+    fn lower_ne(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::cmp(CmpOp::Ne, lhs, rhs))
+    }
+
+    fn lower_and(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::and(lhs, rhs))
+    }
+
+    fn lower_or(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::or(lhs, rhs))
+    }
+
+    fn lower_not(&self, value: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::not(value))
+    }
+
+    fn lower_true(&self) -> haloumi_lowering::Result<Term> {
+        Ok(Term::bool(true))
+    }
+
+    fn lower_false(&self) -> haloumi_lowering::Result<Term> {
+        Ok(Term::bool(false))
+    }
+
+    fn lower_det(&self, _: &Term) -> haloumi_lowering::Result<Term> {
+        // Determinism is the property the whole query checks and cannot be
+        // expressed within a single witness.
+        Err(backend_err!(SmtlibError::Unsupported("det")))
+    }
+
+    fn lower_implies(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::implies(lhs, rhs))
+    }
+
+    fn lower_iff(&self, lhs: &Term, rhs: &Term) -> haloumi_lowering::Result<Term> {
+        Ok(Term::iff(lhs, rhs))
+    }
+
+    fn lower_function_input(&self, i: usize) -> Slot {
+        ArgNo::from(i).into()
+    }
+
+    fn lower_function_output(&self, o: usize) -> Slot {
+        OutputId::from(o).into()
+    }
+
+    fn lower_funcio<IO>(&self, io: IO) -> haloumi_lowering::Result<Term>
+    where
+        IO: Into<Slot>,
+    {
+        Ok(Term::var(io.into()))
+    }
+}
+
+/// Error type used by [`SmtlibCodegen`].
+#[derive(Debug, thiserror::Error)]
+pub enum SmtlibError {
+    #[error(transparent)]
+    Lowering(#[from] haloumi_lowering::error::Error),
+    #[error(transparent)]
+    IR(#[from] haloumi::ir_gen::error::Error),
+    #[error("Prime was not set!")]
+    PrimeNotSet,
+    #[error("Expression '{0}' is not supported by the SMT-LIB backend")]
+    Unsupported(&'static str),
+    #[error("Module {0} is called with {1} inputs and {2} outputs but declares {3} and {4}")]
+    ArityMismatch(String, usize, usize, usize, usize),
+    #[error("Entrypoint module {0} was not generated")]
+    MissingEntrypoint(String),
+}
@@ -0,0 +1,200 @@
+use std::{fmt::Write as _, rc::Rc};
+
+use haloumi::core::{cmp::CmpOp, felt::Felt, slot::Slot};
+
+/// Expression over the variables of a module.
+///
+/// Cloning is cheap since the nodes are shared.
+#[derive(Clone, Debug)]
+pub struct Term(Rc<TermImpl>);
+
+#[derive(Debug)]
+enum TermImpl {
+    Var(Slot),
+    Const(Felt),
+    Bool(bool),
+    Add(Term, Term),
+    Mul(Term, Term),
+    Neg(Term),
+    Cmp(CmpOp, Term, Term),
+    And(Term, Term),
+    Or(Term, Term),
+    Not(Term),
+    Implies(Term, Term),
+    Iff(Term, Term),
+}
+
+impl From<TermImpl> for Term {
+    fn from(value: TermImpl) -> Self {
+        Self(Rc::new(value))
+    }
+}
+
+impl Term {
+    pub fn var(slot: Slot) -> Self {
+        TermImpl::Var(slot).into()
+    }
+
+    pub fn constant(f: Felt) -> Self {
+        TermImpl::Const(f).into()
+    }
+
+    pub fn bool(b: bool) -> Self {
+        TermImpl::Bool(b).into()
+    }
+
+    pub fn add(lhs: &Term, rhs: &Term) -> Self {
+        TermImpl::Add(lhs.clone(), rhs.clone()).into()
+    }
+
+    pub fn mul(lhs: &Term, rhs: &Term) -> Self {
+        TermImpl::Mul(lhs.clone(), rhs.clone()).into()
+    }
+
+    pub fn neg(expr: &Term) -> Self {
+        TermImpl::Neg(expr.clone()).into()
+    }
+
+    pub fn cmp(op: CmpOp, lhs: &Term, rhs: &Term) -> Self {
+        TermImpl::Cmp(op, lhs.clone(), rhs.clone()).into()
+    }
+
+    pub fn and(lhs: &Term, rhs: &Term) -> Self {
+        TermImpl::And(lhs.clone(), rhs.clone()).into()
+    }
+
+    pub fn or(lhs: &Term, rhs: &Term) -> Self {
+        TermImpl::Or(lhs.clone(), rhs.clone()).into()
+    }
+
+    pub fn not(expr: &Term) -> Self {
+        TermImpl::Not(expr.clone()).into()
+    }
+
+    pub fn implies(lhs: &Term, rhs: &Term) -> Self {
+        TermImpl::Implies(lhs.clone(), rhs.clone()).into()
+    }
+
+    pub fn iff(lhs: &Term, rhs: &Term) -> Self {
+        TermImpl::Iff(lhs.clone(), rhs.clone()).into()
+    }
+
+    /// Returns the value of the term if it is a constant.
+    pub fn as_constant(&self) -> Option<Felt> {
+        match &*self.0 {
+            TermImpl::Const(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Renders the term as an SMT-LIB2 expression in the given environment.
+    pub fn render(&self, env: &mut impl Env) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, env);
+        out
+    }
+
+    fn render_app(out: &mut String, f: &str, args: &[&Term], env: &mut dyn Env) {
+        write!(out, "({f}").unwrap();
+        for arg in args {
+            out.push(' ');
+            arg.render_into(out, env);
+        }
+        out.push(')');
+    }
+
+    fn render_into(&self, out: &mut String, env: &mut dyn Env) {
+        let app = Self::render_app;
+        match &*self.0 {
+            TermImpl::Var(slot) => out.push_str(&env.var(*slot)),
+            TermImpl::Const(f) => write!(out, "(as ff{f} F)").unwrap(),
+            TermImpl::Bool(b) => write!(out, "{b}").unwrap(),
+            TermImpl::Add(lhs, rhs) => app(out, "ff.add", &[lhs, rhs], env),
+            TermImpl::Mul(lhs, rhs) => app(out, "ff.mul", &[lhs, rhs], env),
+            TermImpl::Neg(expr) => app(out, "ff.neg", &[expr], env),
+            TermImpl::Cmp(op, lhs, rhs) => match op {
+                CmpOp::Eq => app(out, "=", &[lhs, rhs], env),
+                CmpOp::Ne => app(out, "distinct", &[lhs, rhs], env),
+                CmpOp::Lt => out.push_str(&env.less_than(lhs, rhs)),
+                CmpOp::Le => write!(out, "(not {})", env.less_than(rhs, lhs)).unwrap(),
+                CmpOp::Gt => out.push_str(&env.less_than(rhs, lhs)),
+                CmpOp::Ge => write!(out, "(not {})", env.less_than(lhs, rhs)).unwrap(),
+            },
+            TermImpl::And(lhs, rhs) => app(out, "and", &[lhs, rhs], env),
+            TermImpl::Or(lhs, rhs) => app(out, "or", &[lhs, rhs], env),
+            TermImpl::Not(expr) => app(out, "not", &[expr], env),
+            TermImpl::Implies(lhs, rhs) => app(out, "=>", &[lhs, rhs], env),
+            TermImpl::Iff(lhs, rhs) => app(out, "=", &[lhs, rhs], env),
+        }
+    }
+}
+
+/// Context in which a [`Term`] is rendered.
+pub trait Env {
+    /// Returns the name of the variable of the slot.
+    fn var(&mut self, slot: Slot) -> String;
+
+    /// Returns a formula that holds iff the canonical representative of `lhs`
+    /// is smaller than the one of `rhs`.
+    ///
+    /// The field sort has no ordering, so comparisons are left to the
+    /// environment.
+    fn less_than(&mut self, lhs: &Term, rhs: &Term) -> String;
+}
+
+/// Statements of a module.
+#[derive(Clone, Debug)]
+pub enum Stmt {
+    Assert(Term),
+    Call {
+        callee: String,
+        inputs: Vec<Term>,
+        outputs: Vec<Slot>,
+    },
+    AssumeDeterministic(Slot),
+    Comment(String),
+}
+
+/// A lowered group of the circuit.
+#[derive(Clone, Debug)]
+pub struct Module {
+    name: String,
+    inputs: usize,
+    outputs: usize,
+    stmts: Vec<Stmt>,
+}
+
+impl Module {
+    pub fn new(name: String, inputs: usize, outputs: usize) -> Self {
+        Self {
+            name,
+            inputs,
+            outputs,
+            stmts: vec![],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    pub fn stmts(&self) -> &[Stmt] {
+        &self.stmts
+    }
+
+    pub fn push(&mut self, stmt: Stmt) {
+        self.stmts.push(stmt);
+    }
+
+    pub fn constraints_len(&self) -> usize {
+        self.stmts.iter().filter(|stmt| matches!(stmt, Stmt::Assert(_))).count()
+    }
+}
@@ -0,0 +1,196 @@
+//! Ordering of field elements by their canonical representatives.
+//!
+//! The finite field theory has no ordering, so each compared value is
+//! decomposed into the bits of its canonical representative, i.e. the integer
+//! in `[0, p)` it is congruent to, and the bits are compared
+//! lexicographically.
+
+use std::fmt;
+
+use haloumi::core::felt::{Felt, Prime};
+
+/// Bit of the canonical representative of a value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Bit {
+    /// Variable constrained to be 0 or 1.
+    Var(String),
+    Const(bool),
+}
+
+impl Bit {
+    /// Returns the bits of the constant, least significant first.
+    pub fn of_constant(f: &Felt, width: u64) -> Vec<Bit> {
+        (0..width).map(|i| Bit::Const(f.bit(i))).collect()
+    }
+
+    /// Returns the bits of the prime, least significant first.
+    pub fn of_prime(prime: Prime) -> Vec<Bit> {
+        let p = prime.value();
+        (0..p.bits()).map(|i| Bit::Const(p.bit(i))).collect()
+    }
+
+    fn is_one(&self) -> Formula {
+        match self {
+            Bit::Var(var) => Formula::IsOne(var.clone()),
+            Bit::Const(b) => Formula::Const(*b),
+        }
+    }
+
+    fn same(&self, other: &Bit) -> Formula {
+        match (self, other) {
+            (Bit::Var(lhs), Bit::Var(rhs)) => Formula::Same(lhs.clone(), rhs.clone()),
+            (Bit::Var(_), Bit::Const(b)) => {
+                let is_one = self.is_one();
+                if *b {
+                    is_one
+                } else {
+                    Formula::not(is_one)
+                }
+            }
+            (Bit::Const(_), Bit::Var(_)) => other.same(self),
+            (Bit::Const(lhs), Bit::Const(rhs)) => Formula::Const(lhs == rhs),
+        }
+    }
+}
+
+/// Boolean formula over bits.
+///
+/// The constructors fold constants so that comparing against a constant only
+/// mentions the bits of the variable.
+#[derive(Clone, Debug)]
+pub enum Formula {
+    Const(bool),
+    IsOne(String),
+    Same(String, String),
+    Not(Box<Formula>),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    fn not(f: Formula) -> Formula {
+        match f {
+            Formula::Const(b) => Formula::Const(!b),
+            Formula::Not(f) => *f,
+            f => Formula::Not(Box::new(f)),
+        }
+    }
+
+    fn and(lhs: Formula, rhs: Formula) -> Formula {
+        match (lhs, rhs) {
+            (Formula::Const(false), _) | (_, Formula::Const(false)) => Formula::Const(false),
+            (Formula::Const(true), f) | (f, Formula::Const(true)) => f,
+            (lhs, rhs) => Formula::And(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    fn or(lhs: Formula, rhs: Formula) -> Formula {
+        match (lhs, rhs) {
+            (Formula::Const(true), _) | (_, Formula::Const(true)) => Formula::Const(true),
+            (Formula::Const(false), f) | (f, Formula::Const(false)) => f,
+            (lhs, rhs) => Formula::Or(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    /// Evaluates the formula with the given value of the variables.
+    #[cfg(test)]
+    fn eval(&self, value: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            Formula::Const(b) => *b,
+            Formula::IsOne(var) => value(var),
+            Formula::Same(lhs, rhs) => value(lhs) == value(rhs),
+            Formula::Not(f) => !f.eval(value),
+            Formula::And(lhs, rhs) => lhs.eval(value) && rhs.eval(value),
+            Formula::Or(lhs, rhs) => lhs.eval(value) || rhs.eval(value),
+        }
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Formula::Const(b) => write!(f, "{b}"),
+            Formula::IsOne(var) => write!(f, "(= {var} (as ff1 F))"),
+            Formula::Same(lhs, rhs) => write!(f, "(= {lhs} {rhs})"),
+            Formula::Not(g) => write!(f, "(not {g})"),
+            Formula::And(lhs, rhs) => write!(f, "(and {lhs} {rhs})"),
+            Formula::Or(lhs, rhs) => write!(f, "(or {lhs} {rhs})"),
+        }
+    }
+}
+
+/// Returns a formula that holds iff the bits of `lhs` are smaller than the
+/// bits of `rhs`. Both are least significant first and missing bits are 0.
+pub fn less_than(lhs: &[Bit], rhs: &[Bit]) -> Formula {
+    let zero = Bit::Const(false);
+    let width = lhs.len().max(rhs.len());
+    (0..width).fold(Formula::Const(false), |below, i| {
+        let l = lhs.get(i).unwrap_or(&zero);
+        let r = rhs.get(i).unwrap_or(&zero);
+        Formula::or(
+            Formula::and(Formula::not(l.is_one()), r.is_one()),
+            Formula::and(l.same(r), below),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str, width: usize) -> Vec<Bit> {
+        (0..width).map(|i| Bit::Var(format!("{name}{i}"))).collect()
+    }
+
+    fn constant(value: u32, width: usize) -> Vec<Bit> {
+        (0..width).map(|i| Bit::Const((value >> i) & 1 == 1)).collect()
+    }
+
+    /// Reads the value of the bit variables from `a` and `b`.
+    fn assignment(a: u32, b: u32) -> impl Fn(&str) -> bool {
+        move |var| {
+            let (name, i) = var.split_at(1);
+            let value = if name == "a" { a } else { b };
+            (value >> i.parse::<u32>().unwrap()) & 1 == 1
+        }
+    }
+
+    #[test]
+    fn compares_variables() {
+        let lt = less_than(&var("a", 3), &var("b", 3));
+        for a in 0..8 {
+            for b in 0..8 {
+                assert_eq!(lt.eval(&assignment(a, b)), a < b, "{a} < {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn compares_against_constants() {
+        for c in 0..8 {
+            let lt = less_than(&var("a", 3), &constant(c, 3));
+            let gt = less_than(&constant(c, 3), &var("a", 3));
+            for a in 0..8 {
+                assert_eq!(lt.eval(&assignment(a, 0)), a < c, "{a} < {c}");
+                assert_eq!(gt.eval(&assignment(a, 0)), c < a, "{c} < {a}");
+            }
+        }
+    }
+
+    #[test]
+    fn compares_different_widths() {
+        let lt = less_than(&var("a", 2), &constant(4, 3));
+        assert!(matches!(lt, Formula::Const(true)));
+        let lt = less_than(&constant(4, 3), &var("a", 2));
+        assert!(matches!(lt, Formula::Const(false)));
+    }
+
+    #[test]
+    fn renders_formula() {
+        assert_eq!(
+            less_than(&var("a", 3), &constant(5, 3)).to_string(),
+            "(or (not (= a2 (as ff1 F))) (and (= a2 (as ff1 F)) \
+             (and (not (= a1 (as ff1 F))) (not (= a0 (as ff1 F))))))"
+        );
+    }
+}
@@ -0,0 +1,671 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+};
+
+use haloumi::core::{felt::Prime, slot::Slot};
+use mdnt_extractor_core::{lookups::TableModule, shuffles::ShuffleModule};
+use num_bigint::BigUint;
+
+use super::{
+    codegen::{SmtlibError, SmtlibOutput},
+    module::{Env, Module, Stmt, Term},
+    order::{self, Bit},
+};
+
+/// Two-witness determinism query of a circuit.
+///
+/// The entrypoint module is instantiated twice over the same inputs and fixed
+/// cells, and the query is satisfiable iff the outputs of both copies can
+/// differ. Comparisons are encoded over the bits of the canonical
+/// representatives of the compared values. Calls to
+/// generated modules are inlined. Calls to modules that are not part of the
+/// circuit (i.e. the ones defined in the Picus preludes or emitted by lookup
+/// callbacks) are encoded as uninterpreted functions, which assumes they are
+/// deterministic. The ones without outputs are uninterpreted predicates that
+/// hold on their arguments, except for the table modules, whose arguments
+/// are constrained to be a row of their table, and the shuffle modules, whose
+/// halves are constrained to have the same power sums.
+pub struct DeterminismQuery {
+    prime: Prime,
+    externs: BTreeMap<String, (usize, usize)>,
+    decls: Vec<String>,
+    body: Vec<String>,
+    assumed: Vec<(String, String)>,
+    outputs: Vec<(String, String)>,
+}
+
+impl DeterminismQuery {
+    pub fn new(
+        output: &SmtlibOutput,
+        tables: &[TableModule],
+        shuffles: &[ShuffleModule],
+    ) -> Result<Self, SmtlibError> {
+        let main = output
+            .entrypoint()
+            .ok_or_else(|| SmtlibError::MissingEntrypoint(output.entrypoint_name().to_owned()))?;
+        let mut encoder = Encoder {
+            output,
+            tables,
+            shuffles,
+            externs: Default::default(),
+            decls: Default::default(),
+            declared: Default::default(),
+            body: Default::default(),
+            assumed: Default::default(),
+            instances: 0,
+            decomposed: Default::default(),
+        };
+
+        let inputs: Vec<_> =
+            (0..main.inputs()).map(|i| encoder.declare(format!("in{i}"))).collect();
+        let mut copies = vec![];
+        for w in 1..=2 {
+            let prefix = format!("w{w}");
+            let outputs: Vec<_> = (0..main.outputs())
+                .map(|o| encoder.declare(format!("{prefix}.out{o}")))
+                .collect();
+            encoder.body.push(format!("; Witness {w}"));
+            encoder.instances = 0;
+            let assumed_before = encoder.assumed.len();
+            encoder.instantiate(main, &prefix, &inputs, &outputs)?;
+            copies.push((outputs, encoder.assumed.split_off(assumed_before)));
+        }
+        let (outputs2, assumed2) = copies.pop().unwrap();
+        let (outputs1, assumed1) = copies.pop().unwrap();
+
+        Ok(Self {
+            prime: output.prime(),
+            externs: encoder.externs,
+            decls: encoder.decls,
+            body: encoder.body,
+            assumed: assumed1.into_iter().zip(assumed2).collect(),
+            outputs: outputs1.into_iter().zip(outputs2).collect(),
+        })
+    }
+}
+
+struct Encoder<'o> {
+    output: &'o SmtlibOutput,
+    tables: &'o [TableModule],
+    shuffles: &'o [ShuffleModule],
+    externs: BTreeMap<String, (usize, usize)>,
+    decls: Vec<String>,
+    declared: HashSet<String>,
+    body: Vec<String>,
+    assumed: Vec<String>,
+    instances: usize,
+    /// Bits of the values that have been compared, by their rendered term.
+    decomposed: HashMap<String, Vec<Bit>>,
+}
+
+fn symbol(name: &str) -> String {
+    format!("|{name}|")
+}
+
+/// Applies an associative operator to the terms, which SMT-LIB only allows
+/// over two or more arguments.
+fn nary(op: &str, unit: &str, terms: Vec<String>) -> String {
+    match terms.len() {
+        0 => unit.to_owned(),
+        1 => terms.into_iter().next().unwrap(),
+        _ => format!("({op} {})", terms.join(" ")),
+    }
+}
+
+/// Constrains the arguments to be one of the rows of the table.
+fn table_rows(args: &[String], rows: &[Vec<BigUint>]) -> String {
+    let row = |row: &Vec<BigUint>| {
+        let eqs = args.iter().zip(row).map(|(arg, value)| format!("(= {arg} (as ff{value} F))"));
+        nary("and", "true", eqs.collect())
+    };
+    nary("or", "false", rows.iter().map(row).collect())
+}
+
+/// Constrains both halves of the arguments to have the same power sums up to
+/// the number of rows, with the values of each row combined as in the Picus
+/// shuffle modules.
+fn power_sums(args: &[String], module: &ShuffleModule, prime: &BigUint) -> Vec<String> {
+    let row = |row: &[String]| {
+        let values = row.iter().enumerate().map(|(n, arg)| match n {
+            0 => arg.clone(),
+            n => {
+                let factor = (BigUint::from(1u8) << (32 * n)) % prime;
+                format!("(ff.mul (as ff{factor} F) {arg})")
+            }
+        });
+        nary("ff.add", "(as ff0 F)", values.collect())
+    };
+    let rows: Vec<_> = args.chunks(module.width()).map(row).collect();
+    let (lhs, rhs) = rows.split_at(module.rows());
+    let power_sum = |rows: &[String], k: usize| {
+        let powers = rows.iter().map(|row| nary("ff.mul", "(as ff1 F)", vec![row.clone(); k]));
+        nary("ff.add", "(as ff0 F)", powers.collect())
+    };
+    (1..=module.rows())
+        .map(|k| format!("(assert (= {} {}))", power_sum(lhs, k), power_sum(rhs, k)))
+        .collect()
+}
+
+fn slot_name(slot: Slot) -> String {
+    match slot {
+        Slot::Advice(cell) => format!("adv[{},{}]", cell.col(), cell.row()),
+        Slot::Fixed(cell) => format!("fix[{},{}]", cell.col(), cell.row()),
+        slot => slot.to_string(),
+    }
+}
+
+/// Variables of the module instance being encoded.
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    prefix: &'a str,
+    inputs: &'a [String],
+    outputs: &'a [String],
+}
+
+impl<'a> Scope<'a> {
+    fn new(prefix: &'a str, inputs: &'a [String], outputs: &'a [String]) -> Self {
+        Self {
+            prefix,
+            inputs,
+            outputs,
+        }
+    }
+
+    fn with<'e, 'o>(self, encoder: &'e mut Encoder<'o>) -> ScopedEncoder<'a, 'e, 'o> {
+        ScopedEncoder {
+            scope: self,
+            encoder,
+        }
+    }
+}
+
+/// Renders the terms of a module instance.
+struct ScopedEncoder<'a, 'e, 'o> {
+    scope: Scope<'a>,
+    encoder: &'e mut Encoder<'o>,
+}
+
+impl Env for ScopedEncoder<'_, '_, '_> {
+    fn var(&mut self, slot: Slot) -> String {
+        let Scope {
+            prefix,
+            inputs,
+            outputs,
+        } = self.scope;
+        self.encoder.var(prefix, inputs, outputs, slot)
+    }
+
+    fn less_than(&mut self, lhs: &Term, rhs: &Term) -> String {
+        let lhs = self.encoder.bits(lhs, self.scope);
+        let rhs = self.encoder.bits(rhs, self.scope);
+        order::less_than(&lhs, &rhs).to_string()
+    }
+}
+
+impl Encoder<'_> {
+    fn declare(&mut self, name: String) -> String {
+        let name = symbol(&name);
+        if self.declared.insert(name.clone()) {
+            self.decls.push(name.clone());
+        }
+        name
+    }
+
+    /// Returns the name of the variable of the slot in the given instance.
+    ///
+    /// Fixed cells are shared by every instance and by both witnesses.
+    fn var(&mut self, prefix: &str, inputs: &[String], outputs: &[String], slot: Slot) -> String {
+        match slot {
+            Slot::Arg(arg) if *arg < inputs.len() => inputs[*arg].clone(),
+            Slot::Output(field) if *field < outputs.len() => outputs[*field].clone(),
+            Slot::Fixed(_) => self.declare(slot_name(slot)),
+            slot => self.declare(format!("{prefix}.{}", slot_name(slot))),
+        }
+    }
+
+    /// Returns the bits of the canonical representative of the term.
+    ///
+    /// The bits of a non-constant term are declared and constrained the first
+    /// time it is compared. The representative is unique so both witnesses
+    /// agree on the bits of equal values.
+    fn bits(&mut self, term: &Term, scope: Scope) -> Vec<Bit> {
+        let prime = self.output.prime();
+        if let Some(f) = term.as_constant() {
+            return Bit::of_constant(&f, prime.value().bits());
+        }
+        let rendered = term.render(&mut scope.with(self));
+        if let Some(bits) = self.decomposed.get(&rendered) {
+            return bits.clone();
+        }
+        let n = self.decomposed.len();
+        let bits: Vec<_> = (0..prime.value().bits())
+            .map(|i| Bit::Var(self.declare(format!("bits#{n}.{i}"))))
+            .collect();
+        let names: Vec<_> = bits
+            .iter()
+            .map(|bit| match bit {
+                Bit::Var(name) => name.as_str(),
+                Bit::Const(_) => unreachable!(),
+            })
+            .collect();
+        self.body.push(format!("; Bits of {rendered}"));
+        for name in &names {
+            self.body.push(format!(
+                "(assert (or (= {name} (as ff0 F)) (= {name} (as ff1 F))))"
+            ));
+        }
+        self.body.push(format!(
+            "(assert (= {rendered} (ff.bitsum {})))",
+            names.join(" ")
+        ));
+        self.body.push(format!(
+            "(assert {})",
+            order::less_than(&bits, &Bit::of_prime(prime))
+        ));
+        self.decomposed.insert(rendered, bits.clone());
+        bits
+    }
+
+    fn instantiate(
+        &mut self,
+        module: &'_ Module,
+        prefix: &str,
+        inputs: &[String],
+        outputs: &[String],
+    ) -> Result<(), SmtlibError> {
+        for stmt in module.stmts() {
+            match stmt {
+                Stmt::Assert(term) => {
+                    let scope = Scope::new(prefix, inputs, outputs);
+                    let term = term.render(&mut scope.with(self));
+                    self.body.push(format!("(assert {term})"));
+                }
+                Stmt::Comment(comment) => {
+                    for line in comment.lines() {
+                        self.body.push(format!("; {line}"));
+                    }
+                }
+                Stmt::AssumeDeterministic(slot) => {
+                    let var = self.var(prefix, inputs, outputs, *slot);
+                    self.assumed.push(var);
+                }
+                Stmt::Call {
+                    callee,
+                    inputs: args,
+                    outputs: rets,
+                } => {
+                    let scope = Scope::new(prefix, inputs, outputs);
+                    let args: Vec<_> =
+                        args.iter().map(|arg| arg.render(&mut scope.with(self))).collect();
+                    let rets: Vec<_> =
+                        rets.iter().map(|ret| self.var(prefix, inputs, outputs, *ret)).collect();
+                    self.call(callee, prefix, &args, &rets)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn call(
+        &mut self,
+        callee: &str,
+        prefix: &str,
+        args: &[String],
+        rets: &[String],
+    ) -> Result<(), SmtlibError> {
+        let arity_err = |inputs, outputs| {
+            SmtlibError::ArityMismatch(callee.to_owned(), args.len(), rets.len(), inputs, outputs)
+        };
+        let output = self.output;
+        if let Some(table) = self.tables.iter().find(|table| table.name() == callee) {
+            if table.inputs() != args.len() || !rets.is_empty() {
+                return Err(arity_err(table.inputs(), 0));
+            }
+            let n = self.instances;
+            self.instances += 1;
+            let prefix = format!("{prefix}.{callee}#{n}");
+            // Bound to fresh variables since every row mentions the arguments.
+            let inputs: Vec<_> = args
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| {
+                    let var = self.declare(format!("{prefix}.in{i}"));
+                    self.body.push(format!("(assert (= {var} {arg}))"));
+                    var
+                })
+                .collect();
+            self.body.push(format!("(assert {})", table_rows(&inputs, table.rows())));
+            return Ok(());
+        }
+        if let Some(shuffle) = self.shuffles.iter().find(|shuffle| shuffle.name() == callee) {
+            if shuffle.inputs() != args.len() || !rets.is_empty() {
+                return Err(arity_err(shuffle.inputs(), 0));
+            }
+            let n = self.instances;
+            self.instances += 1;
+            let prefix = format!("{prefix}.{callee}#{n}");
+            // Bound to fresh variables since every power sum mentions the
+            // arguments.
+            let inputs: Vec<_> = args
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| {
+                    let var = self.declare(format!("{prefix}.in{i}"));
+                    self.body.push(format!("(assert (= {var} {arg}))"));
+                    var
+                })
+                .collect();
+            let prime = self.output.prime();
+            self.body.extend(power_sums(&inputs, shuffle, prime.value()));
+            return Ok(());
+        }
+        match output.module(callee) {
+            Some(module) => {
+                if module.inputs() != args.len() || module.outputs() != rets.len() {
+                    return Err(arity_err(module.inputs(), module.outputs()));
+                }
+                let n = self.instances;
+                self.instances += 1;
+                let prefix = format!("{prefix}.{callee}#{n}");
+                // Bind the arguments to fresh variables to avoid duplicating
+                // the expressions inside the callee.
+                let inputs: Vec<_> = args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| {
+                        let var = self.declare(format!("{prefix}.in{i}"));
+                        self.body.push(format!("(assert (= {var} {arg}))"));
+                        var
+                    })
+                    .collect();
+                self.instantiate(module, &prefix, &inputs, rets)
+            }
+            None => {
+                let arity =
+                    *self.externs.entry(callee.to_owned()).or_insert((args.len(), rets.len()));
+                if arity != (args.len(), rets.len()) {
+                    return Err(arity_err(arity.0, arity.1));
+                }
+                let app = |name: String| {
+                    if args.is_empty() {
+                        symbol(&name)
+                    } else {
+                        format!("({} {})", symbol(&name), args.join(" "))
+                    }
+                };
+                if rets.is_empty() {
+                    self.body.push(format!("(assert {})", app(callee.to_owned())));
+                }
+                for (o, ret) in rets.iter().enumerate() {
+                    let app = app(format!("{callee}.{o}"));
+                    self.body.push(format!("(assert (= {ret} {app}))"));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for DeterminismQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "(set-logic QF_UFFF)")?;
+        writeln!(f, "(set-option :produce-models true)")?;
+        writeln!(f, "(define-sort F () (_ FiniteField {}))", self.prime)?;
+        if !self.externs.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "; Modules assumed to be deterministic")?;
+        }
+        for (name, (inputs, outputs)) in &self.externs {
+            let domain = vec!["F"; *inputs].join(" ");
+            if *outputs == 0 {
+                writeln!(f, "(declare-fun {} ({domain}) Bool)", symbol(name))?;
+            }
+            for o in 0..*outputs {
+                writeln!(
+                    f,
+                    "(declare-fun {} ({domain}) F)",
+                    symbol(&format!("{name}.{o}"))
+                )?;
+            }
+        }
+        writeln!(f)?;
+        for decl in &self.decls {
+            writeln!(f, "(declare-const {decl} F)")?;
+        }
+        writeln!(f)?;
+        for line in &self.body {
+            writeln!(f, "{line}")?;
+        }
+        if !self.assumed.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "; Values assumed to be deterministic")?;
+        }
+        for (lhs, rhs) in &self.assumed {
+            writeln!(f, "(assert (= {lhs} {rhs}))")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "; Some output differs between the two witnesses")?;
+        match self.outputs.as_slice() {
+            [] => writeln!(f, "(assert false)")?,
+            [(lhs, rhs)] => writeln!(f, "(assert (distinct {lhs} {rhs}))")?,
+            outputs => {
+                writeln!(f, "(assert (or")?;
+                for (lhs, rhs) in outputs {
+                    writeln!(f, "  (distinct {lhs} {rhs})")?;
+                }
+                writeln!(f, "))")?;
+            }
+        }
+        writeln!(f, "(check-sat)")?;
+        writeln!(f, "(get-model)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::bn256::Fr;
+    use haloumi::core::{
+        cmp::CmpOp,
+        felt::{Felt, Prime},
+        slot::{arg::ArgNo, output::OutputId, Slot},
+    };
+    use haloumi_backend::codegen::Codegen as _;
+    use haloumi_lowering::Lowering as _;
+
+    use super::*;
+    use crate::smtlib::codegen::{SmtlibCodegen, SmtlibModuleLowering, SmtlibParams, SmtlibState};
+
+    fn arg(i: usize) -> Term {
+        Term::var(ArgNo::from(i).into())
+    }
+
+    fn out(i: usize) -> Term {
+        Term::var(OutputId::from(i).into())
+    }
+
+    /// Lowers the modules defined by `build` and encodes the query of `main`.
+    fn query(build: impl FnOnce(&dyn Fn(&str, usize, usize) -> SmtlibModuleLowering)) -> String {
+        query_with_tables(&[], &[], build)
+    }
+
+    fn query_with_tables(
+        tables: &[TableModule],
+        shuffles: &[ShuffleModule],
+        build: impl FnOnce(&dyn Fn(&str, usize, usize) -> SmtlibModuleLowering),
+    ) -> String {
+        let state = SmtlibState::from(SmtlibParams::new("main"));
+        let codegen = SmtlibCodegen::initialize(&state);
+        codegen.set_prime_field(Prime::new::<Fr>()).unwrap();
+        build(&|name, inputs, outputs| codegen.define_function(name, inputs, outputs).unwrap());
+        let output = codegen.generate_output().unwrap();
+        DeterminismQuery::new(&output, tables, shuffles).unwrap().to_string()
+    }
+
+    #[test]
+    fn golden_query() {
+        let query = query(|define| {
+            let main = define("main", 2, 1);
+            let double = define("double", 1, 1);
+            let fixed = Term::var(Slot::fixed_abs(0, 0));
+            let advice = Term::var(Slot::advice_abs(0, 0));
+            main.generate_constraint(CmpOp::Eq, &advice, &Term::mul(&arg(0), &fixed))
+                .unwrap();
+            main.generate_call("double", &[advice], &[OutputId::from(0).into()]).unwrap();
+            main.generate_call("Spread", &[arg(1)], &[Slot::advice_abs(1, 0)]).unwrap();
+            double
+                .generate_constraint(CmpOp::Eq, &out(0), &Term::add(&arg(0), &arg(0)))
+                .unwrap();
+        });
+        assert_eq!(
+            query,
+            "\
+(set-logic QF_UFFF)
+(set-option :produce-models true)
+(define-sort F () (_ FiniteField 21888242871839275222246405745257275088548364400416034343698204186575808495617))
+
+; Modules assumed to be deterministic
+(declare-fun |Spread.0| (F) F)
+
+(declare-const |in0| F)
+(declare-const |in1| F)
+(declare-const |w1.out0| F)
+(declare-const |w1.adv[0,0]| F)
+(declare-const |fix[0,0]| F)
+(declare-const |w1.double#0.in0| F)
+(declare-const |w1.adv[1,0]| F)
+(declare-const |w2.out0| F)
+(declare-const |w2.adv[0,0]| F)
+(declare-const |w2.double#0.in0| F)
+(declare-const |w2.adv[1,0]| F)
+
+; Witness 1
+(assert (= |w1.adv[0,0]| (ff.mul |in0| |fix[0,0]|)))
+(assert (= |w1.double#0.in0| |w1.adv[0,0]|))
+(assert (= |w1.out0| (ff.add |w1.double#0.in0| |w1.double#0.in0|)))
+(assert (= |w1.adv[1,0]| (|Spread.0| |in1|)))
+; Witness 2
+(assert (= |w2.adv[0,0]| (ff.mul |in0| |fix[0,0]|)))
+(assert (= |w2.double#0.in0| |w2.adv[0,0]|))
+(assert (= |w2.out0| (ff.add |w2.double#0.in0| |w2.double#0.in0|)))
+(assert (= |w2.adv[1,0]| (|Spread.0| |in1|)))
+
+; Some output differs between the two witnesses
+(assert (distinct |w1.out0| |w2.out0|))
+(check-sat)
+(get-model)
+"
+        );
+    }
+
+    #[test]
+    fn calls_without_outputs_are_predicates() {
+        let query = query(|define| {
+            let main = define("main", 2, 1);
+            main.generate_call("Range", &[arg(0), arg(1)], &[]).unwrap();
+            main.generate_call("Check", &[], &[]).unwrap();
+            main.generate_constraint(CmpOp::Eq, &out(0), &arg(0)).unwrap();
+        });
+        assert_eq!(
+            query,
+            "\
+(set-logic QF_UFFF)
+(set-option :produce-models true)
+(define-sort F () (_ FiniteField 21888242871839275222246405745257275088548364400416034343698204186575808495617))
+
+; Modules assumed to be deterministic
+(declare-fun |Check| () Bool)
+(declare-fun |Range| (F F) Bool)
+
+(declare-const |in0| F)
+(declare-const |in1| F)
+(declare-const |w1.out0| F)
+(declare-const |w2.out0| F)
+
+; Witness 1
+(assert (|Range| |in0| |in1|))
+(assert |Check|)
+(assert (= |w1.out0| |in0|))
+; Witness 2
+(assert (|Range| |in0| |in1|))
+(assert |Check|)
+(assert (= |w2.out0| |in0|))
+
+; Some output differs between the two witnesses
+(assert (distinct |w1.out0| |w2.out0|))
+(check-sat)
+(get-model)
+"
+        );
+    }
+
+    #[test]
+    fn calls_to_table_modules_assert_a_row_of_the_table() {
+        let rows = [[0u64, 1], [1, 2], [2, 4]]
+            .iter()
+            .map(|row| row.iter().copied().map(BigUint::from).collect())
+            .collect();
+        let table = TableModule::new("Pow2", 2, rows);
+        let query = query_with_tables(&[table], &[], |define| {
+            let main = define("main", 2, 0);
+            main.generate_call("Pow2", &[arg(0), Term::add(&arg(1), &arg(1))], &[]).unwrap();
+        });
+        assert!(!query.contains("|Pow2|"), "{query}");
+        assert!(query.contains("(declare-const |w1.Pow2#0.in1| F)"));
+        assert!(query.contains(
+            "(assert (= |w1.Pow2#0.in1| (ff.add |in1| |in1|)))\n\
+             (assert (or \
+             (and (= |w1.Pow2#0.in0| (as ff0 F)) (= |w1.Pow2#0.in1| (as ff1 F))) \
+             (and (= |w1.Pow2#0.in0| (as ff1 F)) (= |w1.Pow2#0.in1| (as ff2 F))) \
+             (and (= |w1.Pow2#0.in0| (as ff2 F)) (= |w1.Pow2#0.in1| (as ff4 F)))))\n"
+        ));
+    }
+
+    #[test]
+    fn calls_to_shuffle_modules_assert_equal_power_sums() {
+        let shuffle = ShuffleModule::new(1, 2);
+        let query = query_with_tables(&[], &[shuffle], |define| {
+            let main = define("main", 2, 0);
+            main.generate_call("MultisetEq_1x2", &[arg(0), arg(1), arg(1), arg(0)], &[])
+                .unwrap();
+        });
+        assert!(!query.contains("|MultisetEq_1x2|"), "{query}");
+        assert!(query.contains(
+            "(assert (= |w1.MultisetEq_1x2#0.in3| |in0|))\n\
+             (assert (= (ff.add |w1.MultisetEq_1x2#0.in0| |w1.MultisetEq_1x2#0.in1|) \
+             (ff.add |w1.MultisetEq_1x2#0.in2| |w1.MultisetEq_1x2#0.in3|)))\n\
+             (assert (= (ff.add \
+             (ff.mul |w1.MultisetEq_1x2#0.in0| |w1.MultisetEq_1x2#0.in0|) \
+             (ff.mul |w1.MultisetEq_1x2#0.in1| |w1.MultisetEq_1x2#0.in1|)) \
+             (ff.add \
+             (ff.mul |w1.MultisetEq_1x2#0.in2| |w1.MultisetEq_1x2#0.in2|) \
+             (ff.mul |w1.MultisetEq_1x2#0.in3| |w1.MultisetEq_1x2#0.in3|))))\n"
+        ));
+    }
+
+    #[test]
+    fn comparisons_use_canonical_bits() {
+        let query = query(|define| {
+            let main = define("main", 1, 1);
+            let bound = Term::constant(Felt::new(Fr::from(256)));
+            main.generate_constraint(CmpOp::Lt, &arg(0), &bound).unwrap();
+            main.generate_constraint(CmpOp::Le, &arg(0), &out(0)).unwrap();
+        });
+        let width = Prime::new::<Fr>().value().bits();
+        // The input is shared by both witnesses so it is decomposed once, and
+        // the output once per witness.
+        let decls = query.lines().filter(|line| line.starts_with("(declare-const |bits#")).count();
+        assert_eq!(decls as u64, 3 * width);
+        assert_eq!(query.matches("(ff.bitsum ").count(), 3);
+        assert!(query.contains("(assert (= |in0| (ff.bitsum |bits#0.0| |bits#0.1| "));
+        assert!(!query.contains("field_lt"));
+        // `in0 < 256` folds the bits of the constant and only mentions the
+        // bits of the input that have to be zero.
+        let lt = query
+            .lines()
+            .find(|line| line.starts_with("(assert (and (not (= |bits#0.253| (as ff1 F)))"))
+            .expect("missing range check");
+        assert!(!lt.contains("|bits#0.7|"));
+        assert!(lt.contains("|bits#0.8|"));
+    }
+}
@@ -0,0 +1,612 @@
+//! Running a solver on the Picus outputs of an extraction.
+//!
+//! Each selected output is checked by its own solver process with a time
+//! limit. The verdict is read from what the solver prints, so any solver that
+//! reports `safe`, `unsafe` or `unknown` can be used in place of Picus.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{BufWriter, Read},
+    num::NonZeroUsize,
+    path::Path,
+    process::{Child, Command, ExitStatus, Stdio},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context as _, Result};
+use counterexample::{Assignments, DecodedCounterexample};
+use mdnt_extractor_core::taxonomy::NAME_COMPONENTS;
+use serde::Serialize;
+
+use crate::{
+    diff::{find_harnesses, PICUS_OUTPUT},
+    picus::sidecar::PicusSidecar,
+    query::Query,
+};
+
+pub mod counterexample;
+
+/// Solver command used if none is given.
+pub const DEFAULT_SOLVER: &str = "picus {args} {file}";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Command that runs the solver on one output.
+///
+/// The command is split on whitespace and the following placeholders are
+/// replaced in its arguments:
+///
+/// - `{file}`: the path of the Picus file.
+/// - `{args}`: the arguments listed in the `picus.json` of the output. It has
+///   to be a whole argument and expands to as many arguments as listed.
+/// - `{entrypoint}`: the name of the top-level module.
+/// - `{time_limit}`: the time limit in seconds.
+#[derive(Debug, Clone)]
+pub struct SolverCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl FromStr for SolverCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words = s.split_whitespace().map(ToOwned::to_owned);
+        let Some(program) = words.next() else {
+            bail!("The solver command is empty");
+        };
+        Ok(Self {
+            program,
+            args: words.collect(),
+        })
+    }
+}
+
+impl SolverCommand {
+    fn args(
+        &self,
+        file: &Path,
+        entrypoint: &str,
+        picus_args: &[String],
+        time_limit: Duration,
+    ) -> Vec<String> {
+        let mut args = vec![];
+        for arg in &self.args {
+            if arg == "{args}" {
+                args.extend_from_slice(picus_args);
+                continue;
+            }
+            args.push(
+                arg.replace("{file}", &file.to_string_lossy())
+                    .replace("{entrypoint}", entrypoint)
+                    .replace("{time_limit}", &time_limit.as_secs().to_string()),
+            );
+        }
+        args
+    }
+}
+
+/// Outcome of running the solver on an output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Safe,
+    Unsafe,
+    Unknown,
+    Timeout,
+    Error,
+}
+
+impl Verdict {
+    const ALL: [Verdict; 5] = [
+        Verdict::Safe,
+        Verdict::Unsafe,
+        Verdict::Unknown,
+        Verdict::Timeout,
+        Verdict::Error,
+    ];
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Safe => write!(f, "safe"),
+            Verdict::Unsafe => write!(f, "unsafe"),
+            Verdict::Unknown => write!(f, "unknown"),
+            Verdict::Timeout => write!(f, "timeout"),
+            Verdict::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Reads the verdict from the last line of the solver output that has one.
+fn parse_verdict(output: &str) -> Option<Verdict> {
+    output.lines().rev().find_map(|line| {
+        let line = line.to_lowercase();
+        let has_word = |words: &[&str]| {
+            line.split(|c: char| !c.is_alphanumeric() && c != '-')
+                .any(|word| words.contains(&word))
+        };
+        if has_word(&["unsafe", "underconstrained", "under-constrained"]) {
+            Some(Verdict::Unsafe)
+        } else if has_word(&["unknown"]) || line.contains("cannot determine") {
+            Some(Verdict::Unknown)
+        } else if has_word(&["safe"]) || line.contains("properly constrained") {
+            Some(Verdict::Safe)
+        } else {
+            None
+        }
+    })
+}
+
+/// Reads the verdict of a solver that exited with `status`.
+///
+/// A solver that fails can still print something that looks like a verdict,
+/// so only an unsafe verdict is trusted from a solver that exits with an error,
+/// as some solvers report it through their exit code.
+fn parse_verdict_with_status(output: &str, status: ExitStatus) -> Result<Verdict, String> {
+    match parse_verdict(output) {
+        Some(verdict) if status.success() || verdict == Verdict::Unsafe => Ok(verdict),
+        _ if !status.success() => Err(format!(
+            "The solver exited with {status}: {}",
+            output.lines().last().unwrap_or_default()
+        )),
+        _ => Err(format!(
+            "The solver output has no verdict: {}",
+            output.lines().last().unwrap_or_default()
+        )),
+    }
+}
+
+/// Returns the name of the harness of an output, without the directory of the
+/// project run that contains it.
+fn harness_name(name: &str) -> &str {
+    name.rmatch_indices('/')
+        .nth(NAME_COMPONENTS - 1)
+        .map_or(name, |(idx, _)| &name[idx + 1..])
+}
+
+/// Returns the part of the solver output that starts at the counterexample.
+fn parse_counterexample(output: &str) -> Option<String> {
+    let lines: Vec<_> = output.lines().collect();
+    let start = lines.iter().position(|line| {
+        let line = line.to_lowercase();
+        line.contains("counterexample") || line.contains("counter-example")
+    })?;
+    Some(lines[start..].join("\n"))
+}
+
+/// Result of verifying one harness.
+#[derive(Debug, Serialize)]
+pub struct HarnessResult {
+    verdict: Verdict,
+    time_secs: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    counterexample: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decoded: Option<DecodedCounterexample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl HarnessResult {
+    pub fn verdict(&self) -> Verdict {
+        self.verdict
+    }
+
+    /// Solver output from the counterexample on, if the output is unsafe.
+    pub fn counterexample(&self) -> Option<&str> {
+        self.counterexample.as_deref()
+    }
+
+    /// Counterexample in terms of the harness types, if it could be decoded.
+    pub fn decoded(&self) -> Option<&DecodedCounterexample> {
+        self.decoded.as_ref()
+    }
+}
+
+/// Results of a verification, by harness name.
+#[derive(Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct Report {
+    harnesses: BTreeMap<String, HarnessResult>,
+}
+
+impl Report {
+    pub fn get(&self, name: &str) -> Option<&HarnessResult> {
+        self.harnesses.get(name)
+    }
+
+    /// Returns true if an output is unsafe or the solver failed on it.
+    pub fn has_failures(&self) -> bool {
+        self.harnesses
+            .values()
+            .any(|result| matches!(result.verdict, Verdict::Unsafe | Verdict::Error))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let output_file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(output_file, self)?;
+        log::info!("Saved verification results in {}", path.display());
+        Ok(())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.harnesses.keys().map(String::len).max().unwrap_or_default();
+        for (name, result) in &self.harnesses {
+            writeln!(
+                f,
+                "{name:width$}  {:7}  {:>8.1}s",
+                result.verdict.to_string(),
+                result.time_secs
+            )?;
+            for difference in result.decoded.iter().flat_map(|d| d.differences()) {
+                writeln!(f, "    {difference}")?;
+            }
+        }
+        let counts = Verdict::ALL
+            .iter()
+            .map(|verdict| {
+                let count = self.harnesses.values().filter(|r| r.verdict == *verdict).count();
+                format!("{count} {verdict}")
+            })
+            .collect::<Vec<_>>();
+        writeln!(f, "{}", counts.join(", "))
+    }
+}
+
+/// Runs a solver on the outputs of an extraction.
+pub struct Verifier {
+    solver: SolverCommand,
+    time_limit: Duration,
+    jobs: NonZeroUsize,
+}
+
+impl Verifier {
+    pub fn new(solver: SolverCommand, time_limit: Duration, jobs: NonZeroUsize) -> Self {
+        Self {
+            solver,
+            time_limit,
+            jobs,
+        }
+    }
+
+    /// Runs the solver on the Picus outputs in `dir` selected by the
+    /// selection expression, or on all of them if there is none.
+    pub fn verify(&self, dir: &Path, select: Option<&str>) -> Result<Report> {
+        let query = select
+            .map(|select| {
+                select
+                    .parse::<Query>()
+                    .with_context(|| format!("Failed to parse selection '{select}'"))
+            })
+            .transpose()?;
+        let harnesses: Vec<_> = find_harnesses(dir)?
+            .into_iter()
+            .filter(|(name, dir)| {
+                dir.join(PICUS_OUTPUT).is_file()
+                    && query.as_ref().is_none_or(|query| query.matches(harness_name(name)))
+            })
+            .collect();
+        if harnesses.is_empty() {
+            bail!("No Picus outputs selected in {}", dir.display());
+        }
+
+        let jobs = self.jobs.get().min(harnesses.len());
+        log::info!("Verifying {} outputs with {jobs} jobs", harnesses.len());
+        let next = AtomicUsize::new(0);
+        let report = Mutex::new(Report::default());
+        thread::scope(|s| {
+            for _ in 0..jobs {
+                s.spawn(|| {
+                    while let Some((name, dir)) =
+                        harnesses.get(next.fetch_add(1, Ordering::Relaxed))
+                    {
+                        let result = self.verify_one(name, dir);
+                        report
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .harnesses
+                            .insert(name.clone(), result);
+                    }
+                });
+            }
+        });
+        Ok(report.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn verify_one(&self, name: &str, dir: &Path) -> HarnessResult {
+        log::info!("Verifying {name}");
+        let start = Instant::now();
+        let outcome = PicusSidecar::read(dir)
+            .with_context(|| format!("Failed to read the picus.json of {name}"))
+            .and_then(|sidecar| {
+                let output = self.run_solver(name, dir, sidecar.as_ref())?;
+                Ok((output, sidecar))
+            });
+        let mut result = HarnessResult {
+            verdict: Verdict::Error,
+            time_secs: start.elapsed().as_secs_f64(),
+            counterexample: None,
+            decoded: None,
+            error: None,
+        };
+        match outcome {
+            Ok((None, _)) => result.verdict = Verdict::Timeout,
+            Ok((Some((output, status)), sidecar)) => {
+                match parse_verdict_with_status(&output, status) {
+                    Ok(verdict) => {
+                        result.verdict = verdict;
+                        if verdict == Verdict::Unsafe {
+                            result.counterexample = parse_counterexample(&output);
+                            result.decoded =
+                                decode(result.counterexample.as_deref(), sidecar.as_ref());
+                        }
+                    }
+                    Err(err) => result.error = Some(err),
+                }
+            }
+            Err(err) => result.error = Some(format!("{err:#}")),
+        }
+        match &result.error {
+            Some(err) => log::error!("Failed to verify {name}: {err}"),
+            None => log::info!("{name} is {} ({:.1}s)", result.verdict, result.time_secs),
+        }
+        result
+    }
+
+    /// Runs the solver on the output in `dir` and returns what it printed and
+    /// how it exited, or `None` if it ran out of time.
+    fn run_solver(
+        &self,
+        name: &str,
+        dir: &Path,
+        sidecar: Option<&PicusSidecar>,
+    ) -> Result<Option<(String, ExitStatus)>> {
+        let file = dir.join(PICUS_OUTPUT);
+        if sidecar.is_none() {
+            log::warn!("{name} has no picus.json, the solver gets no extra arguments");
+        }
+        let args = self.solver.args(
+            &file,
+            sidecar.map_or(name, PicusSidecar::entrypoint),
+            sidecar.map(PicusSidecar::args).unwrap_or_default(),
+            self.time_limit,
+        );
+        log::debug!("Running {} {}", self.solver.program, args.join(" "));
+        let mut command = Command::new(&self.solver.program);
+        command
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        // The solver gets its own process group so that the processes it
+        // spawns, like the SMT solvers run by Picus, are killed with it.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.solver.program))?;
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+
+        let deadline = Instant::now() + self.time_limit;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                kill(&mut child)?;
+                child.wait()?;
+                // The readers are not joined in case a process escaped the
+                // group of the solver and still holds the pipes open.
+                return Ok(None);
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+        let join = |reader: thread::JoinHandle<String>| reader.join().unwrap_or_default();
+        Ok(Some((join(stdout) + &join(stderr), status)))
+    }
+}
+
+/// Decodes the counterexample with the layout recorded in `picus.json`.
+fn decode(
+    counterexample: Option<&str>,
+    sidecar: Option<&PicusSidecar>,
+) -> Option<DecodedCounterexample> {
+    let assignments = Assignments::parse(counterexample?);
+    if assignments.is_empty() {
+        return None;
+    }
+    Some(DecodedCounterexample::decode(
+        sidecar?.layout()?,
+        &assignments,
+    ))
+}
+
+/// Kills the solver and the processes in its group.
+fn kill(child: &mut Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        // The group of the solver has the id of its process.
+        let group = -(child.id() as libc::pid_t);
+        // SAFETY: `kill` has no memory safety requirements.
+        if unsafe { libc::kill(group, libc::SIGKILL) } == 0 {
+            return Ok(());
+        }
+    }
+    child.kill()
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = vec![];
+        if let Some(mut pipe) = pipe {
+            // A partial output is still worth looking at.
+            let _ = pipe.read_to_end(&mut output);
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdicts() {
+        assert_eq!(
+            parse_verdict("# weak uniqueness: safe."),
+            Some(Verdict::Safe)
+        );
+        assert_eq!(
+            parse_verdict("The circuit is underconstrained\nCounterexample:\n  x = 1"),
+            Some(Verdict::Unsafe)
+        );
+        assert_eq!(parse_verdict("result: UNKNOWN"), Some(Verdict::Unknown));
+        assert_eq!(parse_verdict("loading file\nsafety check"), None);
+        assert_eq!(
+            parse_counterexample("unsafe\nCounter-example:\n  x = 1\n  y = 2").as_deref(),
+            Some("Counter-example:\n  x = 1\n  y = 2")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verdicts_of_failed_solvers() {
+        use std::os::unix::process::ExitStatusExt as _;
+
+        let (success, failure) = (ExitStatus::from_raw(0), ExitStatus::from_raw(3 << 8));
+        assert_eq!(
+            parse_verdict_with_status("result: unknown", success),
+            Ok(Verdict::Unknown)
+        );
+        assert_eq!(
+            parse_verdict_with_status("error: unknown option --foo", failure),
+            Err("The solver exited with exit status: 3: error: unknown option --foo".to_owned())
+        );
+        assert!(parse_verdict_with_status("safe", failure).is_err());
+        assert_eq!(
+            parse_verdict_with_status("underconstrained", failure),
+            Ok(Verdict::Unsafe)
+        );
+        assert!(parse_verdict_with_status("done", success).is_err());
+    }
+
+    #[test]
+    fn names_without_run_directory() {
+        assert_eq!(
+            harness_name("hash/hash_1/sha256/byte"),
+            "hash/hash_1/sha256/byte"
+        );
+        assert_eq!(
+            harness_name("native/hash/hash_1/sha256/byte"),
+            "hash/hash_1/sha256/byte"
+        );
+        assert_eq!(
+            harness_name("runs/bls/field/add/native/native@bls12-381"),
+            "field/add/native/native@bls12-381"
+        );
+    }
+
+    #[test]
+    fn solver_args() {
+        let solver: SolverCommand =
+            "picus --timeout {time_limit} {args} --main {entrypoint} {file}"
+                .parse()
+                .unwrap();
+        let args = solver.args(
+            Path::new("out/output.picus"),
+            "a/b/c/d",
+            &[
+                "--assume-deterministic".to_owned(),
+                "Spread,Unspread".to_owned(),
+            ],
+            Duration::from_secs(60),
+        );
+        assert_eq!(solver.program, "picus");
+        assert_eq!(
+            args,
+            [
+                "--timeout",
+                "60",
+                "--assume-deterministic",
+                "Spread,Unspread",
+                "--main",
+                "a/b/c/d",
+                "out/output.picus"
+            ]
+        );
+        assert!("".parse::<SolverCommand>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn solver_time_limit() {
+        let dir = std::env::temp_dir().join(format!("verify-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(PICUS_OUTPUT), "").unwrap();
+        let run = |solver: &str, time_limit| {
+            Verifier::new(solver.parse().unwrap(), time_limit, NonZeroUsize::MIN)
+                .verify_one("a/b/c/d", &dir)
+                .verdict()
+        };
+        assert_eq!(run("echo safe", Duration::from_secs(10)), Verdict::Safe);
+        assert_eq!(
+            run("sleep 10", Duration::from_millis(200)),
+            Verdict::Timeout
+        );
+        assert_eq!(run("true", Duration::from_secs(10)), Verdict::Error);
+        assert_eq!(
+            run("echo unknown", Duration::from_secs(10)),
+            Verdict::Unknown
+        );
+        let script = dir.join("crash.sh");
+        std::fs::write(&script, "echo unknown >&2\nexit 1\n").unwrap();
+        let solver = format!("sh {}", script.display());
+        assert_eq!(run(&solver, Duration::from_secs(10)), Verdict::Error);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn timeout_kills_solver_subprocesses() {
+        let dir = std::env::temp_dir().join(format!("verify-group-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(PICUS_OUTPUT), "").unwrap();
+        let (script, pid_file) = (dir.join("solver.sh"), dir.join("pid"));
+        std::fs::write(&script, "sleep 30 &\necho $! > \"$1\"\nwait\n").unwrap();
+        let solver = format!("sh {} {}", script.display(), pid_file.display());
+        let verdict = Verifier::new(
+            solver.parse().unwrap(),
+            Duration::from_millis(500),
+            NonZeroUsize::MIN,
+        )
+        .verify_one("a/b/c/d", &dir)
+        .verdict();
+        assert_eq!(verdict, Verdict::Timeout);
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        // Killed processes that were not reaped yet are zombies.
+        let running = || {
+            std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()))
+                .is_ok_and(|stat| !stat.contains(") Z "))
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while running() && Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(!running());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+//! Decoding of the counterexamples reported by the solver.
+//!
+//! A counterexample gives two assignments of the variables of the Picus file
+//! that agree on the inputs and differ on some output. The top-level module
+//! names its inputs `in_<n>` and its outputs `out_<n>`, where `n` is the
+//! position of the cell in the IO of the harness. The [`IoLayout`] recorded in
+//! `picus.json` turns those positions back into values of the harness types.
+
+use std::{collections::BTreeMap, fmt, sync::LazyLock};
+
+use mdnt_extractor_core::cells::layout::{CellLayout, IoLayout};
+use regex::Regex;
+use serde::Serialize;
+
+/// Matches assignments like `in_0: 1`, `main.out_2 = 0x2a` or `("out_2" . 42)`.
+static ASSIGNMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\b(in|out)_(\d+)"?\s*(?::|=|->|\.)\s*(0x[0-9a-fA-F]+|\d+)"#).unwrap()
+});
+
+/// Values of the IO cells in each of the two assignments.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Assignments {
+    inputs: [BTreeMap<usize, String>; 2],
+    outputs: [BTreeMap<usize, String>; 2],
+}
+
+impl Assignments {
+    /// Reads the values of the IO cells from the counterexample.
+    ///
+    /// The values that follow a line mentioning the first or the second
+    /// solution belong to that assignment. The rest, usually the inputs, are
+    /// shared by both.
+    pub fn parse(counterexample: &str) -> Self {
+        let mut assignments = Self::default();
+        let mut current: Option<usize> = None;
+        for line in counterexample.lines() {
+            // Only the text before the first assignment can name a section.
+            let header = ASSIGNMENT
+                .find(line)
+                .map_or(line, |assignment| &line[..assignment.start()])
+                .to_lowercase();
+            if header.contains("first") {
+                current = Some(0);
+            } else if header.contains("second") {
+                current = Some(1);
+            } else if header.contains("input") {
+                current = None;
+            }
+            for captures in ASSIGNMENT.captures_iter(line) {
+                let cells = match &captures[1] {
+                    "in" => &mut assignments.inputs,
+                    _ => &mut assignments.outputs,
+                };
+                let Ok(index) = captures[2].parse::<usize>() else {
+                    continue;
+                };
+                let value = &captures[3];
+                for assignment in current.map_or(0..2, |n| n..n + 1) {
+                    cells[assignment].insert(index, value.to_owned());
+                }
+            }
+        }
+        assignments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.iter().chain(&self.outputs).all(BTreeMap::is_empty)
+    }
+}
+
+/// Value of a harness type rebuilt from the values of its cells. Cells missing
+/// from the counterexample have no value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum TypedValue {
+    Cell(Option<String>),
+    Cells(Vec<Option<String>>),
+    Array(Vec<TypedValue>),
+    Tuple(Vec<TypedValue>),
+}
+
+impl TypedValue {
+    fn build(layout: &CellLayout, cells: &mut impl Iterator<Item = Option<String>>) -> Self {
+        match layout {
+            CellLayout::Cells { size: 1, .. } => Self::Cell(cells.next().flatten()),
+            CellLayout::Cells { size, .. } => {
+                Self::Cells((0..*size).map(|_| cells.next().flatten()).collect())
+            }
+            CellLayout::Array { elem, len } => {
+                Self::Array((0..*len).map(|_| Self::build(elem, cells)).collect())
+            }
+            CellLayout::Tuple(elems) => {
+                Self::Tuple(elems.iter().map(|elem| Self::build(elem, cells)).collect())
+            }
+        }
+    }
+
+    fn from_cells(layout: &CellLayout, values: &BTreeMap<usize, String>) -> Self {
+        Self::build(layout, &mut (0..).map(|index| values.get(&index).cloned()))
+    }
+}
+
+impl fmt::Display for TypedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list<T: fmt::Display>(
+            f: &mut fmt::Formatter<'_>,
+            items: &[T],
+            (open, close): (&str, &str),
+        ) -> fmt::Result {
+            write!(f, "{open}")?;
+            for (n, item) in items.iter().enumerate() {
+                if n > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{item}")?;
+            }
+            write!(f, "{close}")
+        }
+
+        match self {
+            Self::Cell(value) => write!(f, "{}", value.as_deref().unwrap_or("?")),
+            Self::Cells(values) => list(
+                f,
+                &values.iter().map(|v| Self::Cell(v.clone())).collect::<Vec<_>>(),
+                ("{", "}"),
+            ),
+            Self::Array(elems) => list(f, elems, ("[", "]")),
+            Self::Tuple(elems) => list(f, elems, ("(", ")")),
+        }
+    }
+}
+
+/// A value of the harness IO on which the two assignments disagree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Difference {
+    path: String,
+    ty: String,
+    values: [TypedValue; 2],
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} != {}",
+            self.path, self.ty, self.values[0], self.values[1]
+        )
+    }
+}
+
+/// Collects the values of the layout that are known in both assignments and
+/// differ.
+fn differences(
+    layout: &CellLayout,
+    path: String,
+    values: [&TypedValue; 2],
+    found: &mut Vec<Difference>,
+) {
+    match (layout, values) {
+        (CellLayout::Cells { ty, .. }, [first, second]) => {
+            let known = |value: &TypedValue| match value {
+                TypedValue::Cell(value) => value.is_some(),
+                TypedValue::Cells(values) => values.iter().all(Option::is_some),
+                _ => false,
+            };
+            if known(first) && known(second) && first != second {
+                found.push(Difference {
+                    path,
+                    ty: ty.clone(),
+                    values: [first.clone(), second.clone()],
+                });
+            }
+        }
+        (CellLayout::Array { elem, .. }, [TypedValue::Array(first), TypedValue::Array(second)]) => {
+            for (n, (first, second)) in first.iter().zip(second).enumerate() {
+                differences(elem, format!("{path}[{n}]"), [first, second], found);
+            }
+        }
+        (CellLayout::Tuple(elems), [TypedValue::Tuple(first), TypedValue::Tuple(second)]) => {
+            for (n, (elem, (first, second))) in
+                elems.iter().zip(first.iter().zip(second)).enumerate()
+            {
+                differences(elem, format!("{path}.{n}"), [first, second], found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The two assignments of a counterexample as values of the harness types.
+#[derive(Debug, Serialize)]
+pub struct DecodedCounterexample {
+    inputs: [TypedValue; 2],
+    outputs: [TypedValue; 2],
+    differences: Vec<Difference>,
+}
+
+impl DecodedCounterexample {
+    pub fn decode(layout: &IoLayout, assignments: &Assignments) -> Self {
+        let typed = |layout, values: &[BTreeMap<usize, String>; 2]| {
+            values.each_ref().map(|values| TypedValue::from_cells(layout, values))
+        };
+        let inputs = typed(layout.inputs(), &assignments.inputs);
+        let outputs = typed(layout.outputs(), &assignments.outputs);
+        let mut found = vec![];
+        differences(
+            layout.inputs(),
+            "input".to_owned(),
+            inputs.each_ref(),
+            &mut found,
+        );
+        differences(
+            layout.outputs(),
+            "output".to_owned(),
+            outputs.each_ref(),
+            &mut found,
+        );
+        Self {
+            inputs,
+            outputs,
+            differences: found,
+        }
+    }
+
+    /// Values on which the two assignments disagree.
+    pub fn differences(&self) -> &[Difference] {
+        &self.differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTEREXAMPLE: &str = "Counterexample:
+  inputs:
+    hash/hash_1/sha256/byte.in_0: 7
+  first possible outputs:
+    out_0: 1
+    out_1: 2
+    out_2: 0x11
+  second possible outputs:
+    out_0: 1
+    out_1: 2
+    out_2: 42
+";
+
+    fn layout() -> IoLayout {
+        let byte = CellLayout::Cells {
+            ty: "AssignedByte<Scalar>".to_owned(),
+            size: 1,
+        };
+        IoLayout::new(
+            CellLayout::array(byte.clone(), 1),
+            CellLayout::array(byte, 3),
+        )
+    }
+
+    #[test]
+    fn parse_assignments() {
+        let assignments = Assignments::parse(COUNTEREXAMPLE);
+        for n in 0..2 {
+            assert_eq!(assignments.inputs[n], BTreeMap::from([(0, "7".to_owned())]));
+        }
+        assert_eq!(assignments.outputs[0][&2], "0x11");
+        assert_eq!(assignments.outputs[1][&2], "42");
+        assert!(Assignments::parse("unsafe").is_empty());
+    }
+
+    #[test]
+    fn decode_differences() {
+        let decoded = DecodedCounterexample::decode(&layout(), &Assignments::parse(COUNTEREXAMPLE));
+        assert_eq!(decoded.inputs[0].to_string(), "[7]");
+        assert_eq!(decoded.outputs[0].to_string(), "[1, 2, 0x11]");
+        assert_eq!(decoded.outputs[1].to_string(), "[1, 2, 42]");
+        let differences: Vec<_> = decoded.differences().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            differences,
+            ["output[2] (AssignedByte<Scalar>): 0x11 != 42"]
+        );
+    }
+}
@@ -1,6 +1,5 @@
-use mdnt_extractor_core::entry as add_entry;
 use mdnt_extractor_core::fields::{Blstrs as F, Loaded as L};
-use mdnt_extractor_macros::{entry, harness};
+use mdnt_extractor_macros::{entry, harness, sweep};
 use midnight_circuits::{
     field::NativeChip,
     instructions::AssignmentInstructions,
@@ -51,26 +50,7 @@ pub fn assign_fixed_native_bit(
     chip.assign_fixed(layouter, x)
 }
 
-add_entry!(
-    "assignment/assign_many_1/native/native",
-    assign_many_native::<1>
-);
-add_entry!(
-    "assignment/assign_many_2/native/native",
-    assign_many_native::<2>
-);
-add_entry!(
-    "assignment/assign_many_3/native/native",
-    assign_many_native::<3>
-);
-add_entry!(
-    "assignment/assign_many_5/native/native",
-    assign_many_native::<5>
-);
-add_entry!(
-    "assignment/assign_many_8/native/native",
-    assign_many_native::<8>
-);
+#[sweep("assignment/assign_many_{SIZE}/native/native", SIZE = [1, 2, 3, 5, 8])]
 #[harness]
 pub fn assign_many_native<const SIZE: usize>(
     chip: &NativeChip<F>,
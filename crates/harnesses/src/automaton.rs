@@ -10,7 +10,7 @@ use crate::utils::{automaton, lookup_mux, range_lookup, vec2array};
 use mdnt_extractor_core::entry;
 
 entry!("automaton/parse_5/automaton/byte", parse::<5>);
-#[harness(lookup_mux().with("pow2range column check", range_lookup(8)).with("automaton transition check", automaton("Automaton", 8)))]
+#[harness(lookup_mux().with_handlers("pow2range column check", range_lookup(8)).with_handlers("automaton transition check", automaton("Automaton", 8)))]
 pub fn parse<const N: usize>(
     chip: &AutomatonChip<StdLibParser, F>,
     layouter: &mut impl Layouter<F>,
@@ -1,9 +1,6 @@
 use crate::utils::range_lookup;
-use mdnt_extractor_core::{
-    chips::{FC, NG},
-    entry,
-};
-use mdnt_extractor_macros::{harness, harness_with_args};
+use mdnt_extractor_core::chips::{FC, NG};
+use mdnt_extractor_macros::{harness, harness_with_args, sweep};
 use midnight_circuits::{
     compact_std_lib::ZkStdLib, field::NativeChip, instructions::CanonicityInstructions as _,
     midnight_proofs::plonk::Error, types::AssignedBit,
@@ -13,18 +10,7 @@ use num_bigint::BigUint;
 type F = mdnt_extractor_core::fields::Blstrs;
 type K = mdnt_extractor_core::fields::MidnightFp;
 
-entry!(
-    "canonicity/le_bits_lower_than_1/native/native",
-    le_bits_lower_than_native::<1>
-);
-entry!(
-    "canonicity/le_bits_lower_than_64/native/native",
-    le_bits_lower_than_native::<64>
-);
-entry!(
-    "canonicity/le_bits_lower_than_255/native/native",
-    le_bits_lower_than_native::<255>
-);
+#[sweep("canonicity/le_bits_lower_than_{N}/native/native", N = [1, 64, 255])]
 #[harness]
 pub fn le_bits_lower_than_native<const N: usize>(
     chip: &NativeChip<F>,
@@ -34,18 +20,7 @@ pub fn le_bits_lower_than_native<const N: usize>(
     chip.le_bits_lower_than(layouter, &bits, bound)
 }
 
-entry!(
-    "canonicity/le_bits_geq_than_1/native/native",
-    le_bits_geq_than_native::<1>
-);
-entry!(
-    "canonicity/le_bits_geq_than_64/native/native",
-    le_bits_geq_than_native::<64>
-);
-entry!(
-    "canonicity/le_bits_geq_than_255/native/native",
-    le_bits_geq_than_native::<255>
-);
+#[sweep("canonicity/le_bits_geq_than_{N}/native/native", N = [1, 64, 255])]
 #[harness]
 pub fn le_bits_geq_than_native<const N: usize>(
     chip: &NativeChip<F>,
@@ -55,18 +30,7 @@ pub fn le_bits_geq_than_native<const N: usize>(
     chip.le_bits_geq_than(layouter, &bits, bound)
 }
 
-entry!(
-    "canonicity/is_canonical_1/native/native",
-    is_canonical_native::<1>
-);
-entry!(
-    "canonicity/is_canonical_64/native/native",
-    is_canonical_native::<64>
-);
-entry!(
-    "canonicity/is_canonical_255/native/native",
-    is_canonical_native::<255>
-);
+#[sweep("canonicity/is_canonical_{N}/native/native", N = [1, 64, 255])]
 #[harness]
 pub fn is_canonical_native<const N: usize>(
     chip: &NativeChip<F>,
@@ -76,18 +40,7 @@ pub fn is_canonical_native<const N: usize>(
     chip.is_canonical(layouter, &bits)
 }
 
-entry!(
-    "canonicity/le_bits_lower_than_1/native-gadget/native",
-    le_bits_lower_than_native_gadget::<1>
-);
-entry!(
-    "canonicity/le_bits_lower_than_64/native-gadget/native",
-    le_bits_lower_than_native_gadget::<64>
-);
-entry!(
-    "canonicity/le_bits_lower_than_255/native-gadget/native",
-    le_bits_lower_than_native_gadget::<255>
-);
+#[sweep("canonicity/le_bits_lower_than_{N}/native-gadget/native", N = [1, 64, 255])]
 #[harness(range_lookup(8))]
 pub fn le_bits_lower_than_native_gadget<const N: usize>(
     chip: &NG<F>,
@@ -97,18 +50,7 @@ pub fn le_bits_lower_than_native_gadget<const N: usize>(
     chip.le_bits_lower_than(layouter, &bits, bound)
 }
 
-entry!(
-    "canonicity/le_bits_geq_than_1/native-gadget/native",
-    le_bits_geq_than_native_gadget::<1>
-);
-entry!(
-    "canonicity/le_bits_geq_than_64/native-gadget/native",
-    le_bits_geq_than_native_gadget::<64>
-);
-entry!(
-    "canonicity/le_bits_geq_than_255/native-gadget/native",
-    le_bits_geq_than_native_gadget::<255>
-);
+#[sweep("canonicity/le_bits_geq_than_{N}/native-gadget/native", N = [1, 64, 255])]
 #[harness(range_lookup(8))]
 pub fn le_bits_geq_than_native_gadget<const N: usize>(
     chip: &NG<F>,
@@ -118,18 +60,7 @@ pub fn le_bits_geq_than_native_gadget<const N: usize>(
     chip.le_bits_geq_than(layouter, &bits, bound)
 }
 
-entry!(
-    "canonicity/is_canonical_1/native-gadget/native",
-    is_canonical_native_gadget::<1>
-);
-entry!(
-    "canonicity/is_canonical_64/native-gadget/native",
-    is_canonical_native_gadget::<64>
-);
-entry!(
-    "canonicity/is_canonical_255/native-gadget/native",
-    is_canonical_native_gadget::<255>
-);
+#[sweep("canonicity/is_canonical_{N}/native-gadget/native", N = [1, 64, 255])]
 #[harness(range_lookup(8))]
 pub fn is_canonical_native_gadget<const N: usize>(
     chip: &NG<F>,
@@ -139,18 +70,7 @@ pub fn is_canonical_native_gadget<const N: usize>(
     chip.is_canonical(layouter, &bits)
 }
 
-entry!(
-    "canonicity/le_bits_lower_than_1/field/field",
-    le_bits_lower_than_field::<1>
-);
-entry!(
-    "canonicity/le_bits_lower_than_64/field/field",
-    le_bits_lower_than_field::<64>
-);
-entry!(
-    "canonicity/le_bits_lower_than_255/field/field",
-    le_bits_lower_than_field::<255>
-);
+#[sweep("canonicity/le_bits_lower_than_{N}/field/field", N = [1, 64, 255])]
 #[harness(range_lookup(8))]
 pub fn le_bits_lower_than_field<const N: usize>(
     chip: &FC<F, K>,
@@ -160,18 +80,7 @@ pub fn le_bits_lower_than_field<const N: usize>(
     chip.le_bits_lower_than(layouter, &bits, bound)
 }
 
-entry!(
-    "canonicity/le_bits_geq_than_1/field/field",
-    le_bits_geq_than_field::<1>
-);
-entry!(
-    "canonicity/le_bits_geq_than_64/field/field",
-    le_bits_geq_than_field::<64>
-);
-entry!(
-    "canonicity/le_bits_geq_than_255/field/field",
-    le_bits_geq_than_field::<255>
-);
+#[sweep("canonicity/le_bits_geq_than_{N}/field/field", N = [1, 64, 255])]
 #[harness(range_lookup(8))]
 pub fn le_bits_geq_than_field<const N: usize>(
     chip: &FC<F, K>,
@@ -181,18 +90,7 @@ pub fn le_bits_geq_than_field<const N: usize>(
     chip.le_bits_geq_than(layouter, &bits, bound)
 }
 
-entry!(
-    "canonicity/is_canonical_1/field/field",
-    is_canonical_field::<1>
-);
-entry!(
-    "canonicity/is_canonical_64/field/field",
-    is_canonical_field::<64>
-);
-entry!(
-    "canonicity/is_canonical_255/field/field",
-    is_canonical_field::<255>
-);
+#[sweep("canonicity/is_canonical_{N}/field/field", N = [1, 64, 255])]
 #[harness(range_lookup(8))]
 pub fn is_canonical_field<const N: usize>(
     chip: &FC<F, K>,
@@ -205,18 +103,7 @@ pub fn is_canonical_field<const N: usize>(
 fn le_bits_lower_than_stdlib_args() -> usize {
     8
 }
-entry!(
-    "canonicity/le_bits_lower_than_1/stdlib/native",
-    le_bits_lower_than_stdlib::<1>
-);
-entry!(
-    "canonicity/le_bits_lower_than_64/stdlib/native",
-    le_bits_lower_than_stdlib::<64>
-);
-entry!(
-    "canonicity/le_bits_lower_than_255/stdlib/native",
-    le_bits_lower_than_stdlib::<255>
-);
+#[sweep("canonicity/le_bits_lower_than_{N}/stdlib/native", N = [1, 64, 255])]
 #[harness_with_args(usize, range_lookup(8))]
 pub fn le_bits_lower_than_stdlib<const N: usize>(
     chip: &ZkStdLib,
@@ -229,18 +116,7 @@ pub fn le_bits_lower_than_stdlib<const N: usize>(
 fn le_bits_geq_than_stdlib_args() -> usize {
     8
 }
-entry!(
-    "canonicity/le_bits_geq_than_1/stdlib/native",
-    le_bits_geq_than_stdlib::<1>
-);
-entry!(
-    "canonicity/le_bits_geq_than_64/stdlib/native",
-    le_bits_geq_than_stdlib::<64>
-);
-entry!(
-    "canonicity/le_bits_geq_than_255/stdlib/native",
-    le_bits_geq_than_stdlib::<255>
-);
+#[sweep("canonicity/le_bits_geq_than_{N}/stdlib/native", N = [1, 64, 255])]
 #[harness_with_args(usize, range_lookup(8))]
 pub fn le_bits_geq_than_stdlib<const N: usize>(
     chip: &ZkStdLib,
@@ -253,18 +129,7 @@ pub fn le_bits_geq_than_stdlib<const N: usize>(
 fn is_canonical_stdlib_args() -> usize {
     8
 }
-entry!(
-    "canonicity/is_canonical_1/stdlib/native",
-    is_canonical_stdlib::<1>
-);
-entry!(
-    "canonicity/is_canonical_64/stdlib/native",
-    is_canonical_stdlib::<64>
-);
-entry!(
-    "canonicity/is_canonical_255/stdlib/native",
-    is_canonical_stdlib::<255>
-);
+#[sweep("canonicity/is_canonical_{N}/stdlib/native", N = [1, 64, 255])]
 #[harness_with_args(usize, range_lookup(8))]
 pub fn is_canonical_stdlib<const N: usize>(
     chip: &ZkStdLib,
@@ -8,7 +8,7 @@ pub mod native {
         cells::load::LoadedG1,
         chips::{Afp, Fecn},
     };
-    use mdnt_extractor_macros::{entry, harness, unit_harness};
+    use mdnt_extractor_macros::{entry, harness, sweep, unit_harness};
     use midnight_circuits::instructions::AssertionInstructions as _;
     use midnight_circuits::types::InnerConstants as _;
     use midnight_circuits::{midnight_proofs::plonk::Error, types::AssignedBit};
@@ -27,14 +27,11 @@ pub mod native {
         chip.assert_in_bls12_381_subgroup(layouter, &x)
     }
 
-    add_entry!(
-        "foreign-ecc/k_out_of_n_points_10_3/foreign-ecc-native/point",
-        k_out_of_n_points_native::<10, 3>
-    );
-    add_entry!(
-        "foreign-ecc/k_out_of_n_points_5_3/foreign-ecc-native/point",
-        k_out_of_n_points_native::<5, 3>
-    );
+    #[sweep(
+        "foreign-ecc/k_out_of_n_points_{N}_{K}/foreign-ecc-native/point",
+        N = [10, 5],
+        K = [3]
+    )]
     #[harness(range_lookup(8))]
     pub fn k_out_of_n_points_native<const N: usize, const K: usize>(
         chip: &Fecn<F, G>,
@@ -94,7 +91,7 @@ pub mod field {
         cells::load::LoadedSecp256k1,
         chips::{Afp, Fecf},
     };
-    use mdnt_extractor_macros::harness;
+    use mdnt_extractor_macros::{harness, sweep};
     use midnight_circuits::instructions::AssertionInstructions as _;
     use midnight_circuits::types::InnerConstants as _;
     use midnight_circuits::{midnight_proofs::plonk::Error, types::AssignedBit};
@@ -102,14 +99,11 @@ pub mod field {
 
     pub type F = mdnt_extractor_core::fields::Blstrs;
 
-    add_entry!(
-        "foreign-ecc/k_out_of_n_points_10_3/foreign-ecc-field/point",
-        k_out_of_n_points_field::<10, 3>
-    );
-    add_entry!(
-        "foreign-ecc/k_out_of_n_points_5_3/foreign-ecc-field/point",
-        k_out_of_n_points_field::<5, 3>
-    );
+    #[sweep(
+        "foreign-ecc/k_out_of_n_points_{N}_{K}/foreign-ecc-field/point",
+        N = [10, 5],
+        K = [3]
+    )]
     #[harness(range_lookup(8))]
     pub fn k_out_of_n_points_field<const N: usize, const K: usize>(
         chip: &Fecf<F, G>,
@@ -13,8 +13,8 @@ pub type F = mdnt_extractor_core::fields::Blstrs;
 entry!("hash/hash_1/sha256/byte", hash_sha256::<1>);
 entry!("hash/hash_10/sha256/byte", hash_sha256::<10>);
 #[harness(lookup_mux::<F>()
-            .with("pow2range column check", range_lookup(8))
-            .with("plain-spreaded lookup",plain_spread_lookup("Spread", "Unspread"))
+            .with_handlers("pow2range column check", range_lookup(8))
+            .with_handlers("plain-spreaded lookup",plain_spread_lookup("Spread", "Unspread"))
 )]
 pub fn hash_sha256<const N: usize>(
     chip: &Sha256Adaptor<F, NG<F>>,
@@ -70,6 +70,7 @@ pub mod utils {
             SpreadByteLookup,
         },
         range::TagRangeLookup,
+        table::TableLookup,
     };
 
     /// Returns a lookup callback that treats the lookup as a range check.
@@ -130,6 +131,12 @@ pub mod utils {
         SpreadByteLookup
     }
 
+    /// Returns a lookup callback that asserts that the inputs are one of the rows of
+    /// the table, for tables of up to `max_rows` distinct rows.
+    pub fn table_lookup(max_rows: usize) -> TableLookup {
+        TableLookup::new(max_rows)
+    }
+
     /// Returns a lookup callback that ignores all lookups
     #[allow(dead_code)]
     pub fn ignore_lookup() -> IgnoreLookup {
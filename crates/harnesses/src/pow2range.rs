@@ -1,4 +1,4 @@
-use crate::utils::range_lookup;
+use crate::utils::{range_lookup, table_lookup};
 use mdnt_extractor_core::cells::store::FreshVar;
 use mdnt_extractor_macros::{entry, harness_with_args, usize_args};
 use midnight_circuits::{
@@ -22,3 +22,17 @@ pub fn assert_values_lower_than_2_pow_8_1(
     //todo!("Add postconditions");
     Ok(FreshVar)
 }
+
+/// Same as [`assert_values_lower_than_2_pow_8_1`], but the lookup is encoded by
+/// a module generated from the rows of the table instead of a range check.
+#[usize_args(8)]
+#[entry("pow2range/assert_values_lower_than_2_pow_8_1_by_table/pow2range/native")]
+#[harness_with_args(usize, table_lookup(0).with_module("Pow2RangeTable"))]
+pub fn assert_values_lower_than_2_pow_8_1_by_table(
+    chip: &Pow2RangeChip<F>,
+    layouter: &mut impl Layouter<F>,
+    values: [AssignedNative<F>; 1],
+) -> Result<FreshVar, Error> {
+    chip.assert_values_lower_than_2_pow_n(layouter, &values, 8)?;
+    Ok(FreshVar)
+}
@@ -1,11 +1,7 @@
-use haloumi_ir_gen::lookups::callbacks::LookupCallbacks;
-use mdnt_extractor_core::{entry as add_entry, fields::Blstrs as F};
+use mdnt_extractor_core::{entry as add_entry, fields::Blstrs as F, lookups::LookupHandlers};
 use mdnt_extractor_macros::{entry, harness};
 use midnight_circuits::types::{AssignedByte, InnerValue as _};
-use midnight_proofs::{
-    circuit::AssignedCell,
-    plonk::{Error, Expression},
-};
+use midnight_proofs::{circuit::AssignedCell, plonk::Error};
 use sha3_circuit::{
     instructions::Keccackf1600Instructions,
     packed_chip::{AbsorbedBlock, PackedChip},
@@ -19,7 +15,7 @@ use crate::utils::{
 
 type AssignedDenseBits = <PackedChip<F> as Keccackf1600Instructions<F>>::AssignedByte;
 
-fn lookups() -> impl LookupCallbacks<F, Expression<F>> {
+fn lookups() -> impl LookupHandlers<F> {
     fn decomposition_lookup_limbs_0_2(n: &str) -> bool {
         n.starts_with("decomposition lookup")
             && (n.ends_with("limb 0") || n.ends_with("limb 1") || n.ends_with("limb 2"))
@@ -33,19 +29,19 @@ fn lookups() -> impl LookupCallbacks<F, Expression<F>> {
     let unspread = "Unspread";
 
     lookup_mux()
-        .with(
+        .with_handlers(
             |n: &str| n.starts_with("spread byte lookup"),
             plain_spread_lookup3(spread, unspread, spread_byte_lookup()),
         )
-        .with(
+        .with_handlers(
             decomposition_lookup_limbs_0_2,
             plain_spread_lookup3(spread, unspread, any_spread()),
         )
-        .with(
+        .with_handlers(
             "decomposition lookup: limb 3",
             plain_spread_lookup3(spread, unspread, spread12()),
         )
-        .with(
+        .with_handlers(
             decomposition_lookup_limbs_4_5,
             plain_spread_lookup3(spread, unspread, spread_by_tag()),
         )
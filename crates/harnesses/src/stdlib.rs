@@ -70,8 +70,8 @@ add_entry!("stdlib/sha256_10/stdlib/byte", sha256::<10>);
 add_entry!("stdlib/sha256_100/stdlib/byte", sha256::<100>);
 #[usize_args(8)]
 #[harness_with_args(usize,lookup_mux::<F>()
-            .with("pow2range column check", range_lookup(8))
-            .with("plain-spreaded lookup",plain_spread_lookup("Spread", "Unspread"))
+            .with_handlers("pow2range column check", range_lookup(8))
+            .with_handlers("plain-spreaded lookup",plain_spread_lookup("Spread", "Unspread"))
 )]
 pub fn sha256<const N: usize>(
     chip: &ZkStdLib,
@@ -13,8 +13,8 @@ use midnight_proofs::plonk::Error;
 entry!("varhash/varhash_64/sha256/byte", varhash_sha256::<64>);
 entry!("varhash/varhash_100/sha256/byte", varhash_sha256::<100>);
 #[harness(lookup_mux::<F>()
-            .with("pow2range column check", range_lookup(8))
-            .with("plain-spreaded lookup", plain_spread_lookup("Spread", "Unspread"))
+            .with_handlers("pow2range column check", range_lookup(8))
+            .with_handlers("plain-spreaded lookup", plain_spread_lookup("Spread", "Unspread"))
 )]
 pub fn varhash_sha256<const N: usize>(
     chip: &VarLenSha256Gadget<F>,
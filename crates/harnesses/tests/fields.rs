@@ -0,0 +1,114 @@
+//! Registers and extracts a harness over a field other than the default one.
+
+use std::{collections::BTreeSet, marker::PhantomData};
+
+use ff::{Field as _, PrimeField};
+use haloumi::core::felt::Prime;
+use mdnt_extractor_core::{circuit::planner::FloorPlanning, fields::Bn254, harness::Ctx};
+use mdnt_extractor_macros::{entry, harness};
+use mdnt_support::circuit::CircuitInitialization;
+use midnight_circuits::types::AssignedNative;
+use midnight_proofs::{
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+
+#[derive(Clone, Debug)]
+struct SquareConfig {
+    x: Column<Advice>,
+    y: Column<Advice>,
+    q: Selector,
+}
+
+/// Chip that squares a cell. Unlike the chips of the harnesses it works over
+/// any field, so it gives the tests a circuit over a second field.
+struct SquareChip<F>(SquareConfig, PhantomData<F>);
+
+impl<F: PrimeField> SquareChip<F> {
+    fn square(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: &AssignedNative<F>,
+    ) -> Result<AssignedNative<F>, Error> {
+        let SquareConfig { x: x_col, y, q } = self.0;
+        layouter.assign_region(
+            || "square",
+            |mut region| {
+                q.enable(&mut region, 0)?;
+                x.copy_advice(|| "x", &mut region, x_col, 0)?;
+                region.assign_advice(|| "x^2", y, 0, || x.value().map(|x| x.square()))
+            },
+        )
+    }
+}
+
+impl<F: PrimeField, L> CircuitInitialization<L> for SquareChip<F> {
+    type Config = SquareConfig;
+
+    type Args = ();
+
+    type ConfigCols = [Column<Advice>; 2];
+
+    type CS = ConstraintSystem<F>;
+
+    type Error = Error;
+
+    fn new_chip(config: &Self::Config, _args: Self::Args) -> Self {
+        Self(config.clone(), PhantomData)
+    }
+
+    fn configure_circuit(meta: &mut Self::CS, &[x, y]: &Self::ConfigCols) -> Self::Config {
+        let q = meta.selector();
+        meta.enable_equality(x);
+        meta.enable_equality(y);
+        meta.create_gate("square", |meta| {
+            let q = meta.query_selector(q);
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+            vec![q * (x.clone() * x - y)]
+        });
+        SquareConfig { x, y, q }
+    }
+
+    fn load_chip(&self, _layouter: &mut L, _config: &Self::Config) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+// Same name as the harness of `NativeChip::square` over the default field.
+#[entry("arithmetic/square/native/native")]
+#[harness]
+fn square_bn254(
+    chip: &SquareChip<Bn254>,
+    layouter: &mut impl Layouter<Bn254>,
+    x: AssignedNative<Bn254>,
+) -> Result<AssignedNative<Bn254>, Error> {
+    chip.square(layouter, &x)
+}
+
+#[test]
+fn entries_over_other_fields_are_qualified() {
+    let entries = mdnt_harnesses::harnesses()
+        .filter(|entry| entry.name() == "arithmetic/square/native/native")
+        .map(|entry| (entry.field(), entry.qualified_name()))
+        .collect::<BTreeSet<_>>();
+    assert_eq!(
+        entries,
+        BTreeSet::from([
+            ("blstrs", "arithmetic/square/native/native"),
+            ("bn254", "arithmetic/square/native/native@bn254"),
+        ])
+    );
+}
+
+#[test]
+fn extracts_harnesses_over_other_fields() {
+    let entry = mdnt_harnesses::harnesses()
+        .find(|entry| entry.qualified_name() == "arithmetic/square/native/native@bn254")
+        .unwrap();
+    let ctx = Ctx::new(&[], false, false, false, 8, FloorPlanning::Simple, false);
+    let ir = entry.harness()(&ctx).unwrap();
+    assert_eq!(ir.prime(), Prime::new::<Bn254>());
+    assert!(ctx.witness_check().unwrap().accepted() > 0);
+}
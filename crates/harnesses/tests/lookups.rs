@@ -0,0 +1,58 @@
+//! Checks how the lookups of a circuit are recorded depending on the callbacks
+//! that the harness passes them to.
+
+use mdnt_extractor_core::{
+    cells::store::FreshVar,
+    circuit::planner::FloorPlanning,
+    fields::Blstrs as F,
+    harness::Ctx,
+    lookups::{
+        callbacks::{ignore::IgnoreLookup, mux::LookupMux},
+        LookupStatus,
+    },
+};
+use mdnt_extractor_macros::{harness_with_args, usize_args};
+use midnight_circuits::{
+    field::decomposition::pow2range::Pow2RangeChip,
+    instructions::decomposition::Pow2RangeInstructions as _, midnight_proofs::plonk::Error,
+    types::AssignedNative,
+};
+
+#[usize_args(8)]
+#[harness_with_args(usize, LookupMux::default().fallback(IgnoreLookup))]
+fn fallback_to_plain_ignore(
+    chip: &Pow2RangeChip<F>,
+    layouter: &mut impl Layouter<F>,
+    values: [AssignedNative<F>; 1],
+) -> Result<FreshVar, Error> {
+    chip.assert_values_lower_than_2_pow_n(layouter, &values, 8)?;
+    Ok(FreshVar)
+}
+
+fn ctx(strict_lookups: bool) -> Ctx<'static> {
+    Ctx::new(
+        &[],
+        false,
+        false,
+        false,
+        0,
+        FloorPlanning::Simple,
+        strict_lookups,
+    )
+}
+
+#[test]
+fn plain_callbacks_are_unverified() {
+    let ctx = ctx(false);
+    fallback_to_plain_ignore().harness()(&ctx).unwrap();
+    let lookups = ctx.lookups();
+    assert!(!lookups.is_empty());
+    assert!(lookups.iter().all(|lookup| lookup.status() == LookupStatus::Unverified));
+}
+
+#[test]
+fn plain_ignore_fallback_fails_in_strict_mode() {
+    let ctx = ctx(true);
+    let err = fallback_to_plain_ignore().harness()(&ctx).unwrap_err();
+    assert!(format!("{err:#}").contains("which may not encode it"));
+}
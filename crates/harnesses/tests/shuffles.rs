@@ -0,0 +1,133 @@
+//! Checks how the shuffles of a circuit are encoded depending on the callbacks
+//! that the harness passes them to.
+
+use haloumi_ir::stmt::IRStmt;
+use mdnt_extractor_core::{
+    circuit::planner::FloorPlanning,
+    fields::Blstrs as F,
+    harness::Ctx,
+    shuffles::{Shuffle, ShuffleCallbacks, ShuffleExpr, ShuffleModule},
+};
+use mdnt_extractor_macros::harness;
+use mdnt_support::circuit::CircuitInitialization;
+use midnight_circuits::types::AssignedNative;
+use midnight_proofs::{
+    circuit::Layouter,
+    plonk::{Advice, Column, ConstraintSystem, Error},
+    poly::Rotation,
+};
+
+#[derive(Clone, Debug)]
+struct ShuffleConfig {
+    input: Column<Advice>,
+    output: Column<Advice>,
+}
+
+/// Chip whose output is only constrained to be a permutation of its input by a
+/// shuffle.
+struct ShuffleChip(ShuffleConfig);
+
+impl ShuffleChip {
+    fn permute(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: &AssignedNative<F>,
+    ) -> Result<AssignedNative<F>, Error> {
+        let ShuffleConfig { input, output } = self.0;
+        layouter.assign_region(
+            || "permute",
+            |mut region| {
+                x.copy_advice(|| "x", &mut region, input, 0)?;
+                region.assign_advice(|| "y", output, 0, || x.value().copied())
+            },
+        )
+    }
+}
+
+impl<L> CircuitInitialization<L> for ShuffleChip {
+    type Config = ShuffleConfig;
+
+    type Args = ();
+
+    type ConfigCols = ();
+
+    type CS = ConstraintSystem<F>;
+
+    type Error = Error;
+
+    fn new_chip(config: &Self::Config, _args: Self::Args) -> Self {
+        Self(config.clone())
+    }
+
+    fn configure_circuit(meta: &mut Self::CS, _columns: &Self::ConfigCols) -> Self::Config {
+        let input = meta.advice_column();
+        let output = meta.advice_column();
+        meta.enable_equality(input);
+        meta.enable_equality(output);
+        meta.shuffle("permutation", |meta| {
+            let x = meta.query_advice(input, Rotation::cur());
+            let y = meta.query_advice(output, Rotation::cur());
+            vec![(x, y)]
+        });
+        ShuffleConfig { input, output }
+    }
+
+    fn load_chip(&self, _layouter: &mut L, _config: &Self::Config) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[harness]
+fn permute(
+    chip: &ShuffleChip,
+    layouter: &mut impl Layouter<F>,
+    x: AssignedNative<F>,
+) -> Result<AssignedNative<F>, Error> {
+    chip.permute(layouter, &x)
+}
+
+/// Shuffle callbacks that leave every shuffle out of the IR.
+struct SkipShuffle;
+
+impl ShuffleCallbacks<F> for SkipShuffle {
+    fn on_shuffle(&self, _shuffle: &Shuffle<F>) -> anyhow::Result<IRStmt<ShuffleExpr<F>>> {
+        Ok(IRStmt::empty())
+    }
+}
+
+#[harness]
+#[shuffles(SkipShuffle)]
+fn permute_skipping_shuffles(
+    chip: &ShuffleChip,
+    layouter: &mut impl Layouter<F>,
+    x: AssignedNative<F>,
+) -> Result<AssignedNative<F>, Error> {
+    chip.permute(layouter, &x)
+}
+
+fn ctx() -> Ctx<'static> {
+    Ctx::new(&[], false, false, false, 8, FloorPlanning::Simple, false)
+}
+
+#[test]
+fn shuffles_call_a_multiset_equality_module() {
+    let ctx = ctx();
+    let ir = permute().harness()(&ctx).unwrap();
+    let modules = ctx.shuffle_modules();
+    let [module] = modules.as_slice() else {
+        panic!("Expected a single shuffle module");
+    };
+    assert_eq!(module.width(), 1);
+    assert!(ir.display().to_string().contains(&module.name()));
+    let check = ctx.witness_check().expect("The witnesses were not checked");
+    assert_eq!(check.skipped(), None);
+    assert!(check.accepted() > 0);
+}
+
+#[test]
+fn harnesses_can_pass_other_shuffle_callbacks() {
+    let ctx = ctx();
+    let ir = permute_skipping_shuffles().harness()(&ctx).unwrap();
+    assert_eq!(ctx.shuffle_modules(), Vec::<ShuffleModule>::new());
+    assert!(!ir.display().to_string().contains("MultisetEq"));
+}
@@ -0,0 +1,87 @@
+//! Extracts harnesses whose inputs and outputs take their length from the
+//! constants.
+
+use mdnt_extractor_core::{
+    cells::var::{BoundedVec, Flagged},
+    circuit::planner::FloorPlanning,
+    fields::Blstrs as F,
+    harness::{Ctx, Output},
+};
+use mdnt_extractor_macros::harness;
+use mdnt_support::circuit::CircuitInitialization;
+use midnight_circuits::types::AssignedNative;
+use midnight_proofs::plonk::{ConstraintSystem, Error};
+
+/// Chip without any columns or gates.
+struct NoChip;
+
+impl<L> CircuitInitialization<L> for NoChip {
+    type Config = ();
+
+    type Args = ();
+
+    type ConfigCols = ();
+
+    type CS = ConstraintSystem<F>;
+
+    type Error = Error;
+
+    fn new_chip(_config: &Self::Config, _args: Self::Args) -> Self {
+        Self
+    }
+
+    fn configure_circuit(_meta: &mut Self::CS, _columns: &Self::ConfigCols) -> Self::Config {}
+
+    fn load_chip(&self, _layouter: &mut L, _config: &Self::Config) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[harness]
+fn round_trip(
+    _chip: &NoChip,
+    _layouter: &mut impl Layouter<F>,
+    (xs, x): (BoundedVec<AssignedNative<F>, 3>, Flagged<AssignedNative<F>>),
+) -> Result<(BoundedVec<AssignedNative<F>, 3>, Flagged<AssignedNative<F>>), Error> {
+    Ok((xs, x))
+}
+
+fn extract(constants: &[&str]) -> anyhow::Result<Output> {
+    let constants: Vec<_> = constants.iter().map(ToString::to_string).collect();
+    let ctx = Ctx::new(
+        &constants,
+        false,
+        false,
+        false,
+        0,
+        FloorPlanning::Simple,
+        false,
+    );
+    round_trip().harness()(&ctx)
+}
+
+/// Number of statements that the inputs injected in the IR.
+fn injected(ir: &Output) -> usize {
+    ir.groups().iter().map(|group| group.injected_count()).sum()
+}
+
+#[test]
+fn inputs_round_trip_through_the_constants() {
+    let ir = extract(&["2", "true"]).unwrap();
+    assert_eq!((ir.main().input_count(), ir.main().output_count()), (6, 6));
+    // The length, the unused element and the flag.
+    assert_eq!(injected(&ir), 3);
+    let ir = extract(&["0", "false"]).unwrap();
+    // The length, the 3 unused elements, the flag and the missing value.
+    assert_eq!(injected(&ir), 6);
+}
+
+#[test]
+fn lengths_over_the_maximum_are_rejected() {
+    assert!(extract(&["4", "true"]).is_err());
+}
+
+#[test]
+fn constants_that_are_not_flags_are_rejected() {
+    assert!(extract(&["1", "1"]).is_err());
+}
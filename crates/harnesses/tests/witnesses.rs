@@ -0,0 +1,157 @@
+//! Checks the IR of harnesses against the witnesses of their circuits.
+
+use mdnt_extractor_core::{
+    circuit::{mock::WitnessCheck, planner::FloorPlanning},
+    fields::Blstrs as F,
+    harness::{Ctx, Definition},
+    lookups::LookupStatus,
+};
+use mdnt_extractor_macros::harness;
+use mdnt_support::circuit::CircuitInitialization;
+use midnight_circuits::types::AssignedNative;
+use midnight_proofs::{
+    circuit::Layouter,
+    plonk::{
+        Advice, Challenge, Column, ConstraintSystem, Error, FirstPhase, SecondPhase, Selector,
+    },
+    poly::Rotation,
+};
+
+fn definition(name: &str) -> Definition {
+    let entry = mdnt_harnesses::harnesses()
+        .find(|entry| entry.qualified_name() == name)
+        .unwrap_or_else(|| panic!("No harness named {name}"));
+    entry.definition()
+}
+
+fn extract(definition: Definition) -> Ctx<'static> {
+    let ctx = Ctx::new(&[], false, false, false, 8, FloorPlanning::Simple, false);
+    definition.harness()(&ctx).unwrap();
+    ctx
+}
+
+fn check_witnesses(name: &str) -> WitnessCheck {
+    check_definition(definition(name))
+}
+
+fn check_definition(definition: Definition) -> WitnessCheck {
+    extract(definition).witness_check().expect("The witnesses were not checked")
+}
+
+#[test]
+fn spread_lookups_are_checked() {
+    let check = check_witnesses("hash/hash_1/sha256/byte");
+    assert_eq!(check.skipped(), None);
+    assert!(check.accepted() > 0);
+}
+
+#[test]
+fn table_modules_are_checked() {
+    let ctx = extract(definition(
+        "pow2range/assert_values_lower_than_2_pow_8_1_by_table/pow2range/native",
+    ));
+    let modules = ctx.table_modules();
+    let [module] = modules.as_slice() else {
+        panic!("Expected a single table module");
+    };
+    assert_eq!(module.name(), "Pow2RangeTable");
+    assert_eq!(module.inputs(), 2);
+    let lookups = ctx.lookups();
+    assert!(!lookups.is_empty());
+    assert!(lookups.iter().all(|lookup| lookup.status() == LookupStatus::Handled));
+    let check = ctx.witness_check().expect("The witnesses were not checked");
+    assert_eq!(check.skipped(), None);
+    assert!(check.accepted() > 0);
+}
+
+#[derive(Clone, Debug)]
+struct RlcConfig {
+    x: Column<Advice>,
+    y: Column<Advice>,
+    q: Selector,
+    challenge: Challenge,
+}
+
+/// Chip that multiplies a cell by a challenge into an advice column of the
+/// second phase.
+struct RlcChip(RlcConfig);
+
+impl RlcChip {
+    fn mul_by_challenge(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: &AssignedNative<F>,
+    ) -> Result<AssignedNative<F>, Error> {
+        let RlcConfig {
+            x: x_col,
+            y,
+            q,
+            challenge,
+        } = self.0;
+        let challenge = layouter.get_challenge(challenge);
+        layouter.assign_region(
+            || "rlc",
+            |mut region| {
+                q.enable(&mut region, 0)?;
+                x.copy_advice(|| "x", &mut region, x_col, 0)?;
+                region.assign_advice(|| "x * c", y, 0, || x.value().copied() * challenge)
+            },
+        )
+    }
+}
+
+impl<L> CircuitInitialization<L> for RlcChip {
+    type Config = RlcConfig;
+
+    type Args = ();
+
+    type ConfigCols = ();
+
+    type CS = ConstraintSystem<F>;
+
+    type Error = Error;
+
+    fn new_chip(config: &Self::Config, _args: Self::Args) -> Self {
+        Self(config.clone())
+    }
+
+    fn configure_circuit(meta: &mut Self::CS, _columns: &Self::ConfigCols) -> Self::Config {
+        let x = meta.advice_column();
+        let y = meta.advice_column_in(SecondPhase);
+        let q = meta.selector();
+        let challenge = meta.challenge_usable_after(FirstPhase);
+        meta.enable_equality(x);
+        meta.enable_equality(y);
+        meta.create_gate("rlc", |meta| {
+            let q = meta.query_selector(q);
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+            let c = meta.query_challenge(challenge);
+            vec![q * (y - x * c)]
+        });
+        RlcConfig { x, y, q, challenge }
+    }
+
+    fn load_chip(&self, _layouter: &mut L, _config: &Self::Config) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[harness]
+fn mul_by_challenge(
+    chip: &RlcChip,
+    layouter: &mut impl Layouter<F>,
+    x: AssignedNative<F>,
+) -> Result<AssignedNative<F>, Error> {
+    chip.mul_by_challenge(layouter, &x)
+}
+
+#[test]
+fn second_phase_advice_is_checked_with_the_challenges_of_the_prover() {
+    let check = check_definition(mul_by_challenge());
+    assert_eq!(check.skipped(), None);
+    // The IR only accepts the nonzero samples with the challenge the output
+    // was computed with, which is the one the prover drew.
+    assert_eq!(check.accepted(), 8);
+    assert_eq!(check.rejected(), 0);
+}
@@ -0,0 +1,384 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens as _};
+use syn::{
+    spanned::Spanned as _, Attribute, Data, DeriveInput, Fields, GenericParam, Generics, Ident,
+    Index, Type, TypeParam, WherePredicate,
+};
+
+/// Internal implementation of [`super::derive_cell_repr_size`].
+pub fn derive_cell_repr_size_impl(input: DeriveInput) -> syn::Result<TokenStream> {
+    let input = CellsInput::parse(input)?;
+    let m = &input.module;
+    let name = &input.name;
+    let types = input.repr_types().collect::<Vec<_>>();
+    let mut generics = input.generics.clone();
+    extend_where_clause(
+        &mut generics,
+        types.iter().map(|ty| quote! { #ty: #m::cells::CellReprSize }),
+    )?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #m::cells::CellReprSize for #name #ty_generics #where_clause {
+            const SIZE: usize = 0 #( + <#types as #m::cells::CellReprSize>::SIZE )*;
+        }
+    })
+}
+
+/// Internal implementation of [`super::derive_load_from_cells`].
+pub fn derive_load_from_cells_impl(input: DeriveInput) -> syn::Result<TokenStream> {
+    let input = CellsInput::parse(input)?;
+    let m = &input.module;
+    let name = &input.name;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let [f, c, h, l] = fresh_idents(&input.generics, ["__F", "__C", "__H", "__L"]);
+    let load_trait = quote! { #m::cells::load::LoadFromCells<#f, #c, #h, #l> };
+
+    let generics = input.trait_generics([&f, &c, &h, &l], &load_trait)?;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let fields = input.fields.iter().zip(&input.reprs).enumerate().map(|(idx, (field, repr))| {
+        let member = member(field.ident.as_ref(), idx);
+        let value = match repr {
+            FieldRepr::Cells => {
+                let ty = &field.ty;
+                quote! { <#ty as #load_trait>::load(ctx, chip, layouter, injected_ir)? }
+            }
+            FieldRepr::Constant => quote! { ctx.primitive_constant()? },
+            FieldRepr::With(ty) => {
+                quote! { <#ty as #load_trait>::load(ctx, chip, layouter, injected_ir)?.into() }
+            }
+        };
+        quote! { #member: #value }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #load_trait for #name #ty_generics #where_clause {
+            fn load(
+                ctx: &mut #m::cells::ctx::ICtx<#f, #h>,
+                chip: &#c,
+                layouter: &mut impl #m::cells::ctx::LayoutAdaptor<#f, #h, Adaptee = #l>,
+                injected_ir: &mut #m::circuit::injected::InjectedIR<#h::RegionIndex, #h::Expression>,
+            ) -> Result<Self, #h::Error> {
+                Ok(Self { #( #fields, )* })
+            }
+        }
+    })
+}
+
+/// Internal implementation of [`super::derive_store_into_cells`].
+pub fn derive_store_into_cells_impl(input: DeriveInput) -> syn::Result<TokenStream> {
+    let input = CellsInput::parse(input)?;
+    let m = &input.module;
+    let name = &input.name;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let [f, c, h, l] = fresh_idents(&input.generics, ["__F", "__C", "__H", "__L"]);
+    let store_trait = quote! { #m::cells::store::StoreIntoCells<#f, #c, #h, #l> };
+
+    let generics = input.trait_generics([&f, &c, &h, &l], &store_trait)?;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    // Constants are not part of the outputs.
+    let fields = input.fields.iter().zip(&input.reprs).enumerate().filter_map(
+        |(idx, (field, repr))| {
+            let member = member(field.ident.as_ref(), idx);
+            match repr {
+                FieldRepr::Cells => {
+                    let ty = &field.ty;
+                    Some(quote! { <#ty as #store_trait>::store(self.#member, ctx, chip, layouter, injected_ir)?; })
+                }
+                FieldRepr::Constant => None,
+                FieldRepr::With(ty) => Some(quote! {
+                    <#ty as #store_trait>::store(<#ty>::from(self.#member), ctx, chip, layouter, injected_ir)?;
+                }),
+            }
+        },
+    );
+
+    Ok(quote! {
+        impl #impl_generics #store_trait for #name #ty_generics #where_clause {
+            fn store(
+                self,
+                ctx: &mut #m::cells::ctx::OCtx<#f, #h>,
+                chip: &#c,
+                layouter: &mut impl #m::cells::ctx::LayoutAdaptor<#f, #h, Adaptee = #l>,
+                injected_ir: &mut #m::circuit::injected::InjectedIR<#h::RegionIndex, #h::Expression>,
+            ) -> Result<(), #h::Error> {
+                #( #fields )*
+                Ok(())
+            }
+        }
+    })
+}
+
+/// How a field is loaded from and stored into cells.
+enum FieldRepr {
+    /// With the implementations of the type of the field.
+    Cells,
+    /// Loaded from the next constant of the harness. Takes no cells and is not
+    /// stored.
+    Constant,
+    /// With the implementations of the given type, which converts from and
+    /// into the type of the field.
+    With(Type),
+}
+
+impl FieldRepr {
+    fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut repr = FieldRepr::Cells;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("cells")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("constant") {
+                    repr = FieldRepr::Constant;
+                    Ok(())
+                } else if meta.path.is_ident("with") {
+                    repr = FieldRepr::With(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `constant` or `with = <type>`"))
+                }
+            })?;
+        }
+        Ok(repr)
+    }
+}
+
+/// The parts of the input the three derive macros share.
+struct CellsInput {
+    name: Ident,
+    generics: Generics,
+    module: Ident,
+    fields: Fields,
+    reprs: Vec<FieldRepr>,
+}
+
+impl CellsInput {
+    fn parse(input: DeriveInput) -> syn::Result<Self> {
+        let span = input.span();
+        let module: Ident = input
+            .attrs
+            .iter()
+            .find_map(|attr| attr.path().is_ident("support_module").then(|| attr.parse_args()))
+            .unwrap_or(Ok(format_ident!("extractor_support")))?;
+        let Data::Struct(data) = input.data else {
+            return Err(syn::Error::new(
+                span,
+                "Only structs can be loaded from and stored into cells",
+            ));
+        };
+        let reprs = data
+            .fields
+            .iter()
+            .map(|field| FieldRepr::from_attrs(&field.attrs))
+            .collect::<syn::Result<_>>()?;
+        Ok(Self {
+            name: input.ident,
+            generics: input.generics,
+            module,
+            fields: data.fields,
+            reprs,
+        })
+    }
+
+    /// Generics of the type extended with the parameters of the load and store
+    /// traits, and with the bounds on the types of the fields.
+    fn trait_generics(
+        &self,
+        [f, c, h, l]: [&Ident; 4],
+        cells_trait: &TokenStream,
+    ) -> syn::Result<Generics> {
+        let m = &self.module;
+        let mut generics = self.generics.clone();
+        generics
+            .params
+            .extend([f, c, h, l].map(|ident| GenericParam::from(TypeParam::from(ident.clone()))));
+        extend_where_clause(
+            &mut generics,
+            [quote! { #f: ff::Field }, quote! { #h: #m::Halo2Types<#f> }]
+                .into_iter()
+                .chain(self.repr_types().map(|ty| quote! { #ty: #cells_trait })),
+        )?;
+        Ok(generics)
+    }
+
+    /// Types whose implementations are used for the fields that take cells, in
+    /// the order of the fields.
+    fn repr_types(&self) -> impl Iterator<Item = &Type> {
+        self.fields.iter().zip(&self.reprs).filter_map(|(field, repr)| match repr {
+            FieldRepr::Cells => Some(&field.ty),
+            FieldRepr::Constant => None,
+            FieldRepr::With(ty) => Some(ty),
+        })
+    }
+}
+
+/// Name of the field, or its index if it belongs to a tuple struct.
+fn member(ident: Option<&Ident>, idx: usize) -> TokenStream {
+    match ident {
+        Some(ident) => ident.to_token_stream(),
+        None => Index::from(idx).to_token_stream(),
+    }
+}
+
+fn extend_where_clause(
+    generics: &mut Generics,
+    predicates: impl IntoIterator<Item = TokenStream>,
+) -> syn::Result<()> {
+    let where_clause = generics.make_where_clause();
+    for predicate in predicates {
+        where_clause.predicates.push(syn::parse2::<WherePredicate>(predicate)?);
+    }
+    Ok(())
+}
+
+/// Returns identifiers for the parameters of the traits that don't collide
+/// with the parameters of the type.
+fn fresh_idents<const N: usize>(generics: &Generics, bases: [&str; N]) -> [Ident; N] {
+    let taken = generics
+        .type_params()
+        .map(|t| t.ident.to_string())
+        .chain(generics.const_params().map(|c| c.ident.to_string()))
+        .collect::<HashSet<_>>();
+    bases.map(|base| {
+        (0..)
+            .map(|n| match n {
+                0 => format_ident!("{base}"),
+                n => format_ident!("{base}{n}"),
+            })
+            .find(|ident| !taken.contains(&ident.to_string()))
+            .unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    type DeriveImpl = fn(DeriveInput) -> syn::Result<TokenStream>;
+
+    macro_rules! unparse {
+        ($ts:expr) => {
+            prettyplease::unparse(&syn::parse2($ts).unwrap())
+        };
+    }
+
+    #[rstest]
+    #[case::size(
+        derive_cell_repr_size_impl,
+        r"
+        struct S<F> { a: AssignedNative<F>, #[cells(constant)] n: usize, #[cells(with = [AssignedBit<F>; 2])] b: Bits<F> }
+        ",
+        r"
+        impl<F> extractor_support::cells::CellReprSize for S<F>
+        where
+            AssignedNative<F>: extractor_support::cells::CellReprSize,
+            [AssignedBit<F>; 2]: extractor_support::cells::CellReprSize,
+        {
+            const SIZE: usize = 0
+                + <AssignedNative<F> as extractor_support::cells::CellReprSize>::SIZE
+                + <[AssignedBit<F>; 2] as extractor_support::cells::CellReprSize>::SIZE;
+        }
+        "
+    )]
+    #[case::size_unit(
+        derive_cell_repr_size_impl,
+        "#[support_module(other_module)] struct S;",
+        r"
+        impl other_module::cells::CellReprSize for S {
+            const SIZE: usize = 0;
+        }
+        "
+    )]
+    #[case::load(
+        derive_load_from_cells_impl,
+        r"
+        struct S<F> { a: AssignedNative<F>, #[cells(constant)] n: usize, #[cells(with = AssignedBit<F>)] b: Bit<F> }
+        ",
+        r"
+        impl<F, __F, __C, __H, __L> extractor_support::cells::load::LoadFromCells<__F, __C, __H, __L> for S<F>
+        where
+            __F: ff::Field,
+            __H: extractor_support::Halo2Types<__F>,
+            AssignedNative<F>: extractor_support::cells::load::LoadFromCells<__F, __C, __H, __L>,
+            AssignedBit<F>: extractor_support::cells::load::LoadFromCells<__F, __C, __H, __L>,
+        {
+            fn load(
+                ctx: &mut extractor_support::cells::ctx::ICtx<__F, __H>,
+                chip: &__C,
+                layouter: &mut impl extractor_support::cells::ctx::LayoutAdaptor<__F, __H, Adaptee = __L>,
+                injected_ir: &mut extractor_support::circuit::injected::InjectedIR<__H::RegionIndex, __H::Expression>,
+            ) -> Result<Self, __H::Error> {
+                Ok(Self {
+                    a: <AssignedNative<F> as extractor_support::cells::load::LoadFromCells<__F, __C, __H, __L>>::load(ctx, chip, layouter, injected_ir)?,
+                    n: ctx.primitive_constant()?,
+                    b: <AssignedBit<F> as extractor_support::cells::load::LoadFromCells<__F, __C, __H, __L>>::load(ctx, chip, layouter, injected_ir)?.into(),
+                })
+            }
+        }
+        "
+    )]
+    #[case::load_tuple_name_collision(
+        derive_load_from_cells_impl,
+        "struct S<__F>(__F, u32);",
+        r"
+        impl<__F, __F1, __C, __H, __L> extractor_support::cells::load::LoadFromCells<__F1, __C, __H, __L> for S<__F>
+        where
+            __F1: ff::Field,
+            __H: extractor_support::Halo2Types<__F1>,
+            __F: extractor_support::cells::load::LoadFromCells<__F1, __C, __H, __L>,
+            u32: extractor_support::cells::load::LoadFromCells<__F1, __C, __H, __L>,
+        {
+            fn load(
+                ctx: &mut extractor_support::cells::ctx::ICtx<__F1, __H>,
+                chip: &__C,
+                layouter: &mut impl extractor_support::cells::ctx::LayoutAdaptor<__F1, __H, Adaptee = __L>,
+                injected_ir: &mut extractor_support::circuit::injected::InjectedIR<__H::RegionIndex, __H::Expression>,
+            ) -> Result<Self, __H::Error> {
+                Ok(Self {
+                    0: <__F as extractor_support::cells::load::LoadFromCells<__F1, __C, __H, __L>>::load(ctx, chip, layouter, injected_ir)?,
+                    1: <u32 as extractor_support::cells::load::LoadFromCells<__F1, __C, __H, __L>>::load(ctx, chip, layouter, injected_ir)?,
+                })
+            }
+        }
+        "
+    )]
+    #[case::store(
+        derive_store_into_cells_impl,
+        r"
+        struct S<F>(AssignedNative<F>, #[cells(constant)] usize, #[cells(with = AssignedBit<F>)] Bit<F>);
+        ",
+        r"
+        impl<F, __F, __C, __H, __L> extractor_support::cells::store::StoreIntoCells<__F, __C, __H, __L> for S<F>
+        where
+            __F: ff::Field,
+            __H: extractor_support::Halo2Types<__F>,
+            AssignedNative<F>: extractor_support::cells::store::StoreIntoCells<__F, __C, __H, __L>,
+            AssignedBit<F>: extractor_support::cells::store::StoreIntoCells<__F, __C, __H, __L>,
+        {
+            fn store(
+                self,
+                ctx: &mut extractor_support::cells::ctx::OCtx<__F, __H>,
+                chip: &__C,
+                layouter: &mut impl extractor_support::cells::ctx::LayoutAdaptor<__F, __H, Adaptee = __L>,
+                injected_ir: &mut extractor_support::circuit::injected::InjectedIR<__H::RegionIndex, __H::Expression>,
+            ) -> Result<(), __H::Error> {
+                <AssignedNative<F> as extractor_support::cells::store::StoreIntoCells<__F, __C, __H, __L>>::store(self.0, ctx, chip, layouter, injected_ir)?;
+                <AssignedBit<F> as extractor_support::cells::store::StoreIntoCells<__F, __C, __H, __L>>::store(<AssignedBit<F>>::from(self.2), ctx, chip, layouter, injected_ir)?;
+                Ok(())
+            }
+        }
+        "
+    )]
+    #[should_panic(expected = "Only structs can be loaded from and stored into cells")]
+    #[case::enum_fail(derive_load_from_cells_impl, "enum E { A }", "")]
+    #[should_panic(expected = "expected `constant` or `with = <type>`")]
+    #[case::unknown_attr_fail(derive_store_into_cells_impl, "struct S(#[cells(skip)] u32);", "")]
+    fn derive_cells_test(#[case] derive: DeriveImpl, #[case] input: &str, #[case] expected: &str) {
+        let expected = unparse!(expected.parse().unwrap());
+        let output = derive(syn::parse_str(input).unwrap()).unwrap();
+        similar_asserts::assert_eq!(expected, unparse!(output));
+    }
+}
@@ -8,6 +8,8 @@ use syn::{parse_macro_input, DeriveInput, ItemFn};
 
 use crate::parse::group::GroupArgs;
 
+#[cfg(feature = "extractor-derive")]
+mod cells;
 mod decompose;
 #[cfg(feature = "extractor-derive")]
 mod extractor;
@@ -131,3 +133,64 @@ pub fn derive_circuit_initialization_from_scratch(input: TokenStream) -> TokenSt
     }
     .into()
 }
+
+/// Derive macro for the `CellReprSize` trait.
+///
+/// The size of the struct is the sum of the sizes of its fields. Fields
+/// annotated with `#[cells(constant)]` don't take any cells and fields
+/// annotated with `#[cells(with = T)]` take as many cells as `T`.
+#[cfg(feature = "extractor-derive")]
+#[proc_macro_derive(CellReprSize, attributes(cells, support_module))]
+pub fn derive_cell_repr_size(input: TokenStream) -> TokenStream {
+    match cells::derive_cell_repr_size_impl(parse_macro_input!(input as DeriveInput)) {
+        Ok(tok) => tok,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+/// Derive macro for the `LoadFromCells` trait.
+///
+/// Loads the fields of the struct in the order they are declared. The
+/// loading of each field can be configured with the `cells` helper attribute.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(CellReprSize, LoadFromCells)]
+/// struct Inputs<F: PrimeField> {
+///     // Loaded with the implementation of `AssignedNative<F>`.
+///     x: AssignedNative<F>,
+///     // Parsed from the next constant of the harness.
+///     #[cells(constant)]
+///     n: usize,
+///     // Loaded as an `AssignedNative<F>` and converted with `Into`.
+///     #[cells(with = AssignedNative<F>)]
+///     y: Wrapper<F>,
+/// }
+/// ```
+#[cfg(feature = "extractor-derive")]
+#[proc_macro_derive(LoadFromCells, attributes(cells, support_module))]
+pub fn derive_load_from_cells(input: TokenStream) -> TokenStream {
+    match cells::derive_load_from_cells_impl(parse_macro_input!(input as DeriveInput)) {
+        Ok(tok) => tok,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+/// Derive macro for the `StoreIntoCells` trait.
+///
+/// Stores the fields of the struct in the order they are declared. Fields
+/// annotated with `#[cells(constant)]` are not stored and fields annotated
+/// with `#[cells(with = T)]` are converted into `T` with `From` before storing
+/// them.
+#[cfg(feature = "extractor-derive")]
+#[proc_macro_derive(StoreIntoCells, attributes(cells, support_module))]
+pub fn derive_store_into_cells(input: TokenStream) -> TokenStream {
+    match cells::derive_store_into_cells_impl(parse_macro_input!(input as DeriveInput)) {
+        Ok(tok) => tok,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
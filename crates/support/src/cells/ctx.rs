@@ -186,9 +186,13 @@ impl<F: Field, H: Halo2Types<F>> OutputDescr<F, H> {
         }
     }
 
-    fn set_to_zero(&self, layouter: &mut impl LayoutAdaptor<F, H>) -> Result<(), H::Error> {
+    fn set_to_constant(
+        &self,
+        constant: F,
+        layouter: &mut impl LayoutAdaptor<F, H>,
+    ) -> Result<(), H::Error> {
         let helper_cell =
-            layouter.constrain_advice_constant(self.helper.col, self.helper.row, F::ZERO)?;
+            layouter.constrain_advice_constant(self.helper.col, self.helper.row, constant)?;
         layouter.constrain_instance(helper_cell, self.cell.col, self.cell.row)?;
         Ok(())
     }
@@ -333,7 +337,16 @@ impl<'o, F: Field, H: Halo2Types<F>> OCtx<'o, F, H> {
         &mut self,
         layouter: &mut impl LayoutAdaptor<F, H>,
     ) -> Result<(), H::Error> {
-        self.next()?.set_to_zero(layouter)
+        self.set_next_to_constant(F::ZERO, layouter)
+    }
+
+    /// Sets the next output to the given constant.
+    pub fn set_next_to_constant(
+        &mut self,
+        constant: F,
+        layouter: &mut impl LayoutAdaptor<F, H>,
+    ) -> Result<(), H::Error> {
+        self.next()?.set_to_constant(constant, layouter)
     }
 
     /// Sets the next output to the given value.
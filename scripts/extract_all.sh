@@ -14,13 +14,8 @@ function extract {
   shift 
   c=$1 
   shift
-  args=""
-  if ! printf '%s\n' "$@" | grep -qE '^--prelude(=|$)'; then
-    args="$args --prelude spread"
-  fi
   $tool \
     --debug-comments \
-    $args \
     --fail-fast \
     --type $t \
     --constants $c $@
@@ -28,7 +23,7 @@ function extract {
 
 extract native $constants 
 extract byte $constants --ignore-chips base64,automaton
-extract byte $constants automaton --prelude automaton
+extract byte $constants automaton
 extract byte $constants base64 --no-opt
 extract bit true 
 extract field $constants assignment